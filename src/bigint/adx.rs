@@ -0,0 +1,217 @@
+//! 8 digit kernels for x86_64 CPUs with BMI2 and ADX. mulx multiplies
+//! without touching the flags, and adcx/adox add with two independent carry
+//! chains (CF and OF), so the low and high halves of each row of products
+//! can be accumulated at the same time.
+
+use std::arch::asm;
+
+/// Whether this CPU has the instructions these kernels need.
+#[inline]
+pub fn is_supported() -> bool {
+    is_x86_feature_detected!("bmi2") && is_x86_feature_detected!("adx")
+}
+
+// One step of a row: c[j] += low(x * b[j]) + high(x * b[j - 1]), with the
+// carries going into the CF and OF chains respectively. Then move on to the
+// next digit with lea, which leaves the flags alone.
+macro_rules! row_step {
+    (()) => {
+        concat!(
+            "mulx {hi}, {lo}, qword ptr [{b}]\n",
+            "adcx {lo}, qword ptr [{c}]\n",
+            "adox {lo}, {prev}\n",
+            "mov qword ptr [{c}], {lo}\n",
+            "mov {prev}, {hi}\n",
+            "lea {b}, [{b} + 8]\n",
+            "lea {c}, [{c} + 8]\n",
+        )
+    };
+}
+
+// Defines a function adding x * b into the digits of c, for a fixed number
+// of digits of b (one per x). Returns the digit that carries out the top.
+macro_rules! mul_add_row {
+    ($name:ident, $($step:tt)*) => {
+        #[target_feature(enable = "bmi2,adx")]
+        unsafe fn $name(x: u64, b: *const u64, c: *mut u64) -> u64 {
+            let carry: u64;
+            asm!(
+                // Zeroing with xor also clears CF and OF
+                "xor {zero:e}, {zero:e}",
+                "xor {prev:e}, {prev:e}",
+                $( row_step!($step), )*
+                // The carry out of a row always fits in one digit
+                "adcx {prev}, {zero}",
+                "adox {prev}, {zero}",
+                inout("rdx") x => _,
+                b = inout(reg) b => _,
+                c = inout(reg) c => _,
+                zero = out(reg) _,
+                prev = out(reg) carry,
+                lo = out(reg) _,
+                hi = out(reg) _,
+                options(nostack),
+            );
+            carry
+        }
+    };
+}
+
+mul_add_row!(mul_add_row_1, ());
+mul_add_row!(mul_add_row_2, () ());
+mul_add_row!(mul_add_row_3, () () ());
+mul_add_row!(mul_add_row_4, () () () ());
+mul_add_row!(mul_add_row_5, () () () () ());
+mul_add_row!(mul_add_row_6, () () () () () ());
+mul_add_row!(mul_add_row_7, () () () () () () ());
+mul_add_row!(mul_add_row_8, () () () () () () () ());
+
+/// Full product of two U512s' digits.
+///
+/// # Safety
+/// The CPU must support BMI2 and ADX, see `is_supported`.
+#[target_feature(enable = "bmi2,adx")]
+pub unsafe fn mul_8(a: &[u64; 8], b: &[u64; 8], c: &mut [u64; 16]) {
+    *c = [0; 16];
+    for idx in 0..8 {
+        c[idx + 8] = mul_add_row_8(a[idx], b.as_ptr(), c.as_mut_ptr().add(idx));
+    }
+}
+
+/// Full square of a U512's digits.
+///
+/// # Safety
+/// The CPU must support BMI2 and ADX, see `is_supported`.
+#[target_feature(enable = "bmi2,adx")]
+pub unsafe fn square_8(a: &[u64; 8], c: &mut [u64; 16]) {
+    *c = [0; 16];
+
+    // Cross products a[i] * a[j] for i < j. Row i starts at digit 2i + 1.
+    {
+        let a_ptr = a.as_ptr();
+        let c_ptr = c.as_mut_ptr();
+        *c_ptr.add(8) = mul_add_row_7(a[0], a_ptr.add(1), c_ptr.add(1));
+        *c_ptr.add(9) = mul_add_row_6(a[1], a_ptr.add(2), c_ptr.add(3));
+        *c_ptr.add(10) = mul_add_row_5(a[2], a_ptr.add(3), c_ptr.add(5));
+        *c_ptr.add(11) = mul_add_row_4(a[3], a_ptr.add(4), c_ptr.add(7));
+        *c_ptr.add(12) = mul_add_row_3(a[4], a_ptr.add(5), c_ptr.add(9));
+        *c_ptr.add(13) = mul_add_row_2(a[5], a_ptr.add(6), c_ptr.add(11));
+        *c_ptr.add(14) = mul_add_row_1(a[6], a_ptr.add(7), c_ptr.add(13));
+    }
+
+    // Each cross product appears twice, then add the squares on the
+    // diagonal.
+    let mut top = 0;
+    for val in c.iter_mut() {
+        let next_top = *val >> 63;
+        *val = (*val << 1) | top;
+        top = next_top;
+    }
+    let mut carry = 0;
+    for idx in 0..8 {
+        let square = (a[idx] as u128) * (a[idx] as u128);
+        let low = (c[2 * idx] as u128) + (square as u64 as u128) + carry;
+        c[2 * idx] = low as u64;
+        let high = (c[2 * idx + 1] as u128) + (square >> 64) + (low >> 64);
+        c[2 * idx + 1] = high as u64;
+        carry = high >> 64;
+    }
+}
+
+/// Montgomery reduction of the full product t: out = t / 2^512 mod m.
+///
+/// # Safety
+/// The CPU must support BMI2 and ADX, see `is_supported`.
+#[target_feature(enable = "bmi2,adx")]
+pub unsafe fn mont_reduce_8(t: &mut [u64; 16], m: &[u64; 8], m_inv: u64, out: &mut [u64; 8]) {
+    // Carry waiting to go into t[idx + 8]
+    let mut top = 0u64;
+    for idx in 0..8 {
+        let u = t[idx].wrapping_mul(m_inv);
+        let carry = mul_add_row_8(u, m.as_ptr(), t.as_mut_ptr().add(idx));
+        let sum = (t[idx + 8] as u128) + (carry as u128) + (top as u128);
+        t[idx + 8] = sum as u64;
+        top = (sum >> 64) as u64;
+    }
+
+    // Subtract m once if the result is still at least m
+    let mut borrow = 0u64;
+    for idx in 0..8 {
+        let diff = (t[idx + 8] as u128)
+            .wrapping_sub(m[idx] as u128)
+            .wrapping_sub(borrow as u128);
+        out[idx] = diff as u64;
+        borrow = (diff >> 127) as u64;
+    }
+    // If that borrowed with no carry out of t to absorb it, add m back
+    let mask = 0u64.wrapping_sub(borrow & !top & 1);
+    let mut carry = 0u64;
+    for idx in 0..8 {
+        let sum = (out[idx] as u128) + ((m[idx] & mask) as u128) + (carry as u128);
+        out[idx] = sum as u64;
+        carry = (sum >> 64) as u64;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::arithmetic;
+    use rand::{thread_rng, Rng};
+
+    fn random_digits<R: Rng>(rng: &mut R) -> [u64; 8] {
+        let mut digits = [0u64; 8];
+        for d in digits.iter_mut() {
+            // Mix in all ones digits to hit the carry edge cases
+            *d = if rng.gen_weighted_bool(4) {
+                !0
+            } else {
+                rng.next_u64()
+            };
+        }
+        digits
+    }
+
+    // Ignored unless the build targets ADX and BMI2, with RUSTFLAGS="-C
+    // target-cpu=native" on a CPU that has them, so a CPU without them
+    // shows up as an ignored test rather than a pass. `--ignored` runs it
+    // anyway, and then it fails on such a CPU.
+    #[test]
+    #[cfg_attr(not(all(target_feature = "adx", target_feature = "bmi2")), ignore)]
+    fn matches_portable() {
+        assert!(is_supported(), "this CPU doesn't support ADX and BMI2");
+
+        let mut rng = thread_rng();
+        for _ in 0..1000 {
+            let a = random_digits(&mut rng);
+            let b = random_digits(&mut rng);
+
+            let (mut expected, mut actual) = ([0u64; 16], [0u64; 16]);
+            arithmetic::mul_8_portable(&a, &b, &mut expected);
+            unsafe { mul_8(&a, &b, &mut actual) };
+            assert_eq!(expected, actual);
+
+            arithmetic::square_8_portable(&a, &mut expected);
+            unsafe { square_8(&a, &mut actual) };
+            assert_eq!(expected, actual);
+
+            // Any odd modulus, with t < m * 2^512
+            let mut m = random_digits(&mut rng);
+            m[0] |= 1;
+            m[7] |= 1 << 63;
+            let mut m_inv = m[0];
+            for _ in 0..5 {
+                m_inv = m_inv.wrapping_mul(2u64.wrapping_sub(m[0].wrapping_mul(m_inv)));
+            }
+            let m_inv = m_inv.wrapping_neg();
+            let mut t = [0u64; 16];
+            arithmetic::mul_8_portable(&a, &m, &mut t);
+            let mut t_copy = t;
+
+            let (mut expected, mut actual) = ([0u64; 8], [0u64; 8]);
+            arithmetic::mont_reduce(&mut t, &m, m_inv, &mut expected);
+            unsafe { mont_reduce_8(&mut t_copy, &m, m_inv, &mut actual) };
+            assert_eq!(expected, actual);
+        }
+    }
+}
@@ -1,131 +1,501 @@
+use std::cmp;
 use std::cmp::Ordering;
-use std::u64;
 
 use rand::Rng;
 
-// Stores the result in a. a must be larger than b.
+#[cfg(all(feature = "asm", target_arch = "x86_64"))]
+use super::adx;
+
+// Stores the result in a. a must be at least as long as b.
 pub fn add(a: &mut [u64], b: &[u64]) -> bool {
     assert!(a.len() >= b.len());
 
-    let mut overflow = false;
+    let mut carry = 0;
     for (x, y) in a.iter_mut().zip(b.iter()) {
-        let digit = if overflow {
-            x.wrapping_add(*y).wrapping_add(1)
-        } else {
-            x.wrapping_add(*y)
-        };
-        overflow = digit < *x;
-        *x = digit;
+        let (sum, carry_out) = add_carry(*x, *y, carry);
+        *x = sum;
+        carry = carry_out;
     }
 
     for x in &mut a[b.len()..] {
-        if !overflow {
+        if carry == 0 {
             break;
         }
-
-        let digit = if overflow {
-            x.wrapping_add(1)
-        } else {
-            *x
-        };
-        overflow = digit < *x;
-        *x = digit;
+        let (sum, carry_out) = add_carry(*x, 0, carry);
+        *x = sum;
+        carry = carry_out;
     }
 
-    overflow
+    carry != 0
 }
 
-// a -= b
+// a -= b. a must be at least as long as b.
 pub fn sub(a: &mut [u64], b: &[u64]) -> bool {
-    assert_eq!(a.len(), b.len());
+    assert!(a.len() >= b.len());
 
-    let mut underflow = false;
+    let mut borrow = 0;
     for (x, y) in a.iter_mut().zip(b.iter()) {
-        let digit = if underflow {
-            x.wrapping_sub(*y).wrapping_sub(1)
-        } else {
-            x.wrapping_sub(*y)
-        };
-        // Digit underflowed iff result is more than the original value
-        underflow = digit > *x;
-        *x = digit;
+        let (diff, borrow_out) = sub_borrow(*x, *y, borrow);
+        *x = diff;
+        borrow = borrow_out;
+    }
+
+    for x in &mut a[b.len()..] {
+        if borrow == 0 {
+            break;
+        }
+        let (diff, borrow_out) = sub_borrow(*x, 0, borrow);
+        *x = diff;
+        borrow = borrow_out;
+    }
+
+    borrow != 0
+}
+
+// `add` that carries through every remaining digit of a instead of stopping
+// once the carry is 0, so the time doesn't depend on the values.
+pub fn add_ct(a: &mut [u64], b: &[u64]) -> bool {
+    assert!(a.len() >= b.len());
+
+    let mut carry = 0;
+    for (idx, x) in a.iter_mut().enumerate() {
+        let y = if idx < b.len() { b[idx] } else { 0 };
+        let (sum, carry_out) = add_carry(*x, y, carry);
+        *x = sum;
+        carry = carry_out;
+    }
+    carry != 0
+}
+
+// `sub` that borrows through every remaining digit of a, like `add_ct`.
+pub fn sub_ct(a: &mut [u64], b: &[u64]) -> bool {
+    assert!(a.len() >= b.len());
+
+    let mut borrow = 0;
+    for (idx, x) in a.iter_mut().enumerate() {
+        let y = if idx < b.len() { b[idx] } else { 0 };
+        let (diff, borrow_out) = sub_borrow(*x, y, borrow);
+        *x = diff;
+        borrow = borrow_out;
     }
-    underflow
+    borrow != 0
 }
 
-/// Multiplication that ignores the high bits.
+// Below this many digits, Karatsuba's extra additions cost more than the
+// multiplications it saves. From the `karatsuba_threshold` timing test, one
+// level of Karatsuba breaks even with schoolbook at about 32 digits for both
+// multiplying and squaring, and is 10-30% faster from 40 up. The 8 digit
+// U512 products use the unrolled kernels and never get near this.
+const KARATSUBA_THRESHOLD: usize = 32;
+
+/// Multiplication that keeps only the low c.len() digits of the product, so
+/// c needs a.len() + b.len() digits to hold all of it.
 pub fn mul(a: &[u64], b: &[u64], c: &mut[u64]) {
-    assert_eq!(a.len(), b.len());
+    let full = c.len() >= a.len() + b.len();
+    if full && cmp::min(a.len(), b.len()) >= KARATSUBA_THRESHOLD {
+        mul_unbalanced(a, b, c);
+    } else {
+        mul_schoolbook(a, b, c);
+    }
+}
+
+// Product scanning (Comba) multiplication: each digit of c is finished in
+// turn by summing every a[i] * b[j] with i + j equal to its index, so the
+// carries stay in a three digit accumulator instead of rippling through c.
+fn mul_schoolbook(a: &[u64], b: &[u64], c: &mut[u64]) {
+    let mut acc = Accumulator::new();
+    for (idx, val) in c.iter_mut().enumerate() {
+        // All the a indices i with a matching b index idx - i
+        let low = (idx + 1).saturating_sub(b.len());
+        let high = cmp::min(idx + 1, a.len());
+        for a_idx in low..high {
+            acc.mul_add(a[a_idx], b[idx - a_idx]);
+        }
+        *val = acc.next_digit();
+    }
+}
+
+// Full product of operands of any length, by cutting the longer one into
+// pieces the size of the shorter one and multiplying those with Karatsuba.
+fn mul_unbalanced(a: &[u64], b: &[u64], c: &mut[u64]) {
+    let (long, short) = if a.len() >= b.len() {
+        (a, b)
+    } else {
+        (b, a)
+    };
 
     for val in c.iter_mut() {
         *val = 0;
     }
 
+    let mut piece_product = vec![0; 2 * short.len()];
+    for (idx, piece) in long.chunks(short.len()).enumerate() {
+        let product = &mut piece_product[..piece.len() + short.len()];
+        if piece.len() == short.len() {
+            mul_balanced(piece, short, product);
+        } else {
+            mul(piece, short, product);
+        }
+        let offset = idx * short.len();
+        add_ct(&mut c[offset..], product);
+    }
+}
+
+// Full product of two numbers with the same number of digits.
+fn mul_balanced(a: &[u64], b: &[u64], c: &mut[u64]) {
+    if a.len() < KARATSUBA_THRESHOLD {
+        mul_schoolbook(a, b, c);
+    } else {
+        karatsuba(a, b, c);
+    }
+}
+
+// One level of Karatsuba on two numbers with the same number of digits.
+// Splitting each into high and low halves, a * b = z2 * B^2 + z1 * B + z0
+// where z1 can be found from (a0 + a1)(b0 + b1) - z0 - z2, so we only need
+// three half sized multiplications instead of four.
+fn karatsuba(a: &[u64], b: &[u64], c: &mut[u64]) {
     let len = a.len();
-    for (a_idx, a_val) in a.iter().enumerate() {
-        for (b_idx, b_val) in b.iter().enumerate() {
-            let mut ans_idx = a_idx + b_idx;
-            if ans_idx >= a.len() {
-                break;
-            }
-            let (low, high) = mul_ints(*a_val, *b_val);
-
-            // Add the low bits
-            let (val, mut overflow) = c[ans_idx].overflowing_add(low);
-            c[ans_idx] = val;
-
-            // Add the high bits
-            ans_idx += 1;
-            if ans_idx < len {
-                let old = c[ans_idx];
-                c[ans_idx] = c[ans_idx].wrapping_add(high);
-                if overflow {
-                    c[ans_idx] = c[ans_idx].wrapping_add(1);
-                }
-                overflow = c[ans_idx] < old;
-            }
+    assert_eq!(b.len(), len);
+    assert_eq!(c.len(), 2 * len);
+
+    let half = len / 2;
+    let (a0, a1) = a.split_at(half);
+    let (b0, b1) = b.split_at(half);
+    {
+        let (z0, z2) = c.split_at_mut(2 * half);
+        mul_balanced(a0, b0, z0);
+        mul_balanced(a1, b1, z2);
+    }
 
-            // Propagate overflow down
-            ans_idx += 1;
-            for idx in ans_idx..len {
-                if !overflow {
-                    break;
-                }
-                let old = c[idx];
-                c[idx] = c[idx].wrapping_add(1);
-                overflow = c[idx] < old;
-            }
+    // The high halves are at least as long as the low halves, and the sums
+    // get an extra digit for the carry.
+    let mut a_sum = a1.to_vec();
+    a_sum.push(0);
+    add_ct(&mut a_sum, a0);
+    let mut b_sum = b1.to_vec();
+    b_sum.push(0);
+    add_ct(&mut b_sum, b0);
+
+    let mut z1 = vec![0; 2 * a_sum.len()];
+    mul_balanced(&a_sum, &b_sum, &mut z1);
+    sub_ct(&mut z1, &c[..2 * half]);
+    sub_ct(&mut z1, &c[2 * half..]);
+
+    // z1 < 2^(64 * (len + 1)), so anything past the end of c is zeros.
+    let end = cmp::min(z1.len(), c.len() - half);
+    add_ct(&mut c[half..], &z1[..end]);
+}
+
+/// The full square of a, c must have 2 * a.len() digits. Each cross product
+/// a[i] * a[j] appears twice in the square, so we compute it once and
+/// double, which saves nearly half of the multiplications.
+pub fn square(a: &[u64], c: &mut[u64]) {
+    assert_eq!(c.len(), 2 * a.len());
+    if a.len() < KARATSUBA_THRESHOLD {
+        square_schoolbook(a, c);
+    } else {
+        karatsuba_square(a, c);
+    }
+}
+
+fn square_schoolbook(a: &[u64], c: &mut[u64]) {
+    let len = a.len();
+    let mut acc = Accumulator::new();
+    for (idx, val) in c.iter_mut().enumerate() {
+        // Cross products with a_idx < idx - a_idx, counted twice
+        let low = (idx + 1).saturating_sub(len);
+        for a_idx in low..idx.div_ceil(2) {
+            acc.mul_add_twice(a[a_idx], a[idx - a_idx]);
+        }
+        if idx % 2 == 0 && idx / 2 < len {
+            acc.mul_add(a[idx / 2], a[idx / 2]);
         }
+        *val = acc.next_digit();
     }
 }
 
-// Return (low bits, high bits)
-fn mul_ints(a: u64, b: u64) -> (u64, u64) {
-    let (a1, a0) = ((a >> 32), a & 0xffffffff);
-    let (b1, b0) = ((b >> 32), b & 0xffffffff);
+// Karatsuba for squaring: z1 = (a0 + a1)^2 - z0 - z2.
+fn karatsuba_square(a: &[u64], c: &mut[u64]) {
+    let len = a.len();
+    let half = len / 2;
+    let (a0, a1) = a.split_at(half);
+    {
+        let (z0, z2) = c.split_at_mut(2 * half);
+        square(a0, z0);
+        square(a1, z2);
+    }
+
+    let mut a_sum = a1.to_vec();
+    a_sum.push(0);
+    add_ct(&mut a_sum, a0);
 
-    let z0 = a0 * b0;
-    // z1 is the middle bits. The low bits in z1 are added to the
-    // high bits of z0, and the high bits in z1 are added to the low
-    // bits of z2
-    let (z1, overflow) = (a0 * b1).overflowing_add(b0 * a1);
-    let z2 = if overflow {
-        a1 * b1 + (1 << 32)
-    } else {
-        a1 * b1
-    };
+    let mut z1 = vec![0; 2 * a_sum.len()];
+    square(&a_sum, &mut z1);
+    sub_ct(&mut z1, &c[..2 * half]);
+    sub_ct(&mut z1, &c[2 * half..]);
 
-    let (low_bits, overflow) = z0.overflowing_add(z1 << 32);
-    let high_bits = if overflow {
-        z2 + (z1 >> 32) + 1
-    } else {
-        z2 + (z1 >> 32)
+    let end = cmp::min(z1.len(), c.len() - half);
+    add_ct(&mut c[half..], &z1[..end]);
+}
+
+/// Montgomery reduction: out = t / 2^(64 * len) mod m, where t has twice as
+/// many digits as m and is less than m * 2^(64 * len). t is used as scratch
+/// space. m_inv must be -m^-1 mod 2^64.
+#[inline]
+pub fn mont_reduce(t: &mut[u64], m: &[u64], m_inv: u64, out: &mut[u64]) {
+    let len = m.len();
+    assert_eq!(t.len(), 2 * len);
+    assert_eq!(out.len(), len);
+
+    // Carry waiting to go into t[idx + len]
+    let mut top = 0;
+    for idx in 0..len {
+        // Adding u * m * 2^(64 * idx) clears digit idx of t
+        let u = t[idx].wrapping_mul(m_inv);
+        let mut carry = 0;
+        for (jdx, m_val) in m.iter().enumerate() {
+            let (low, high) = mul_add(u, *m_val, t[idx + jdx], carry);
+            t[idx + jdx] = low;
+            carry = high;
+        }
+        let (sum, carry_out) = add_carry(t[idx + len], carry, top);
+        t[idx + len] = sum;
+        top = carry_out;
+    }
+
+    // The result is less than 2m, so bring it under m. If the subtraction
+    // borrows but there was a carry out of t, the carry absorbs the borrow.
+    out.clone_from_slice(&t[len..]);
+    let underflow = sub(out, m);
+    let mask = 0u64.wrapping_sub((underflow as u64) & !top & 1);
+    add_masked(out, m, mask);
+}
+
+// a += b & mask. mask should be all ones or all zeros.
+pub fn add_masked(a: &mut[u64], b: &[u64], mask: u64) -> bool {
+    let mut carry = 0;
+    for (x, y) in a.iter_mut().zip(b.iter()) {
+        let (sum, carry_out) = add_carry(*x, *y & mask, carry);
+        *x = sum;
+        carry = carry_out;
+    }
+    carry != 0
+}
+
+// a = b where mask is all ones, a is unchanged where mask is all zeros.
+pub fn select_masked(a: &mut[u64], b: &[u64], mask: u64) {
+    for (x, y) in a.iter_mut().zip(b.iter()) {
+        *x ^= (*x ^ *y) & mask;
+    }
+}
+
+// All ones if a == b, otherwise all zeros. Does not branch on the values.
+pub fn eq_mask(a: u64, b: u64) -> u64 {
+    let diff = a ^ b;
+    // The top bit of diff | -diff is set iff diff is nonzero
+    let nonzero = (diff | diff.wrapping_neg()) >> 63;
+    nonzero.wrapping_sub(1)
+}
+
+// a * b + c + d, which always fits in two digits. Returns (low bits, high bits)
+#[inline(always)]
+fn mul_add(a: u64, b: u64, c: u64, d: u64) -> (u64, u64) {
+    let wide = (a as u128) * (b as u128) + (c as u128) + (d as u128);
+    (wide as u64, (wide >> 64) as u64)
+}
+
+// a + b + carry. Returns (sum, carry out)
+#[inline(always)]
+fn add_carry(a: u64, b: u64, carry: u64) -> (u64, u64) {
+    let wide = (a as u128) + (b as u128) + (carry as u128);
+    (wide as u64, (wide >> 64) as u64)
+}
+
+// a - b - borrow. Returns (difference, borrow out)
+#[inline(always)]
+fn sub_borrow(a: u64, b: u64, borrow: u64) -> (u64, u64) {
+    let wide = (a as u128).wrapping_sub(b as u128).wrapping_sub(borrow as u128);
+    // Any borrow wraps all the way around, setting the top bit
+    (wide as u64, (wide >> 127) as u64)
+}
+
+// Three digits for summing the products that make up one digit of a product.
+// Each column has at most a few thousand products, so the top digit can't
+// overflow.
+struct Accumulator {
+    low: u64,
+    mid: u64,
+    high: u64,
+}
+
+impl Accumulator {
+    #[inline(always)]
+    fn new() -> Accumulator {
+        Accumulator { low: 0, mid: 0, high: 0 }
+    }
+
+    #[inline(always)]
+    fn mul_add(&mut self, a: u64, b: u64) {
+        let product = (a as u128) * (b as u128);
+        let (low, carry) = add_carry(self.low, product as u64, 0);
+        // The high half of a product is at most 2^64 - 2, so this can't wrap
+        let (mid, carry) = add_carry(self.mid, (product >> 64) as u64 + carry, 0);
+        self.low = low;
+        self.mid = mid;
+        self.high += carry;
+    }
+
+    // Adds 2 * a * b, with one multiplication. The doubled product is 129
+    // bits, so the bit shifted out of the top goes into the third digit.
+    #[inline(always)]
+    fn mul_add_twice(&mut self, a: u64, b: u64) {
+        let product = (a as u128) * (b as u128);
+        let top = (product >> 127) as u64;
+        let doubled = product << 1;
+        let (low, carry) = add_carry(self.low, doubled as u64, 0);
+        let (mid, carry) = add_carry(self.mid, (doubled >> 64) as u64, carry);
+        self.low = low;
+        self.mid = mid;
+        self.high += top + carry;
+    }
+
+    // Take the finished low digit, and move on to the next column.
+    #[inline(always)]
+    fn next_digit(&mut self) -> u64 {
+        let digit = self.low;
+        self.low = self.mid;
+        self.mid = self.high;
+        self.high = 0;
+        digit
+    }
+}
+
+// Unrolled Comba columns. Each line lists the (i, j) pairs with i + j = k
+// for digit k, where (i, j) adds a[i] * b[j] and (i, j) x2 adds it twice.
+macro_rules! comba_columns {
+    ($acc:ident, $a:ident, $b:ident, $c:ident;
+     $( $k:literal: $( ($i:literal, $j:literal) $($twice:ident)* )* ; )*) => {
+        $(
+            $( comba_columns!(@term $acc, $a, $b, $i, $j $($twice)*); )*
+            $c[$k] = $acc.next_digit();
+        )*
     };
-    (low_bits, high_bits)
+    (@term $acc:ident, $a:ident, $b:ident, $i:literal, $j:literal) => {
+        $acc.mul_add($a[$i], $b[$j])
+    };
+    (@term $acc:ident, $a:ident, $b:ident, $i:literal, $j:literal x2) => {
+        $acc.mul_add_twice($a[$i], $b[$j])
+    };
+}
+
+/// Full product of two U512s' digits.
+#[inline]
+pub fn mul_8(a: &[u64; 8], b: &[u64; 8], c: &mut [u64; 16]) {
+    #[cfg(all(feature = "asm", target_arch = "x86_64"))]
+    {
+        if adx::is_supported() {
+            return unsafe { adx::mul_8(a, b, c) };
+        }
+    }
+    mul_8_portable(a, b, c)
+}
+
+/// Full square of a U512's digits.
+#[inline]
+pub fn square_8(a: &[u64; 8], c: &mut [u64; 16]) {
+    #[cfg(all(feature = "asm", target_arch = "x86_64"))]
+    {
+        if adx::is_supported() {
+            return unsafe { adx::square_8(a, c) };
+        }
+    }
+    square_8_portable(a, c)
+}
+
+/// `mont_reduce` for a U512 modulus.
+#[inline]
+pub fn mont_reduce_8(t: &mut [u64; 16], m: &[u64; 8], m_inv: u64, out: &mut [u64; 8]) {
+    #[cfg(all(feature = "asm", target_arch = "x86_64"))]
+    {
+        if adx::is_supported() {
+            return unsafe { adx::mont_reduce_8(t, m, m_inv, out) };
+        }
+    }
+    mont_reduce(t, m, m_inv, out)
 }
 
+// The kernels below are plain Rust, and are used whenever the asm versions
+// are not compiled in or the CPU doesn't support them.
+
+#[inline]
+pub fn mul_8_portable(a: &[u64; 8], b: &[u64; 8], c: &mut [u64; 16]) {
+    let mut acc = Accumulator::new();
+    comba_columns!(acc, a, b, c;
+        0: (0, 0);
+        1: (0, 1) (1, 0);
+        2: (0, 2) (1, 1) (2, 0);
+        3: (0, 3) (1, 2) (2, 1) (3, 0);
+        4: (0, 4) (1, 3) (2, 2) (3, 1) (4, 0);
+        5: (0, 5) (1, 4) (2, 3) (3, 2) (4, 1) (5, 0);
+        6: (0, 6) (1, 5) (2, 4) (3, 3) (4, 2) (5, 1) (6, 0);
+        7: (0, 7) (1, 6) (2, 5) (3, 4) (4, 3) (5, 2) (6, 1) (7, 0);
+        8: (1, 7) (2, 6) (3, 5) (4, 4) (5, 3) (6, 2) (7, 1);
+        9: (2, 7) (3, 6) (4, 5) (5, 4) (6, 3) (7, 2);
+        10: (3, 7) (4, 6) (5, 5) (6, 4) (7, 3);
+        11: (4, 7) (5, 6) (6, 5) (7, 4);
+        12: (5, 7) (6, 6) (7, 5);
+        13: (6, 7) (7, 6);
+        14: (7, 7);
+    );
+    c[15] = acc.next_digit();
+}
+
+/// The low half of the product of two U512s' digits.
+#[inline]
+pub fn mul_low_8(a: &[u64; 8], b: &[u64; 8], c: &mut [u64; 8]) {
+    let mut acc = Accumulator::new();
+    comba_columns!(acc, a, b, c;
+        0: (0, 0);
+        1: (0, 1) (1, 0);
+        2: (0, 2) (1, 1) (2, 0);
+        3: (0, 3) (1, 2) (2, 1) (3, 0);
+        4: (0, 4) (1, 3) (2, 2) (3, 1) (4, 0);
+        5: (0, 5) (1, 4) (2, 3) (3, 2) (4, 1) (5, 0);
+        6: (0, 6) (1, 5) (2, 4) (3, 3) (4, 2) (5, 1) (6, 0);
+        7: (0, 7) (1, 6) (2, 5) (3, 4) (4, 3) (5, 2) (6, 1) (7, 0);
+    );
+}
+
+#[inline]
+pub fn square_8_portable(a: &[u64; 8], c: &mut [u64; 16]) {
+    let mut acc = Accumulator::new();
+    comba_columns!(acc, a, a, c;
+        0: (0, 0);
+        1: (0, 1) x2;
+        2: (0, 2) x2 (1, 1);
+        3: (0, 3) x2 (1, 2) x2;
+        4: (0, 4) x2 (1, 3) x2 (2, 2);
+        5: (0, 5) x2 (1, 4) x2 (2, 3) x2;
+        6: (0, 6) x2 (1, 5) x2 (2, 4) x2 (3, 3);
+        7: (0, 7) x2 (1, 6) x2 (2, 5) x2 (3, 4) x2;
+        8: (1, 7) x2 (2, 6) x2 (3, 5) x2 (4, 4);
+        9: (2, 7) x2 (3, 6) x2 (4, 5) x2;
+        10: (3, 7) x2 (4, 6) x2 (5, 5);
+        11: (4, 7) x2 (5, 6) x2;
+        12: (5, 7) x2 (6, 6);
+        13: (6, 7) x2;
+        14: (7, 7);
+    );
+    c[15] = acc.next_digit();
+}
+
+// Missing high digits of the shorter number count as zeros.
 pub fn cmp(a: &[u64], b: &[u64]) -> Ordering {
-    for (x, y) in a.iter().zip(b.iter()).rev() {
+    let len = cmp::max(a.len(), b.len());
+    for idx in (0..len).rev() {
+        let x = a.get(idx).unwrap_or(&0);
+        let y = b.get(idx).unwrap_or(&0);
         if x > y {
             return Ordering::Greater;
         } else if y > x {
@@ -135,9 +505,12 @@ pub fn cmp(a: &[u64], b: &[u64]) -> Ordering {
     Ordering::Equal
 }
 
-// Shorthand to save a heap allocation where we can
+// Shorthand to save a heap allocation where we can. out may be longer than a.
 fn shl_to(a: &[u64], out: &mut[u64], shift: usize) {
-    out.clone_from_slice(a);
+    out[..a.len()].clone_from_slice(a);
+    for val in &mut out[a.len()..] {
+        *val = 0;
+    }
     shl(out, shift);
 }
 
@@ -163,8 +536,8 @@ pub fn shl(a: &mut[u64], shift: usize) {
 
     // The digit on the edge is shifted normally
     a[digits_shifted] = a[0] << shift;
-    for idx in 0..digits_shifted {
-        a[idx] = 0;
+    for val in &mut a[..digits_shifted] {
+        *val = 0;
     }
 }
 
@@ -196,7 +569,7 @@ pub fn shr(a: &mut[u64], shift: usize) {
     }
 }
 
-fn get_msb_idx(a: &[u64]) -> usize {
+pub fn get_msb_idx(a: &[u64]) -> usize {
     let mut idx = 0;
     for (i, val) in a.iter().enumerate() {
         let x = (64 - val.leading_zeros()) as usize;
@@ -207,8 +580,9 @@ fn get_msb_idx(a: &[u64]) -> usize {
     idx
 }
 
+// quot and rem must be as long as a, and b must be no longer than a.
 pub fn div_rem(a: &[u64], b: &[u64], quot: &mut[u64], rem: &mut[u64]) {
-    assert_eq!(a.len(), b.len());
+    assert!(a.len() >= b.len());
     rem.clone_from_slice(a);
     let b_msb_idx = get_msb_idx(b);
 
@@ -216,9 +590,9 @@ pub fn div_rem(a: &[u64], b: &[u64], quot: &mut[u64], rem: &mut[u64]) {
         *x = 0;
     }
 
-    let mut shifted_b = b.to_vec();
+    let mut shifted_b = vec![0; a.len()];
     loop {
-        match cmp(&b, &rem) {
+        match cmp(b, rem) {
             Ordering::Equal => {
                 quot[0] |= 1;
                 for x in rem.iter_mut() {
@@ -230,22 +604,17 @@ pub fn div_rem(a: &[u64], b: &[u64], quot: &mut[u64], rem: &mut[u64]) {
             Ordering::Less => (),
         }
 
+        // Line the msb of b up with the msb of rem. b < rem, so this is a
+        // left shift, and it never pushes any bits off the top.
         let a_msb_idx = get_msb_idx(rem);
-        let mut shift_amount = if a_msb_idx > b_msb_idx {
-            a_msb_idx - b_msb_idx - 1
-        } else {
-            0
-        };
-
-        shl_to(b, &mut shifted_b, shift_amount + 1);
-        if cmp(&shifted_b, rem) != Ordering::Greater {
-            sub(rem, &shifted_b);
-            shift_amount += 1;
-        } else {
-            // We can undo like this because we know we didn't push the msb off.
+        let mut shift_amount = a_msb_idx - b_msb_idx;
+        shl_to(b, &mut shifted_b, shift_amount);
+        if cmp(&shifted_b, rem) == Ordering::Greater {
+            // b < rem, so shift_amount can't have been 0 here.
             shr(&mut shifted_b, 1);
-            sub(rem, &shifted_b);
+            shift_amount -= 1;
         }
+        sub(rem, &shifted_b);
         let num_idx = shift_amount / 64;
         quot[num_idx] |= 1 << (shift_amount % 64);
     }
@@ -280,14 +649,14 @@ pub fn rand_int_lt<R: Rng>(a: &[u64], out: &mut[u64], rng: &mut R) {
 
     let msb = get_msb_idx(a);
     // How many digits to generate
-    let digits = if msb % 64 == 0 {
+    let digits = if msb.is_multiple_of(64) {
         msb / 64
     } else {
         msb / 64 + 1
     };
 
-    for idx in digits..out.len() {
-        out[idx] = 0;
+    for val in &mut out[digits..] {
+        *val = 0;
     }
 
     loop {
@@ -325,3 +694,99 @@ pub fn rand_int_lt<R: Rng>(a: &[u64], out: &mut[u64], rng: &mut R) {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{thread_rng, Rng};
+    use std::time::Instant;
+
+    fn random_digits<R: Rng>(len: usize, rng: &mut R) -> Vec<u64> {
+        (0..len).map(|_| rng.next_u64()).collect()
+    }
+
+    fn schoolbook_product(a: &[u64], b: &[u64]) -> Vec<u64> {
+        let mut c = vec![0; a.len() + b.len()];
+        mul_schoolbook(a, b, &mut c);
+        c
+    }
+
+    #[test]
+    fn add_sub_ct() {
+        let mut rng = thread_rng();
+        // The carry and borrow run off the end of b, and then out of a
+        for a in [random_digits(5, &mut rng), vec![!0, !0, !0, 0, 0], vec![0; 5], vec![!0; 5]].iter() {
+            for b in [random_digits(2, &mut rng), vec![1, 0], vec![!0, !0]].iter() {
+                let (mut x, mut y) = (a.clone(), a.clone());
+                assert_eq!(add_ct(&mut x, b), add(&mut y, b));
+                assert_eq!(x, y);
+                let (mut x, mut y) = (a.clone(), a.clone());
+                assert_eq!(sub_ct(&mut x, b), sub(&mut y, b));
+                assert_eq!(x, y);
+            }
+        }
+    }
+
+    #[test]
+    fn karatsuba_matches_schoolbook() {
+        let mut rng = thread_rng();
+        let t = KARATSUBA_THRESHOLD;
+        for &len in [1, 2, 7, t - 1, t, t + 1, 2 * t - 1, 2 * t, 2 * t + 3, 4 * t + 5].iter() {
+            // All ones maximises the carries through the middle terms
+            for a in [random_digits(len, &mut rng), vec![!0; len]].iter() {
+                let b = random_digits(len, &mut rng);
+                let expected = schoolbook_product(a, &b);
+                let mut c = vec![0; 2 * len];
+                mul(a, &b, &mut c);
+                assert_eq!(c, expected);
+                if len > 1 {
+                    karatsuba(a, &b, &mut c);
+                    assert_eq!(c, expected);
+                    karatsuba_square(a, &mut c);
+                    assert_eq!(c, schoolbook_product(a, a));
+                }
+                square(a, &mut c);
+                assert_eq!(c, schoolbook_product(a, a));
+            }
+        }
+
+        // Pieces of the longer operand, with a short one left over
+        let (a, b) = (random_digits(3 * t + 7, &mut rng), random_digits(t + 1, &mut rng));
+        let mut c = vec![0; a.len() + b.len()];
+        mul(&a, &b, &mut c);
+        assert_eq!(c, schoolbook_product(&a, &b));
+        mul(&b, &a, &mut c);
+        assert_eq!(c, schoolbook_product(&a, &b));
+    }
+
+    // Times one level of Karatsuba against schoolbook at each size, to find
+    // where it starts paying off. Run with
+    // `cargo test --release --lib karatsuba_threshold -- --ignored --nocapture`
+    #[test]
+    #[ignore]
+    fn karatsuba_threshold() {
+        // The best of several batches, to keep noise out of the comparison
+        fn time<F: FnMut()>(mut f: F) -> f64 {
+            let iterations = 2000;
+            (0..10).map(|_| {
+                let start = Instant::now();
+                for _ in 0..iterations {
+                    f();
+                }
+                start.elapsed().as_secs_f64() * 1e9 / iterations as f64
+            }).fold(f64::INFINITY, f64::min)
+        }
+
+        let mut rng = thread_rng();
+        println!("digits   mul: schoolbook karatsuba   square: schoolbook karatsuba");
+        for len in (8..=96).step_by(8) {
+            let (a, b) = (random_digits(len, &mut rng), random_digits(len, &mut rng));
+            let mut c = vec![0; 2 * len];
+            let mul_plain = time(|| mul_schoolbook(&a, &b, &mut c));
+            let mul_split = time(|| karatsuba(&a, &b, &mut c));
+            let square_plain = time(|| square_schoolbook(&a, &mut c));
+            let square_split = time(|| karatsuba_square(&a, &mut c));
+            println!("{:>6} {:>15.0} {:>9.0} {:>18.0} {:>9.0} ns", len, mul_plain, mul_split, square_plain, square_split);
+        }
+    }
+}
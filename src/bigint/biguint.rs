@@ -0,0 +1,642 @@
+use std::cmp;
+use std::convert::TryFrom;
+use std::fmt;
+use std::ops;
+use std::sync::OnceLock;
+
+use rand::Rng;
+
+use super::arithmetic;
+use super::i512::TryFromIntError;
+use super::montgomery::{default_window, inv_u64};
+use super::U512;
+
+/// An unsigned integer of any size, for when 512 bits isn't enough. Uses the
+/// same slice arithmetic as `U512`, growing the digits as needed.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BigUint {
+    // Least significant 64 bits first. The top digit is never zero, so zero
+    // has no digits at all.
+    digits: Vec<u64>,
+}
+
+impl BigUint {
+    pub(super) fn from_digits(digits: Vec<u64>) -> BigUint {
+        let mut ans = BigUint { digits };
+        ans.normalize();
+        ans
+    }
+
+    // Drop zero digits off the top
+    fn normalize(&mut self) {
+        while self.digits.last() == Some(&0) {
+            self.digits.pop();
+        }
+    }
+
+    pub fn zero() -> BigUint {
+        BigUint { digits: Vec::new() }
+    }
+
+    pub fn from_u64(x: u64) -> BigUint {
+        BigUint::from_digits(vec![x])
+    }
+
+    pub fn from_bytes_be(mut bytes: Vec<u8>) -> BigUint {
+        bytes.reverse();
+        BigUint::from_bytes_le(bytes)
+    }
+
+    pub fn from_bytes_le(bytes: Vec<u8>) -> BigUint {
+        let mut digits = vec![0u64; bytes.len().div_ceil(8)];
+        for (digit, chunk) in bytes.chunks(8).enumerate() {
+            for (i, byte) in chunk.iter().enumerate() {
+                digits[digit] |= (*byte as u64) << (i * 8);
+            }
+        }
+        BigUint::from_digits(digits)
+    }
+
+    /// Little endian bytes, without any zero bytes at the end.
+    pub fn to_bytes_le(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(self.digits.len() * 8);
+        for digit in &self.digits {
+            for jdx in 0..8 {
+                bytes.push(((digit >> (jdx * 8)) & 0xFF) as u8);
+            }
+        }
+        while bytes.last() == Some(&0) {
+            bytes.pop();
+        }
+        bytes
+    }
+
+    /// Big endian bytes, without any leading zero bytes.
+    pub fn to_bytes_be(&self) -> Vec<u8> {
+        let mut bytes = self.to_bytes_le();
+        bytes.reverse();
+        bytes
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.digits.is_empty()
+    }
+
+    pub fn is_even(&self) -> bool {
+        self.digits.first().is_none_or(|d| d & 1 == 0)
+    }
+
+    /// Number of bits needed to represent self.
+    pub fn bits(&self) -> usize {
+        arithmetic::get_msb_idx(&self.digits)
+    }
+
+    /// Bit idx of self, counting from the least significant bit. Bits past
+    /// the top digit are zero.
+    pub fn bit(&self, idx: usize) -> bool {
+        self.digits.get(idx / 64).is_some_and(|d| (d >> (idx % 64)) & 1 == 1)
+    }
+
+    /// self * self, which is cheaper than a general multiplication.
+    pub fn square(&self) -> BigUint {
+        let mut digits = vec![0; 2 * self.digits.len()];
+        arithmetic::square(&self.digits, &mut digits);
+        BigUint::from_digits(digits)
+    }
+
+    /// A uniformly random number in [low, high).
+    pub fn random_in_range<R: Rng>(low: &BigUint, high: &BigUint, rng: &mut R) -> BigUint {
+        assert!(low < high);
+        let range = high - low;
+        let mut digits = vec![0; range.digits.len()];
+        arithmetic::rand_int_lt(&range.digits, &mut digits, rng);
+        &BigUint::from_digits(digits) + low
+    }
+
+    /// The inverse of self mod modulus, or None if they are not coprime.
+    /// The same extended Euclidean algorithm as `U512::inv_mod`, with the
+    /// coefficients kept reduced mod modulus. Not constant time.
+    pub fn inv_mod(&self, modulus: &BigUint) -> Option<BigUint> {
+        assert!(!modulus.is_zero());
+        // Invariant: r0 = t0 * self and r1 = t1 * self (mod modulus)
+        let (mut r0, mut r1) = (modulus.clone(), self % modulus);
+        let (mut t0, mut t1) = (BigUint::zero(), &BigUint::from_u64(1) % modulus);
+        while !r1.is_zero() {
+            let (quot, rem) = r0.div_rem(&r1);
+            // t0 - quot * t1, adding modulus back if that goes negative
+            let sub = &(&quot * &t1) % modulus;
+            let t2 = if t0 >= sub { &t0 - &sub } else { &(&t0 + modulus) - &sub };
+            r0 = r1;
+            r1 = rem;
+            t0 = t1;
+            t1 = t2;
+        }
+
+        if r0 == BigUint::from_u64(1) {
+            Some(t0)
+        } else {
+            None
+        }
+    }
+
+    /// self^exp mod modulus. The running time depends on exp, so use
+    /// `pow_mod_ct` for secret exponents.
+    pub fn pow_mod(&self, exp: &BigUint, modulus: &BigUint) -> BigUint {
+        assert!(!modulus.is_zero());
+        if modulus.is_even() {
+            return self.pow_mod_even(exp, modulus);
+        }
+        let mont = WideMontgomery::new(modulus);
+        BigUint::from_digits(mont.out_of_mont(&mont.pow(&mont.to_mont(self), exp)))
+    }
+
+    /// self^exp mod modulus in time independent of exp, which can't have
+    /// more digits than modulus. modulus must be odd.
+    pub fn pow_mod_ct(&self, exp: &BigUint, modulus: &BigUint) -> BigUint {
+        assert!(exp.digits.len() <= modulus.digits.len(), "exponent is longer than the modulus");
+        let mont = WideMontgomery::new(modulus);
+        BigUint::from_digits(mont.out_of_mont(&mont.pow_ct(&mont.to_mont(self), exp)))
+    }
+
+    /// Whether self is probably prime: trial division by small primes, then
+    /// Miller-Rabin with the given number of random bases as in FIPS 186-4
+    /// appendix C.3.1. A composite passes with probability at most
+    /// 4^-rounds. Not constant time.
+    pub fn is_probable_prime<R: Rng>(&self, rounds: usize, rng: &mut R) -> bool {
+        let one = BigUint::from_u64(1);
+        let two = BigUint::from_u64(2);
+        if *self < two {
+            return false;
+        }
+        for &prime in small_primes() {
+            if self.rem_u64(prime) == 0 {
+                return self.digits == [prime];
+            }
+        }
+
+        // self - 1 = 2^a * m with m odd
+        let minus_one = self - &one;
+        let a = (1..).find(|&idx| minus_one.bit(idx)).unwrap();
+        let m = minus_one.clone() >> a;
+        'rounds: for _ in 0..rounds {
+            let b = BigUint::random_in_range(&two, &minus_one, rng);
+            let mut z = b.pow_mod(&m, self);
+            if z == one || z == minus_one {
+                continue;
+            }
+            for _ in 1..a {
+                z = &z.square() % self;
+                if z == minus_one {
+                    continue 'rounds;
+                }
+                if z == one {
+                    return false;
+                }
+            }
+            return false;
+        }
+        true
+    }
+
+    // self mod a single digit d
+    fn rem_u64(&self, d: u64) -> u64 {
+        self.digits.iter().rev().fold(0, |rem, &digit| {
+            ((((rem as u128) << 64) | digit as u128) % d as u128) as u64
+        })
+    }
+
+    // Montgomery form needs an odd modulus, so fall back to square and
+    // multiply with a full reduction each step.
+    fn pow_mod_even(&self, exp: &BigUint, modulus: &BigUint) -> BigUint {
+        let base = self % modulus;
+        let mut ans = &BigUint::from_u64(1) % modulus;
+        for idx in (0..exp.bits()).rev() {
+            ans = &ans.square() % modulus;
+            if exp.bit(idx) {
+                ans = &(&ans * &base) % modulus;
+            }
+        }
+        ans
+    }
+
+    // The digits, with zeros added on top to make len of them
+    pub(super) fn padded(&self, len: usize) -> Vec<u64> {
+        let mut digits = self.digits.clone();
+        digits.resize(len, 0);
+        digits
+    }
+
+    fn full_mul(&self, rhs: &BigUint) -> BigUint {
+        let mut digits = vec![0; self.digits.len() + rhs.digits.len()];
+        arithmetic::mul(&self.digits, &rhs.digits, &mut digits);
+        BigUint::from_digits(digits)
+    }
+
+    fn div_rem(&self, rhs: &BigUint) -> (BigUint, BigUint) {
+        assert!(!rhs.is_zero(), "attempt to divide by zero");
+        if *self < *rhs {
+            return (BigUint::zero(), self.clone());
+        }
+        let mut quot = vec![0; self.digits.len()];
+        let mut rem = vec![0; self.digits.len()];
+        arithmetic::div_rem(&self.digits, &rhs.digits, &mut quot, &mut rem);
+        (BigUint::from_digits(quot), BigUint::from_digits(rem))
+    }
+}
+
+impl From<u64> for BigUint {
+    fn from(x: u64) -> BigUint {
+        BigUint::from_u64(x)
+    }
+}
+
+impl From<U512> for BigUint {
+    fn from(x: U512) -> BigUint {
+        BigUint::from_digits(x.digits.to_vec())
+    }
+}
+
+impl TryFrom<BigUint> for U512 {
+    type Error = TryFromIntError;
+    fn try_from(x: BigUint) -> Result<U512, TryFromIntError> {
+        if x.digits.len() > 8 {
+            return Err(TryFromIntError);
+        }
+        let mut ans = U512::zero();
+        ans.digits[..x.digits.len()].clone_from_slice(&x.digits);
+        Ok(ans)
+    }
+}
+
+impl<'a> ops::Add<&'a BigUint> for &'a BigUint {
+    type Output = BigUint;
+    fn add(self, rhs: &BigUint) -> BigUint {
+        let (long, short) = if self.digits.len() >= rhs.digits.len() {
+            (self, rhs)
+        } else {
+            (rhs, self)
+        };
+        // One extra digit for the carry
+        let mut digits = long.digits.clone();
+        digits.push(0);
+        arithmetic::add(&mut digits, &short.digits);
+        BigUint::from_digits(digits)
+    }
+}
+
+impl ops::Add for BigUint {
+    type Output = BigUint;
+    fn add(self, rhs: BigUint) -> BigUint {
+        &self + &rhs
+    }
+}
+
+impl<'a> ops::Sub<&'a BigUint> for &'a BigUint {
+    type Output = BigUint;
+    fn sub(self, rhs: &BigUint) -> BigUint {
+        assert!(*self >= *rhs, "attempt to subtract with overflow");
+        let mut digits = self.digits.clone();
+        arithmetic::sub(&mut digits, &rhs.digits);
+        BigUint::from_digits(digits)
+    }
+}
+
+impl ops::Sub for BigUint {
+    type Output = BigUint;
+    fn sub(self, rhs: BigUint) -> BigUint {
+        &self - &rhs
+    }
+}
+
+impl<'a> ops::Mul<&'a BigUint> for &'a BigUint {
+    type Output = BigUint;
+    fn mul(self, rhs: &BigUint) -> BigUint {
+        self.full_mul(rhs)
+    }
+}
+
+impl ops::Mul for BigUint {
+    type Output = BigUint;
+    fn mul(self, rhs: BigUint) -> BigUint {
+        &self * &rhs
+    }
+}
+
+impl<'a> ops::Div<&'a BigUint> for &'a BigUint {
+    type Output = BigUint;
+    fn div(self, rhs: &BigUint) -> BigUint {
+        self.div_rem(rhs).0
+    }
+}
+
+impl ops::Div for BigUint {
+    type Output = BigUint;
+    fn div(self, rhs: BigUint) -> BigUint {
+        self.div_rem(&rhs).0
+    }
+}
+
+impl<'a> ops::Rem<&'a BigUint> for &'a BigUint {
+    type Output = BigUint;
+    fn rem(self, rhs: &BigUint) -> BigUint {
+        self.div_rem(rhs).1
+    }
+}
+
+impl ops::Rem for BigUint {
+    type Output = BigUint;
+    fn rem(self, rhs: BigUint) -> BigUint {
+        self.div_rem(&rhs).1
+    }
+}
+
+impl ops::Shl<usize> for BigUint {
+    type Output = BigUint;
+    fn shl(mut self, rhs: usize) -> BigUint {
+        if self.is_zero() {
+            return self;
+        }
+        // Make room for everything that gets shifted up
+        let new_len = self.digits.len() + rhs / 64 + 1;
+        self.digits.resize(new_len, 0);
+        arithmetic::shl(&mut self.digits, rhs);
+        self.normalize();
+        self
+    }
+}
+
+impl ops::Shr<usize> for BigUint {
+    type Output = BigUint;
+    fn shr(mut self, rhs: usize) -> BigUint {
+        if rhs >= self.bits() {
+            return BigUint::zero();
+        }
+        arithmetic::shr(&mut self.digits, rhs);
+        self.normalize();
+        self
+    }
+}
+
+impl fmt::Display for BigUint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.digits.split_last() {
+            Some((top, rest)) => {
+                write!(f, "{:x}", top)?;
+                for d in rest.iter().rev() {
+                    write!(f, "{:0>16x}", d)?;
+                }
+                Ok(())
+            },
+            None => write!(f, "0"),
+        }
+    }
+}
+
+impl cmp::Ord for BigUint {
+    fn cmp(&self, other: &BigUint) -> cmp::Ordering {
+        arithmetic::cmp(&self.digits, &other.digits)
+    }
+}
+
+impl cmp::PartialOrd for BigUint {
+    fn partial_cmp(&self, other: &BigUint) -> Option<cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+// The primes below 2000, for trial division
+fn small_primes() -> &'static [u64] {
+    static PRIMES: OnceLock<Vec<u64>> = OnceLock::new();
+    PRIMES.get_or_init(|| {
+        (2..2000).filter(|&n| (2..n).take_while(|d| d * d <= n).all(|d| n % d != 0)).collect()
+    })
+}
+
+// Montgomery multiplication like `Montgomery`, for an odd modulus of any
+// length. For a modulus of len digits R is 2^(64 * len), and values are
+// always exactly len digits.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(super) struct WideMontgomery {
+    modulus: BigUint,
+    // -modulus^-1 mod 2^64
+    m_inv: u64,
+    // R^2 mod modulus, used to convert into Montgomery form
+    r2: Vec<u64>,
+    // R^3 mod modulus, for converting without dividing in `to_mont_ct`
+    r3: Vec<u64>,
+    // R mod modulus, which is 1 in Montgomery form
+    one: Vec<u64>,
+}
+
+impl WideMontgomery {
+    pub(super) fn new(modulus: &BigUint) -> WideMontgomery {
+        assert!(!modulus.is_even(), "Montgomery modulus must be odd");
+        let len = modulus.digits.len();
+        let one = (BigUint::from_u64(1) << (64 * len)) % modulus.clone();
+        let r2 = &one.square() % modulus;
+        let mut mont = WideMontgomery {
+            modulus: modulus.clone(),
+            m_inv: inv_u64(modulus.digits[0]).wrapping_neg(),
+            r2: r2.padded(len),
+            r3: Vec::new(),
+            one: one.padded(len),
+        };
+        mont.r3 = mont.square(&mont.r2);
+        mont
+    }
+
+    pub(super) fn modulus(&self) -> &BigUint {
+        &self.modulus
+    }
+
+    pub(super) fn len(&self) -> usize {
+        self.modulus.digits.len()
+    }
+
+    pub(super) fn to_mont(&self, x: &BigUint) -> Vec<u64> {
+        let x = x % &self.modulus;
+        self.mul(&x.padded(self.len()), &self.r2)
+    }
+
+    // to_mont in constant time, for x of at most 2 * len digits and less
+    // than modulus * R. Montgomery reduction takes x to x / R, and then
+    // multiplying by R^3 gives x * R, without dividing by the modulus.
+    pub(super) fn to_mont_ct(&self, x: &[u64]) -> Vec<u64> {
+        assert!(x.len() <= 2 * self.len());
+        let mut wide = x.to_vec();
+        wide.resize(2 * self.len(), 0);
+        let x = self.reduce(&mut wide);
+        self.mul(&x, &self.r3)
+    }
+
+    // Out of Montgomery form, as exactly len digits
+    pub(super) fn out_of_mont(&self, x: &[u64]) -> Vec<u64> {
+        let mut one = vec![0; self.len()];
+        one[0] = 1;
+        self.mul(x, &one)
+    }
+
+    pub(super) fn mul(&self, a: &[u64], b: &[u64]) -> Vec<u64> {
+        let mut wide = vec![0; 2 * self.len()];
+        arithmetic::mul(a, b, &mut wide);
+        self.reduce(&mut wide)
+    }
+
+    // a - b mod modulus for a and b less than it, in constant time
+    pub(super) fn sub(&self, a: &[u64], b: &[u64]) -> Vec<u64> {
+        let mut ans = a.to_vec();
+        let borrow = arithmetic::sub_ct(&mut ans, b);
+        arithmetic::add_masked(&mut ans, &self.modulus.digits, 0u64.wrapping_sub(borrow as u64));
+        ans
+    }
+
+    fn square(&self, a: &[u64]) -> Vec<u64> {
+        let mut wide = vec![0; 2 * self.len()];
+        arithmetic::square(a, &mut wide);
+        self.reduce(&mut wide)
+    }
+
+    fn reduce(&self, wide: &mut [u64]) -> Vec<u64> {
+        let mut ans = vec![0; self.len()];
+        arithmetic::mont_reduce(wide, &self.modulus.digits, self.m_inv, &mut ans);
+        ans
+    }
+
+    // The same sliding window as `Montgomery::pow_window`, with the window
+    // sized for the exponent. Not constant time.
+    pub(super) fn pow(&self, x: &[u64], exp: &BigUint) -> Vec<u64> {
+        let window = default_window(exp.bits());
+
+        // table[i] = x^(2i + 1)
+        let x2 = self.square(x);
+        let mut table = vec![x.to_vec()];
+        for idx in 1..1 << (window - 1) {
+            let next = self.mul(&table[idx - 1], &x2);
+            table.push(next);
+        }
+
+        let mut ans = self.one.clone();
+        let mut idx = exp.bits();
+        while idx > 0 {
+            if !exp.bit(idx - 1) {
+                ans = self.square(&ans);
+                idx -= 1;
+                continue;
+            }
+
+            let mut low = idx.saturating_sub(window);
+            while !exp.bit(low) {
+                low += 1;
+            }
+
+            let mut digit = 0;
+            for bit_idx in (low..idx).rev() {
+                ans = self.square(&ans);
+                digit = (digit << 1) | exp.bit(bit_idx) as usize;
+            }
+            ans = self.mul(&ans, &table[digit >> 1]);
+            idx = low;
+        }
+        ans
+    }
+
+    // The same fixed 4 bit window as `Montgomery::pow_ct`, over as many bits
+    // as the modulus has digits, reading the whole table every time.
+    pub(super) fn pow_ct(&self, x: &[u64], exp: &BigUint) -> Vec<u64> {
+        const WINDOW: usize = 4;
+
+        // table[i] = x^i
+        let mut table = vec![self.one.clone()];
+        for idx in 1..1 << WINDOW {
+            let next = self.mul(&table[idx - 1], x);
+            table.push(next);
+        }
+
+        let exp = exp.padded(self.len());
+        let mut ans = self.one.clone();
+        for window_idx in (0..64 * self.len() / WINDOW).rev() {
+            for _ in 0..WINDOW {
+                ans = self.square(&ans);
+            }
+
+            let bit_idx = window_idx * WINDOW;
+            let digit = (exp[bit_idx / 64] >> (bit_idx % 64)) & ((1 << WINDOW) - 1);
+            let mut entry = vec![0; self.len()];
+            for (idx, val) in table.iter().enumerate() {
+                let mask = arithmetic::eq_mask(idx as u64, digit);
+                arithmetic::select_masked(&mut entry, val, mask);
+            }
+            ans = self.mul(&ans, &entry);
+        }
+        ans
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::thread_rng;
+
+    #[test]
+    fn u512_round_trip() {
+        let x = U512::from_hex_be(b"ffffffff00000001000000000000000000000000ffffffffffffffffffffffff");
+        assert_eq!(U512::try_from(BigUint::from(x)), Ok(x));
+        assert_eq!(BigUint::from(U512::zero()), BigUint::zero());
+
+        let too_big = BigUint::from(!U512::zero()) + BigUint::from_u64(1);
+        assert_eq!(too_big.bits(), 513);
+        assert_eq!(U512::try_from(too_big), Err(TryFromIntError));
+    }
+
+    #[test]
+    fn normalized() {
+        let x = BigUint::from_bytes_be(vec![0, 0, 0, 0, 0, 0, 0, 0, 0, 1]);
+        assert_eq!(x, BigUint::from_u64(1));
+        assert_eq!(x.to_bytes_be(), vec![1]);
+        assert_eq!(&x - &x, BigUint::zero());
+        assert_eq!((x.clone() << 1000) >> 1000, x);
+        assert_eq!(x.to_string(), "1");
+    }
+
+    #[test]
+    fn pow_mod_matches_u512() {
+        let x = U512::from_hex_be(b"123456789abcdef0fedcba9876543210");
+        let e = U512::from_hex_be(b"f0e1d2c3b4a5968778695a4b3c2d1e0f1f2e3d4c");
+        for &m in [U512::from_u64(1000003), U512::from_u64(1 << 40), (U512::from_u64(1) << 511) - U512::from_u64(187)].iter() {
+            let ans = BigUint::from(x.pow_mod(e, m));
+            let (x, e, m) = (BigUint::from(x), BigUint::from(e), BigUint::from(m));
+            assert_eq!(x.pow_mod(&e, &m), ans);
+            if !m.is_even() && e.bits() <= m.bits() {
+                assert_eq!(x.pow_mod_ct(&e, &m), ans);
+            }
+        }
+        assert_eq!(BigUint::from_u64(5).pow_mod(&BigUint::zero(), &BigUint::from_u64(7)), BigUint::from_u64(1));
+    }
+
+    #[test]
+    fn primality() {
+        let mut rng = thread_rng();
+        let one = BigUint::from_u64(1);
+        for &n in [2, 3, 1999, 2003, 1000003, 0xffffffffffffffc5].iter() {
+            assert!(BigUint::from_u64(n).is_probable_prime(20, &mut rng));
+        }
+        // 2047 and 3215031751 are strong pseudoprimes to small bases, and
+        // 41041 and 825265 are Carmichael numbers
+        for &n in [0, 1, 4, 1999 * 2003, 2047, 41041, 825265, 3215031751].iter() {
+            assert!(!BigUint::from_u64(n).is_probable_prime(20, &mut rng));
+        }
+        // 2^521 - 1 is prime, and 2^523 - 1 isn't
+        assert!((&(one.clone() << 521) - &one).is_probable_prime(20, &mut rng));
+        assert!(!(&(one.clone() << 523) - &one).is_probable_prime(20, &mut rng));
+    }
+
+    #[test]
+    fn inverse() {
+        let m = &(BigUint::from_u64(1) << 1279) - &BigUint::from_u64(1);
+        let x = BigUint::from_bytes_be(vec![0xab; 100]);
+        let inv = x.inv_mod(&m).unwrap();
+        assert_eq!(&(&x * &inv) % &m, BigUint::from_u64(1));
+        assert_eq!(BigUint::from_u64(6).inv_mod(&BigUint::from_u64(9)), None);
+        assert_eq!(BigUint::from_u64(3).inv_mod(&BigUint::from_u64(1)), Some(BigUint::zero()));
+    }
+}
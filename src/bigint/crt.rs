@@ -0,0 +1,104 @@
+use rand::Rng;
+
+use super::arithmetic;
+use super::biguint::{BigUint, WideMontgomery};
+
+/// x^d mod n = p * q for a secret d, through the CRT, for RSA private keys.
+/// Everything that touches secrets is constant time: values are reduced by
+/// Montgomery reduction instead of division, the exponentiations use fixed
+/// windows, and the result comes out as a fixed number of digits. Each
+/// input is also blinded with a fresh random r^e, and the result is checked
+/// against the public exponent before anything is returned, so a fault in
+/// one half of the CRT can't give away a factor of n.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct Crt {
+    n: WideMontgomery,
+    p: WideMontgomery,
+    q: WideMontgomery,
+    // n, p and q as exactly as many digits as the Montgomery values
+    n_digits: Vec<u64>,
+    q_digits: Vec<u64>,
+    e: BigUint,
+    dp: BigUint,
+    dq: BigUint,
+    // q^-1 mod p
+    qinv: Vec<u64>,
+}
+
+impl Crt {
+    /// The CRT form of a key with primes p and q, e * dp = 1 mod p - 1,
+    /// e * dq = 1 mod q - 1 and qinv = q^-1 mod p. None unless p and q have
+    /// the same number of 64 bit digits, which the reductions rely on.
+    pub(crate) fn new(p: &BigUint, q: &BigUint, e: &BigUint, dp: &BigUint, dq: &BigUint, qinv: &BigUint)
+        -> Option<Crt> {
+        if p.bits().div_ceil(64) != q.bits().div_ceil(64) {
+            return None;
+        }
+        let n = WideMontgomery::new(&(p * q));
+        let (p, q) = (WideMontgomery::new(p), WideMontgomery::new(q));
+        Some(Crt {
+            n_digits: n.modulus().padded(n.len()),
+            q_digits: q.modulus().padded(q.len()),
+            qinv: qinv.padded(p.len()),
+            n,
+            p,
+            q,
+            e: e.clone(),
+            dp: dp.clone(),
+            dq: dq.clone(),
+        })
+    }
+
+    /// x^d mod n as exactly len big endian bytes, for x < n. None if the
+    /// result fails the check x = (x^d)^e mod n, which only happens if
+    /// something went wrong while computing it.
+    pub(crate) fn pow<R: Rng>(&self, x: &BigUint, len: usize, rng: &mut R) -> Option<Vec<u8>> {
+        assert!(x < self.n.modulus(), "x must be less than n");
+        assert!(8 * len >= self.n.modulus().bits(), "len is too short for the result");
+        let (n, p, q) = (&self.n, &self.p, &self.q);
+        let x = x.padded(n.len());
+
+        // Blind with r^e, so the exponentiations work on a value the caller
+        // can't choose or predict, and unblind with r^-1 at the end
+        let (r, r_inv) = self.blinding_pair(rng);
+        let blinded = n.mul(&x, &n.pow(&n.to_mont_ct(&r), &self.e));
+
+        // m1 = blinded^dp mod p and m2 = blinded^dq mod q. blinded < n, which
+        // is less than p * R and q * R since p and q are the same size.
+        let m1 = p.pow_ct(&p.to_mont_ct(&blinded), &self.dp);
+        let m2 = q.out_of_mont(&q.pow_ct(&q.to_mont_ct(&blinded), &self.dq));
+
+        // m = m2 + q * (qinv * (m1 - m2) mod p). Multiplying a Montgomery
+        // value by a plain one gives a plain result.
+        let h = p.mul(&p.sub(&m1, &p.to_mont_ct(&m2)), &self.qinv);
+        let mut m = vec![0; 2 * p.len()];
+        arithmetic::mul(&h, &self.q_digits, &mut m);
+        arithmetic::add_ct(&mut m, &m2);
+        m.truncate(n.len());
+        let m = n.mul(&m, &r_inv);
+
+        if n.pow(&n.to_mont_ct(&m), &self.e) != n.to_mont_ct(&x) {
+            return None;
+        }
+        let bytes: Vec<u8> = m.iter().rev().flat_map(|digit| digit.to_be_bytes()).collect();
+        Some(bytes[bytes.len() - len..].to_vec())
+    }
+
+    // A random r in [1, n) and r^-1 in Montgomery form. r itself never
+    // goes through the variable time inverse: that inverts r * s for
+    // another random s, and multiplies by s afterwards.
+    fn blinding_pair<R: Rng>(&self, rng: &mut R) -> (Vec<u64>, Vec<u64>) {
+        let n = &self.n;
+        loop {
+            let mut r = vec![0; n.len()];
+            let mut s = vec![0; n.len()];
+            arithmetic::rand_int_lt(&self.n_digits, &mut r, rng);
+            arithmetic::rand_int_lt(&self.n_digits, &mut s, rng);
+            let s = n.to_mont_ct(&s);
+            if let Some(rs_inv) = BigUint::from_digits(n.mul(&r, &s)).inv_mod(n.modulus()) {
+                let r_inv = n.mul(&n.to_mont_ct(&rs_inv.padded(n.len())), &s);
+                return (r, r_inv);
+            }
+        }
+    }
+}
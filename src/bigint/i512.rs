@@ -0,0 +1,389 @@
+use std::cmp;
+use std::convert::TryFrom;
+use std::error;
+use std::fmt;
+use std::ops;
+use std::str;
+
+use super::U512;
+
+/// A signed 512 bit integer in two's complement, stored in the same digits
+/// as a `U512`. Addition, subtraction and multiplication wrap exactly like
+/// the unsigned versions, so they reuse them directly.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct I512 {
+    bits: U512,
+}
+
+/// The error from parsing an `I512` from a string.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ParseIntError {
+    Empty,
+    InvalidDigit,
+    Overflow,
+}
+
+/// The error from converting between `I512` and `U512` when the value is out
+/// of range for the target type.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct TryFromIntError;
+
+impl I512 {
+    pub const fn zero() -> I512 {
+        I512 { bits: U512::zero() }
+    }
+
+    pub fn from_i64(x: i64) -> I512 {
+        let ans = I512 { bits: U512::from_u64(x.unsigned_abs()) };
+        if x < 0 {
+            -ans
+        } else {
+            ans
+        }
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.bits.is_zero()
+    }
+
+    pub fn is_negative(&self) -> bool {
+        self.bits.bit(511)
+    }
+
+    /// -1, 0 or 1 matching the sign of self.
+    pub fn signum(&self) -> I512 {
+        if self.is_negative() {
+            I512::from_i64(-1)
+        } else if self.is_zero() {
+            I512::zero()
+        } else {
+            I512::from_i64(1)
+        }
+    }
+
+    /// The absolute value. Like the primitive types, the minimum value has no
+    /// positive counterpart and wraps back to itself.
+    pub fn abs(self) -> I512 {
+        if self.is_negative() {
+            -self
+        } else {
+            self
+        }
+    }
+
+    /// The absolute value as a `U512`, which always fits.
+    pub fn unsigned_abs(self) -> U512 {
+        self.abs().bits
+    }
+
+    /// Quotient rounded so that the remainder from `rem_euclid` is never
+    /// negative.
+    pub fn div_euclid(self, rhs: I512) -> I512 {
+        self.div_rem_euclid(rhs).0
+    }
+
+    /// Remainder in [0, |rhs|).
+    pub fn rem_euclid(self, rhs: I512) -> I512 {
+        self.div_rem_euclid(rhs).1
+    }
+
+    // Truncated division, as for the primitive integer types: the quotient
+    // rounds toward zero and the remainder takes the sign of self.
+    fn div_rem(self, rhs: I512) -> (I512, I512) {
+        assert!(!rhs.is_zero(), "attempt to divide by zero");
+        let (a, b) = (self.unsigned_abs(), rhs.unsigned_abs());
+        let mut quot = I512 { bits: a / b };
+        let mut rem = I512 { bits: a % b };
+        if self.is_negative() != rhs.is_negative() {
+            quot = -quot;
+        }
+        if self.is_negative() {
+            rem = -rem;
+        }
+        (quot, rem)
+    }
+
+    fn div_rem_euclid(self, rhs: I512) -> (I512, I512) {
+        let (mut quot, mut rem) = self.div_rem(rhs);
+        if rem.is_negative() {
+            if rhs.is_negative() {
+                quot += I512::from_i64(1);
+                rem -= rhs;
+            } else {
+                quot -= I512::from_i64(1);
+                rem += rhs;
+            }
+        }
+        (quot, rem)
+    }
+}
+
+impl From<i64> for I512 {
+    fn from(x: i64) -> I512 {
+        I512::from_i64(x)
+    }
+}
+
+impl TryFrom<U512> for I512 {
+    type Error = TryFromIntError;
+    /// Fails for values of 2^511 and up, which would come out negative.
+    fn try_from(x: U512) -> Result<I512, TryFromIntError> {
+        let ans = I512 { bits: x };
+        if ans.is_negative() {
+            Err(TryFromIntError)
+        } else {
+            Ok(ans)
+        }
+    }
+}
+
+impl TryFrom<I512> for U512 {
+    type Error = TryFromIntError;
+    fn try_from(x: I512) -> Result<U512, TryFromIntError> {
+        if x.is_negative() {
+            Err(TryFromIntError)
+        } else {
+            Ok(x.bits)
+        }
+    }
+}
+
+impl ops::Neg for I512 {
+    type Output = I512;
+    fn neg(self) -> I512 {
+        I512 { bits: U512::zero() - self.bits }
+    }
+}
+
+impl ops::Add for I512 {
+    type Output = I512;
+    fn add(self, rhs: I512) -> I512 {
+        I512 { bits: self.bits + rhs.bits }
+    }
+}
+
+impl ops::AddAssign for I512 {
+    fn add_assign(&mut self, rhs: I512) {
+        self.bits += rhs.bits;
+    }
+}
+
+impl ops::Sub for I512 {
+    type Output = I512;
+    fn sub(self, rhs: I512) -> I512 {
+        I512 { bits: self.bits - rhs.bits }
+    }
+}
+
+impl ops::SubAssign for I512 {
+    fn sub_assign(&mut self, rhs: I512) {
+        self.bits -= rhs.bits;
+    }
+}
+
+impl ops::Mul for I512 {
+    type Output = I512;
+    fn mul(self, rhs: I512) -> I512 {
+        I512 { bits: self.bits * rhs.bits }
+    }
+}
+
+impl ops::MulAssign for I512 {
+    fn mul_assign(&mut self, rhs: I512) {
+        self.bits *= rhs.bits;
+    }
+}
+
+impl ops::Div for I512 {
+    type Output = I512;
+    fn div(self, rhs: I512) -> I512 {
+        self.div_rem(rhs).0
+    }
+}
+
+impl ops::DivAssign for I512 {
+    fn div_assign(&mut self, rhs: I512) {
+        *self = self.div_rem(rhs).0;
+    }
+}
+
+impl ops::Rem for I512 {
+    type Output = I512;
+    fn rem(self, rhs: I512) -> I512 {
+        self.div_rem(rhs).1
+    }
+}
+
+impl ops::RemAssign for I512 {
+    fn rem_assign(&mut self, rhs: I512) {
+        *self = self.div_rem(rhs).1;
+    }
+}
+
+impl ops::Shl<usize> for I512 {
+    type Output = I512;
+    fn shl(self, rhs: usize) -> I512 {
+        I512 { bits: self.bits << rhs }
+    }
+}
+
+impl ops::ShlAssign<usize> for I512 {
+    fn shl_assign(&mut self, rhs: usize) {
+        self.bits <<= rhs;
+    }
+}
+
+// Arithmetic shift, so negative numbers stay negative.
+impl ops::Shr<usize> for I512 {
+    type Output = I512;
+    fn shr(self, rhs: usize) -> I512 {
+        if self.is_negative() {
+            I512 { bits: !(!self.bits >> rhs) }
+        } else {
+            I512 { bits: self.bits >> rhs }
+        }
+    }
+}
+
+impl ops::ShrAssign<usize> for I512 {
+    fn shr_assign(&mut self, rhs: usize) {
+        *self = *self >> rhs;
+    }
+}
+
+// Hex, like U512, with a leading - for negative numbers.
+impl fmt::Display for I512 {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.is_negative() {
+            write!(f, "-")?;
+        }
+        write!(f, "{}", self.unsigned_abs())
+    }
+}
+
+// Parses the output of Display: hex digits with an optional sign.
+impl str::FromStr for I512 {
+    type Err = ParseIntError;
+    fn from_str(s: &str) -> Result<I512, ParseIntError> {
+        let (negative, digits) = if let Some(rest) = s.strip_prefix('-') {
+            (true, rest)
+        } else if let Some(rest) = s.strip_prefix('+') {
+            (false, rest)
+        } else {
+            (false, s)
+        };
+        if digits.is_empty() {
+            return Err(ParseIntError::Empty);
+        }
+
+        let mut magnitude = U512::zero();
+        for c in digits.chars() {
+            let digit = match c.to_digit(16) {
+                Some(d) => d,
+                None => return Err(ParseIntError::InvalidDigit),
+            };
+            if magnitude.bits() > 508 {
+                return Err(ParseIntError::Overflow);
+            }
+            magnitude = (magnitude << 4) | U512::from_u64(digit as u64);
+        }
+
+        // -2^511 is the only value whose magnitude has the top bit set
+        let ans = I512 { bits: magnitude };
+        if negative {
+            if ans.is_negative() && magnitude != U512::from_u64(1) << 511 {
+                return Err(ParseIntError::Overflow);
+            }
+            Ok(-ans)
+        } else if ans.is_negative() {
+            Err(ParseIntError::Overflow)
+        } else {
+            Ok(ans)
+        }
+    }
+}
+
+impl cmp::Ord for I512 {
+    fn cmp(&self, other: &I512) -> cmp::Ordering {
+        match (self.is_negative(), other.is_negative()) {
+            (true, false) => cmp::Ordering::Less,
+            (false, true) => cmp::Ordering::Greater,
+            // Two's complement keeps the same order within either sign
+            _ => self.bits.cmp(&other.bits),
+        }
+    }
+}
+
+impl cmp::PartialOrd for I512 {
+    fn partial_cmp(&self, other: &I512) -> Option<cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl fmt::Display for ParseIntError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let msg = match *self {
+            ParseIntError::Empty => "cannot parse integer from empty string",
+            ParseIntError::InvalidDigit => "invalid digit found in string",
+            ParseIntError::Overflow => "number too large to fit in target type",
+        };
+        write!(f, "{}", msg)
+    }
+}
+
+impl error::Error for ParseIntError {}
+
+impl fmt::Display for TryFromIntError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "out of range integral type conversion attempted")
+    }
+}
+
+impl error::Error for TryFromIntError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn signs() {
+        let x = I512::from_i64(-7);
+        assert!(x.is_negative());
+        assert_eq!(-x, I512::from_i64(7));
+        assert_eq!(x.abs(), I512::from_i64(7));
+        assert_eq!(x.signum(), I512::from_i64(-1));
+        assert_eq!(I512::zero().signum(), I512::zero());
+        assert!(x < I512::zero());
+        assert!(x > I512::from_i64(-8));
+        assert_eq!(x >> 1, I512::from_i64(-4));
+        assert_eq!(x >> 511, I512::from_i64(-1));
+    }
+
+    #[test]
+    fn conversions() {
+        let max = U512::from_u64(1) << 511;
+        assert_eq!(I512::try_from(max), Err(TryFromIntError));
+        assert_eq!(I512::try_from(max - U512::from_u64(1)).unwrap().unsigned_abs(),
+                   max - U512::from_u64(1));
+        assert_eq!(U512::try_from(I512::from_i64(-1)), Err(TryFromIntError));
+        assert_eq!(U512::try_from(I512::from_i64(5)), Ok(U512::from_u64(5)));
+    }
+
+    #[test]
+    fn parse() {
+        assert_eq!("-ff".parse(), Ok(I512::from_i64(-255)));
+        assert_eq!("+10".parse(), Ok(I512::from_i64(16)));
+        assert_eq!("".parse::<I512>(), Err(ParseIntError::Empty));
+        assert_eq!("-".parse::<I512>(), Err(ParseIntError::Empty));
+        assert_eq!("1g".parse::<I512>(), Err(ParseIntError::InvalidDigit));
+
+        let min = format!("-8{}", "0".repeat(127));
+        let min: I512 = min.parse().unwrap();
+        assert_eq!(min, -min);
+        assert_eq!(min.to_string().parse(), Ok(min));
+        let too_big = format!("8{}", "0".repeat(127));
+        assert_eq!(too_big.parse::<I512>(), Err(ParseIntError::Overflow));
+        let too_big = format!("1{}", "0".repeat(128));
+        assert_eq!(too_big.parse::<I512>(), Err(ParseIntError::Overflow));
+    }
+}
@@ -0,0 +1,65 @@
+use super::montgomery::Montgomery;
+use super::U512;
+
+/// Replace every value with its inverse mod modulus, using Montgomery's
+/// trick: invert the product of all the values once, then peel the
+/// individual inverses off with 3n multiplications.
+///
+/// Values that have no inverse (zeros, for a prime modulus) are left as they
+/// are, and their indices are returned so the rest of the batch still
+/// succeeds.
+pub fn batch_inv_mod(values: &mut [U512], modulus: U512) -> Vec<usize> {
+    if modulus.is_even() {
+        return inv_each(values, modulus);
+    }
+
+    // The values never go into Montgomery form. Each Montgomery
+    // multiplication brings in a factor of R^-1, and with the prefix
+    // products starting from R, the factors in each prefix cancel the ones
+    // in the running inverse below, so the results come out plain.
+    let mont = Montgomery::new(modulus);
+    let reduced: Vec<U512> = values.iter().map(|&x| if x >= modulus { x % modulus } else { x }).collect();
+
+    // prefix[i] is the product of the nonzero values before i
+    let mut prefix = Vec::with_capacity(values.len());
+    let mut failed = Vec::new();
+    let mut product = mont.one();
+    for (idx, x) in reduced.iter().enumerate() {
+        prefix.push(product);
+        if x.is_zero() {
+            failed.push(idx);
+        } else {
+            product = mont.mul(product, *x);
+        }
+    }
+
+    let mut inv = match product.inv_mod(modulus) {
+        Some(inv) => inv,
+        // Some nonzero value shares a factor with the modulus, so we have to
+        // find out which ones the slow way.
+        None => return inv_each(values, modulus),
+    };
+
+    // inv is the inverse of the product of everything up to idx, so
+    // multiplying by prefix[idx] leaves just the inverse of values[idx].
+    for idx in (0..values.len()).rev() {
+        let x = reduced[idx];
+        if x.is_zero() {
+            continue;
+        }
+        values[idx] = mont.mul(inv, prefix[idx]);
+        inv = mont.mul(inv, x);
+    }
+    failed
+}
+
+fn inv_each(values: &mut [U512], modulus: U512) -> Vec<usize> {
+    let mut failed = Vec::new();
+    for (idx, x) in values.iter_mut().enumerate() {
+        match x.inv_mod(modulus) {
+            Some(inv) => *x = inv,
+            None => failed.push(idx),
+        }
+    }
+    failed
+}
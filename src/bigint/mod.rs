@@ -1,4 +1,19 @@
+#[cfg(all(feature = "asm", target_arch = "x86_64"))]
+mod adx;
 mod arithmetic;
+mod biguint;
+mod crt;
+mod i512;
+mod inverse;
+mod montgomery;
+mod multi_exp;
+mod recoding;
 mod u512;
 
+pub use self::biguint::BigUint;
+pub(crate) use self::crt::Crt;
+pub use self::i512::{I512, ParseIntError, TryFromIntError};
+pub use self::inverse::batch_inv_mod;
+pub use self::montgomery::Montgomery;
+pub use self::multi_exp::multi_pow_mod;
 pub use self::u512::U512;
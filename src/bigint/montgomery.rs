@@ -0,0 +1,233 @@
+use super::arithmetic;
+use super::U512;
+
+/// Precomputed values for multiplication modulo an odd number. Values are
+/// kept in Montgomery form, where x is represented by x * 2^512 mod m, which
+/// lets us reduce products without any division.
+#[derive(Copy, Clone, Debug)]
+pub struct Montgomery {
+    modulus: U512,
+    // -modulus^-1 mod 2^64
+    m_inv: u64,
+    // 2^1024 mod modulus, used to convert into Montgomery form
+    r2: U512,
+    // 2^512 mod modulus, which is 1 in Montgomery form
+    one: U512,
+}
+
+impl Montgomery {
+    pub fn new(modulus: U512) -> Montgomery {
+        assert!(!modulus.is_even(), "Montgomery modulus must be odd");
+
+        // 2^512 - m wraps around to the same value as 2^512 mod m
+        let one = (U512::zero() - modulus) % modulus;
+        Montgomery {
+            modulus,
+            m_inv: inv_u64(modulus.digits[0]).wrapping_neg(),
+            r2: one.mul_mod(one, modulus),
+            one,
+        }
+    }
+
+    pub fn modulus(&self) -> U512 {
+        self.modulus
+    }
+
+    /// 1 in Montgomery form.
+    pub fn one(&self) -> U512 {
+        self.one
+    }
+
+    pub fn to_mont(&self, x: U512) -> U512 {
+        let x = if x >= self.modulus {
+            x % self.modulus
+        } else {
+            x
+        };
+        self.mul(x, self.r2)
+    }
+
+    pub fn from_mont(&self, x: U512) -> U512 {
+        self.mul(x, U512::from_u64(1))
+    }
+
+    /// x mod the modulus for any 512 bit x, in constant time. from_mont
+    /// divides by 2^512 and reduces, which works for any x since it is less
+    /// than m * 2^512, and to_mont multiplies back. The other order would
+    /// send x through to_mont's division whenever x >= m.
+    pub fn reduce_512(&self, x: U512) -> U512 {
+        self.to_mont(self.from_mont(x))
+    }
+
+    /// a + b for values in Montgomery form, or any values less than the
+    /// modulus. Constant time.
+    pub fn add(&self, a: U512, b: U512) -> U512 {
+        let mut ans = a;
+        let carry = arithmetic::add(&mut ans.digits, &b.digits);
+        // Take the modulus off, and put it back if that went below zero
+        let borrow = arithmetic::sub(&mut ans.digits, &self.modulus.digits);
+        let mask = 0u64.wrapping_sub((borrow & !carry) as u64);
+        arithmetic::add_masked(&mut ans.digits, &self.modulus.digits, mask);
+        ans
+    }
+
+    /// a - b for values in Montgomery form, or any values less than the
+    /// modulus. Constant time.
+    pub fn sub(&self, a: U512, b: U512) -> U512 {
+        let mut ans = a;
+        let borrow = arithmetic::sub(&mut ans.digits, &b.digits);
+        let mask = 0u64.wrapping_sub(borrow as u64);
+        arithmetic::add_masked(&mut ans.digits, &self.modulus.digits, mask);
+        ans
+    }
+
+    pub fn neg(&self, a: U512) -> U512 {
+        self.sub(U512::zero(), a)
+    }
+
+    /// Multiply two values in Montgomery form.
+    pub fn mul(&self, a: U512, b: U512) -> U512 {
+        let mut wide = [0u64; 16];
+        arithmetic::mul_8(&a.digits, &b.digits, &mut wide);
+        self.reduce(&mut wide)
+    }
+
+    pub fn square(&self, a: U512) -> U512 {
+        let mut wide = [0u64; 16];
+        arithmetic::square_8(&a.digits, &mut wide);
+        self.reduce(&mut wide)
+    }
+
+    // Reduce a full product of two values in Montgomery form
+    fn reduce(&self, wide: &mut [u64; 16]) -> U512 {
+        let mut ans = U512::zero();
+        arithmetic::mont_reduce_8(wide, &self.modulus.digits, self.m_inv, &mut ans.digits);
+        ans
+    }
+
+    /// x^exp for x in Montgomery form, using a sliding window sized for the
+    /// length of the exponent. Not constant time.
+    pub fn pow(&self, x: U512, exp: U512) -> U512 {
+        self.pow_window(x, exp, default_window(exp.bits()))
+    }
+
+    /// x^exp for x in Montgomery form, using a sliding window of the given
+    /// width (1 to 8 bits). Only odd powers of x up to 2^window are
+    /// precomputed, and runs of zero bits cost only squarings. The sequence
+    /// of operations depends on exp, so this must not be used with secret
+    /// exponents.
+    pub fn pow_window(&self, x: U512, exp: U512, window: usize) -> U512 {
+        assert!((1..=8).contains(&window), "window must be between 1 and 8 bits");
+
+        // table[i] = x^(2i + 1)
+        let x2 = self.square(x);
+        let mut table = vec![x; 1 << (window - 1)];
+        for idx in 1..table.len() {
+            table[idx] = self.mul(table[idx - 1], x2);
+        }
+
+        let mut ans = self.one;
+        let mut idx = exp.bits();
+        while idx > 0 {
+            if !exp.bit(idx - 1) {
+                ans = self.square(ans);
+                idx -= 1;
+                continue;
+            }
+
+            // Take the longest window starting at idx - 1 that ends in a 1.
+            let mut low = idx.saturating_sub(window);
+            while !exp.bit(low) {
+                low += 1;
+            }
+
+            let mut digit = 0;
+            for bit_idx in (low..idx).rev() {
+                ans = self.square(ans);
+                digit = (digit << 1) | exp.bit(bit_idx) as usize;
+            }
+            ans = self.mul(ans, table[digit >> 1]);
+            idx = low;
+        }
+        ans
+    }
+
+    /// x^exp for x in Montgomery form, for secret exponents. Uses a fixed 4
+    /// bit window over all 512 bits of exp, and reads every table entry for
+    /// each window so neither the operations nor the memory accessed depend
+    /// on exp.
+    pub fn pow_ct(&self, x: U512, exp: U512) -> U512 {
+        const WINDOW: usize = 4;
+
+        // table[i] = x^i
+        let mut table = [self.one; 1 << WINDOW];
+        for idx in 1..table.len() {
+            table[idx] = self.mul(table[idx - 1], x);
+        }
+
+        let mut ans = self.one;
+        for window_idx in (0..512 / WINDOW).rev() {
+            for _ in 0..WINDOW {
+                ans = self.square(ans);
+            }
+
+            let bit_idx = window_idx * WINDOW;
+            let digit = (exp.digits[bit_idx / 64] >> (bit_idx % 64)) & ((1 << WINDOW) - 1);
+            let mut entry = U512::zero();
+            for (idx, val) in table.iter().enumerate() {
+                let mask = arithmetic::eq_mask(idx as u64, digit);
+                arithmetic::select_masked(&mut entry.digits, &val.digits, mask);
+            }
+            ans = self.mul(ans, entry);
+        }
+        ans
+    }
+}
+
+// Window sizes that minimize the number of multiplications for a given
+// exponent length.
+pub(super) fn default_window(exp_bits: usize) -> usize {
+    if exp_bits > 239 {
+        5
+    } else if exp_bits > 79 {
+        4
+    } else if exp_bits > 23 {
+        3
+    } else {
+        1
+    }
+}
+
+// Inverse of an odd x mod 2^64 by Newton's method. x is its own inverse
+// mod 8, and each iteration doubles the number of correct bits.
+pub(super) fn inv_u64(x: u64) -> u64 {
+    let mut inv = x;
+    for _ in 0..5 {
+        inv = inv.wrapping_mul(2u64.wrapping_sub(x.wrapping_mul(inv)));
+    }
+    inv
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn inverse() {
+        for x in [1u64, 3, 0xffffffffffffffff, 0x123456789abcdef1].iter() {
+            assert_eq!(x.wrapping_mul(inv_u64(*x)), 1);
+        }
+    }
+
+    #[test]
+    fn round_trip() {
+        let m = U512::from_hex_be(b"ffffffff00000001000000000000000000000000ffffffffffffffffffffffff");
+        let mont = Montgomery::new(m);
+        let x = U512::from_u64(0xdeadbeef);
+        assert_eq!(mont.from_mont(mont.to_mont(x)), x);
+        assert_eq!(mont.from_mont(mont.one()), U512::from_u64(1));
+        for x in [x, m, m + U512::from_u64(1), !U512::zero()].iter() {
+            assert_eq!(mont.reduce_512(*x), *x % m);
+        }
+    }
+}
@@ -0,0 +1,152 @@
+use std::cmp;
+
+use super::montgomery::Montgomery;
+use super::U512;
+
+// Above this many terms the bucket method beats interleaved windows.
+const PIPPENGER_THRESHOLD: usize = 32;
+
+/// The product of base^exp over all pairs, mod modulus. This gives the same
+/// result as multiplying the individual `pow_mod`s together, but shares the
+/// squarings between all of the terms. Not constant time.
+pub fn multi_pow_mod(pairs: &[(U512, U512)], modulus: U512) -> U512 {
+    if modulus.is_even() {
+        let mut ans = U512::from_u64(1) % modulus;
+        for &(base, exp) in pairs {
+            ans = ans.mul_mod(base.pow_mod(exp, modulus), modulus);
+        }
+        return ans;
+    }
+
+    let mont = Montgomery::new(modulus);
+    let pairs: Vec<(U512, U512)> = pairs.iter()
+        .map(|&(base, exp)| (mont.to_mont(base), exp))
+        .collect();
+    let ans = if pairs.len() > PIPPENGER_THRESHOLD {
+        pippenger(&mont, &pairs)
+    } else {
+        straus(&mont, &pairs)
+    };
+    mont.from_mont(ans)
+}
+
+// Number of bits needed for the largest exponent
+fn max_bits(pairs: &[(U512, U512)]) -> usize {
+    pairs.iter().map(|&(_, exp)| exp.bits()).max().unwrap_or(0)
+}
+
+// The digit of exp made of the bits [low, low + width)
+fn digit(exp: &U512, low: usize, width: usize) -> usize {
+    let mut ans = 0;
+    for idx in (low..cmp::min(low + width, 512)).rev() {
+        ans = (ans << 1) | exp.bit(idx) as usize;
+    }
+    ans
+}
+
+// Shamir's trick with fixed 4 bit windows: each base gets its own table of
+// powers, and all exponents are scanned together so the squarings are done
+// only once.
+fn straus(mont: &Montgomery, pairs: &[(U512, U512)]) -> U512 {
+    const WINDOW: usize = 4;
+
+    // tables[i][d] = base_i^d
+    let tables: Vec<[U512; 1 << WINDOW]> = pairs.iter()
+        .map(|&(base, _)| {
+            let mut table = [mont.one(); 1 << WINDOW];
+            for idx in 1..table.len() {
+                table[idx] = mont.mul(table[idx - 1], base);
+            }
+            table
+        })
+        .collect();
+
+    let windows = max_bits(pairs).div_ceil(WINDOW);
+    let mut ans = mont.one();
+    for window_idx in (0..windows).rev() {
+        for _ in 0..WINDOW {
+            ans = mont.square(ans);
+        }
+        for (table, &(_, exp)) in tables.iter().zip(pairs.iter()) {
+            let d = digit(&exp, window_idx * WINDOW, WINDOW);
+            if d != 0 {
+                ans = mont.mul(ans, table[d]);
+            }
+        }
+    }
+    ans
+}
+
+// Pippenger's bucket method. For each window, every base is multiplied into
+// the bucket for its digit, and then the buckets are combined into
+// prod(bucket[d]^d) with running products, so no per-base tables are needed.
+fn pippenger(mont: &Montgomery, pairs: &[(U512, U512)]) -> U512 {
+    let bits = max_bits(pairs);
+    let width = pippenger_window(pairs.len(), bits);
+    let windows = bits.div_ceil(width);
+
+    let mut ans = mont.one();
+    for window_idx in (0..windows).rev() {
+        for _ in 0..width {
+            ans = mont.square(ans);
+        }
+
+        let mut buckets = vec![None; 1 << width];
+        for &(base, exp) in pairs {
+            let d = digit(&exp, window_idx * width, width);
+            if d != 0 {
+                buckets[d] = Some(match buckets[d] {
+                    Some(val) => mont.mul(val, base),
+                    None => base,
+                });
+            }
+        }
+
+        // running = prod(bucket[j] for j >= d), and the product of the
+        // running values for every d is prod(bucket[d]^d).
+        let mut running = None;
+        let mut window_ans = None;
+        for bucket in buckets[1..].iter().rev() {
+            running = match (running, *bucket) {
+                (Some(r), Some(b)) => Some(mont.mul(r, b)),
+                (r, b) => r.or(b),
+            };
+            if let Some(r) = running {
+                window_ans = Some(match window_ans {
+                    Some(w) => mont.mul(w, r),
+                    None => r,
+                });
+            }
+        }
+        if let Some(w) = window_ans {
+            ans = mont.mul(ans, w);
+        }
+    }
+    ans
+}
+
+// Each window costs about one multiplication per term plus two per bucket,
+// so pick the width that minimizes the total over all windows.
+fn pippenger_window(terms: usize, bits: usize) -> usize {
+    (1..16usize)
+        .min_by_key(|&width| bits.div_ceil(width) * (terms + (2 << width)))
+        .unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn straus_matches_pippenger() {
+        let m = U512::from_hex_be(b"ffffffff00000001000000000000000000000000ffffffffffffffffffffffff");
+        let mont = Montgomery::new(m);
+        let pairs: Vec<(U512, U512)> = (1..10u64)
+            .map(|x| (mont.to_mont(U512::from_u64(x * 0x1234567)),
+                      U512::from_u64(x.wrapping_mul(0x9e3779b97f4a7c15))))
+            .collect();
+        assert_eq!(straus(&mont, &pairs), pippenger(&mont, &pairs));
+        assert_eq!(straus(&mont, &[]), mont.one());
+        assert_eq!(pippenger(&mont, &pairs[..1]), mont.pow(pairs[0].0, pairs[0].1));
+    }
+}
@@ -0,0 +1,170 @@
+//! Signed digit representations of a `U512`, for scalar multiplication and
+//! exponentiation in groups where negating an element is cheap. Digits are
+//! stored least significant first.
+
+use super::U512;
+
+impl U512 {
+    // The window bits of self starting at bit low, as an integer. Bits past
+    // the top are zero.
+    fn window_at(&self, low: usize, window: usize) -> u32 {
+        let mut ans = 0;
+        for idx in (low..(low + window).min(512)).rev() {
+            ans = (ans << 1) | self.bit(idx) as u32;
+        }
+        ans
+    }
+
+    /// The non-adjacent form of self: digits of -1, 0 or 1 with no two
+    /// adjacent digits nonzero, where digit i is worth 2^i. This has the
+    /// fewest nonzero digits of any signed binary representation, a third of
+    /// them on average. There may be one more digit than self has bits.
+    pub fn to_naf(&self) -> Vec<i8> {
+        self.to_wnaf(2)
+    }
+
+    /// The width window non-adjacent form of self: every nonzero digit is
+    /// odd and less than 2^(window - 1) in absolute value, and any window
+    /// consecutive digits have at most one nonzero among them. Digit i is
+    /// worth 2^i. window must be between 2 and 8. Not constant time.
+    pub fn to_wnaf(&self, window: usize) -> Vec<i8> {
+        assert!((2..=8).contains(&window), "wNAF window must be between 2 and 8 bits");
+        let width = 1i32 << window;
+
+        // The value left to recode is self / 2^pos + carry, and carry is
+        // what's owed to the next window after a negative digit
+        let mut digits = vec![0i8; 513];
+        let mut pos = 0;
+        let mut carry = 0;
+        while pos < digits.len() {
+            let value = self.window_at(pos, window) as i32 + carry;
+            if value & 1 == 0 {
+                pos += 1;
+                continue;
+            }
+            if value < width / 2 {
+                carry = 0;
+                digits[pos] = value as i8;
+            } else {
+                carry = 1;
+                digits[pos] = (value - width) as i8;
+            }
+            pos += window;
+        }
+
+        while digits.last() == Some(&0) {
+            digits.pop();
+        }
+        digits
+    }
+
+    /// self in base 2^window with signed digits in [-2^(window - 1),
+    /// 2^(window - 1)), for secret scalars. Every value gives the same number
+    /// of digits, 512 / window rounded up plus one, and the recoding doesn't
+    /// branch on self. Digit i is worth 2^(window * i). window must be
+    /// between 2 and 8.
+    pub fn to_signed_window(&self, window: usize) -> Vec<i8> {
+        assert!((2..=8).contains(&window), "window must be between 2 and 8 bits");
+        let count = 512usize.div_ceil(window) + 1;
+        let half = 1i32 << (window - 1);
+
+        let mut digits = Vec::with_capacity(count);
+        let mut carry = 0;
+        for idx in 0..count {
+            // Digits of 2^(window - 1) and up borrow from the next window
+            let value = self.window_at(idx * window, window) as i32 + carry;
+            carry = (value + half) >> window;
+            digits.push((value - (carry << window)) as i8);
+        }
+        digits
+    }
+
+    /// The value of signed digits where digit i is worth 2^(digit_bits * i),
+    /// which undoes the recodings here: use 1 for `to_naf` and `to_wnaf`, and
+    /// the window for `to_signed_window`. Wraps mod 2^512 if the digits add
+    /// up to a negative number or one too big for 512 bits.
+    pub fn from_signed_digits(digits: &[i8], digit_bits: usize) -> U512 {
+        let mut ans = U512::zero();
+        for &digit in digits.iter().rev() {
+            ans <<= digit_bits;
+            let magnitude = U512::from_u64(digit.unsigned_abs() as u64);
+            if digit < 0 {
+                ans -= magnitude;
+            } else {
+                ans += magnitude;
+            }
+        }
+        ans
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{thread_rng, Rng};
+
+    use super::*;
+
+    fn samples() -> Vec<U512> {
+        let mut rng = thread_rng();
+        let mut xs = vec![U512::zero(), U512::from_u64(1), U512::from_u64(7), !U512::zero(),
+                          !U512::zero() >> 1, U512::from_u64(1) << 511];
+        for _ in 0..50 {
+            let x: U512 = rng.gen();
+            xs.push(x);
+            // Fewer bits, and long runs of ones
+            xs.push(x >> (rng.gen::<usize>() % 512));
+            xs.push(x | (x >> 1) | (x >> 2));
+        }
+        xs
+    }
+
+    #[test]
+    fn naf() {
+        for x in samples() {
+            let digits = x.to_naf();
+            assert_eq!(U512::from_signed_digits(&digits, 1), x);
+            assert!(digits.len() <= x.bits() + 1);
+            for pair in digits.windows(2) {
+                assert!(pair[0] == 0 || pair[1] == 0);
+            }
+            assert!(digits.iter().all(|&d| (-1..=1).contains(&d)));
+        }
+        assert!(U512::zero().to_naf().is_empty());
+        // 7 = 8 - 1
+        assert_eq!(U512::from_u64(7).to_naf(), vec![-1, 0, 0, 1]);
+        // 2^512 - 1 needs a 513th digit
+        assert_eq!((!U512::zero()).to_naf().len(), 513);
+    }
+
+    #[test]
+    fn wnaf() {
+        for window in 2..=8 {
+            for x in samples() {
+                let digits = x.to_wnaf(window);
+                assert_eq!(U512::from_signed_digits(&digits, 1), x);
+                for (idx, &digit) in digits.iter().enumerate() {
+                    if digit == 0 {
+                        continue;
+                    }
+                    assert!(digit % 2 != 0);
+                    assert!((digit as i32).abs() < 1 << (window - 1));
+                    let end = (idx + window).min(digits.len());
+                    assert!(digits[idx + 1..end].iter().all(|&d| d == 0));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn signed_window() {
+        for window in 2..=8 {
+            let half = 1i32 << (window - 1);
+            for x in samples() {
+                let digits = x.to_signed_window(window);
+                assert_eq!(digits.len(), 512usize.div_ceil(window) + 1);
+                assert_eq!(U512::from_signed_digits(&digits, window), x);
+                assert!(digits.iter().all(|&d| -half <= d as i32 && (d as i32) < half));
+            }
+        }
+    }
+}
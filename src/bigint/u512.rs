@@ -5,13 +5,14 @@ use std::fmt;
 use std::ops;
 
 use super::arithmetic;
+use super::montgomery::Montgomery;
 
 /// TODO: Basic operations with u64 as well.
 
 #[derive(Copy, Clone, Debug)]
 pub struct U512 {
     // These are stored with the least significant 64 bits first.
-    digits: [u64; 8],
+    pub(super) digits: [u64; 8],
 }
 
 impl U512 {
@@ -29,12 +30,32 @@ impl U512 {
         U512::from_bytes_le(bytes)
     }
 
+    /// The leftmost bits of a hash, as many as modulus has, reduced mod
+    /// modulus. This is how ECDSA and DSA turn a message hash into a scalar:
+    /// bits2int from RFC 6979 section 2.3.2 and one subtraction. Constant
+    /// time for a given hash length and modulus.
+    pub fn from_hash_be(hash: &[u8], modulus: U512) -> U512 {
+        assert!(!modulus.is_zero());
+        let bits = modulus.bits();
+        let len = hash.len().min(bits.div_ceil(8));
+        let mut ans = U512::from_bytes_be(hash[..len].to_vec());
+        if len * 8 > bits {
+            ans >>= len * 8 - bits;
+        }
+
+        // ans < 2^bits <= 2 * modulus, so it only needs subtracting once
+        let mut reduced = ans;
+        let borrow = arithmetic::sub(&mut reduced.digits, &modulus.digits);
+        ans.conditional_assign(&reduced, !borrow);
+        ans
+    }
+
     // convenient way to convert a hex literal to a U512
     // e.g. for crypto constants like for ecdh stuff
     // ffffffff00000001000000000000000000000000fffffffffffffffffffffffc
     // dont give the leading 0x!
     pub const fn from_hex_be(hex_be: &[u8]) -> U512 {
-        assert!(hex_be.len() % 2 == 0);
+        assert!(hex_be.len().is_multiple_of(2));
         assert!(hex_be.len() <= 128);
 
         // 2 hex chars to a byte
@@ -87,7 +108,7 @@ impl U512 {
         let mut digits: [u64; 8] = [0; 8];
         for (digit, chunk) in bytes.chunks(8).enumerate() {
             for (i, byte) in chunk.iter().enumerate() {
-                digits[digit] |= (*byte as u64) << i * 8;
+                digits[digit] |= (*byte as u64) << (i * 8);
             }
         }
 
@@ -118,6 +139,145 @@ impl U512 {
         ans + low
     }
 
+    /// The number of bits needed to represent self, which is 0 for 0.
+    pub fn bits(&self) -> usize {
+        arithmetic::get_msb_idx(&self.digits)
+    }
+
+    /// Bit idx of self, counting from the least significant bit. Panics if
+    /// idx is 512 or more.
+    pub fn bit(&self, idx: usize) -> bool {
+        (self.digits[idx / 64] >> (idx % 64)) & 1 == 1
+    }
+
+    // Sets self to other if choice is true, without branching on choice
+    pub(crate) fn conditional_assign(&mut self, other: &U512, choice: bool) {
+        let mask = 0u64.wrapping_sub(choice as u64);
+        arithmetic::select_masked(&mut self.digits, &other.digits, mask);
+    }
+
+    /// The full 1024 bit product, as (low bits, high bits). Use
+    /// `square_wide` to square, which is cheaper.
+    pub fn mul_wide(self, rhs: U512) -> (U512, U512) {
+        let mut product = [0u64; 16];
+        arithmetic::mul_8(&self.digits, &rhs.digits, &mut product);
+        U512::split_wide(&product)
+    }
+
+    /// The full 1024 bit square, as (low bits, high bits).
+    pub fn square_wide(self) -> (U512, U512) {
+        let mut product = [0u64; 16];
+        arithmetic::square_8(&self.digits, &mut product);
+        U512::split_wide(&product)
+    }
+
+    fn split_wide(product: &[u64; 16]) -> (U512, U512) {
+        let (mut low, mut high) = (U512::zero(), U512::zero());
+        low.digits.clone_from_slice(&product[..8]);
+        high.digits.clone_from_slice(&product[8..]);
+        (low, high)
+    }
+
+    /// (self * rhs) mod modulus, reduced from the full 1024 bit product.
+    pub fn mul_mod(self, rhs: U512, modulus: U512) -> U512 {
+        assert!(!modulus.is_zero());
+        let mut product = [0u64; 16];
+        arithmetic::mul_8(&self.digits, &rhs.digits, &mut product);
+
+        let mut rem = [0u64; 16];
+        let mut quot = [0u64; 16];
+        arithmetic::div_rem(&product, &modulus.digits, &mut quot, &mut rem);
+
+        let mut ans = U512::zero();
+        ans.digits.clone_from_slice(&rem[..8]);
+        ans
+    }
+
+    /// (self + rhs) mod modulus, for self and rhs already less than modulus.
+    pub fn add_mod(self, rhs: U512, modulus: U512) -> U512 {
+        let mut ans = self;
+        let overflow = arithmetic::add(&mut ans.digits, &rhs.digits);
+        if overflow || ans >= modulus {
+            arithmetic::sub(&mut ans.digits, &modulus.digits);
+        }
+        ans
+    }
+
+    /// (self - rhs) mod modulus, for self and rhs already less than modulus.
+    pub fn sub_mod(self, rhs: U512, modulus: U512) -> U512 {
+        let mut ans = self;
+        if arithmetic::sub(&mut ans.digits, &rhs.digits) {
+            arithmetic::add(&mut ans.digits, &modulus.digits);
+        }
+        ans
+    }
+
+    /// The inverse of self mod modulus, or None if they are not coprime.
+    /// Works for any modulus, by running the extended Euclidean algorithm
+    /// with the coefficients kept reduced mod modulus so they never go
+    /// negative.
+    pub fn inv_mod(self, modulus: U512) -> Option<U512> {
+        assert!(!modulus.is_zero());
+        // Invariant: r0 = t0 * self and r1 = t1 * self (mod modulus)
+        let (mut r0, mut r1) = (modulus, self % modulus);
+        let (mut t0, mut t1) = (U512::zero(), U512::from_u64(1) % modulus);
+        while !r1.is_zero() {
+            let mut quot = U512::zero();
+            let mut rem = U512::zero();
+            arithmetic::div_rem(&r0.digits, &r1.digits, &mut quot.digits, &mut rem.digits);
+            let t2 = t0.sub_mod(quot.mul_mod(t1, modulus), modulus);
+            r0 = r1;
+            r1 = rem;
+            t0 = t1;
+            t1 = t2;
+        }
+
+        if r0 == U512::from_u64(1) {
+            Some(t0)
+        } else {
+            None
+        }
+    }
+
+    /// self^exp mod modulus. The running time depends on exp, so use
+    /// `pow_mod_ct` for secret exponents.
+    pub fn pow_mod(self, exp: U512, modulus: U512) -> U512 {
+        if modulus.is_even() {
+            return self.pow_mod_even(exp, modulus);
+        }
+        let mont = Montgomery::new(modulus);
+        mont.from_mont(mont.pow(mont.to_mont(self), exp))
+    }
+
+    /// self^exp mod modulus with a sliding window of the given width in bits.
+    pub fn pow_mod_window(self, exp: U512, modulus: U512, window: usize) -> U512 {
+        if modulus.is_even() {
+            return self.pow_mod_even(exp, modulus);
+        }
+        let mont = Montgomery::new(modulus);
+        mont.from_mont(mont.pow_window(mont.to_mont(self), exp, window))
+    }
+
+    /// self^exp mod modulus in time independent of exp. modulus must be odd.
+    pub fn pow_mod_ct(self, exp: U512, modulus: U512) -> U512 {
+        let mont = Montgomery::new(modulus);
+        mont.from_mont(mont.pow_ct(mont.to_mont(self), exp))
+    }
+
+    // Montgomery form needs an odd modulus, so fall back to square and
+    // multiply with a full reduction each step.
+    fn pow_mod_even(self, exp: U512, modulus: U512) -> U512 {
+        let base = self % modulus;
+        let mut ans = U512::from_u64(1) % modulus;
+        for idx in (0..exp.bits()).rev() {
+            ans = ans.mul_mod(ans, modulus);
+            if exp.bit(idx) {
+                ans = ans.mul_mod(base, modulus);
+            }
+        }
+        ans
+    }
+
     pub fn to_bytes_le(&self) -> [u8; 64] {
         let mut bytes = [0u8; 64];
         for (idx, digit) in self.digits.iter().enumerate() {
@@ -166,16 +326,16 @@ impl ops::SubAssign for U512 {
 impl ops::Mul for U512 {
     type Output = U512;
     fn mul(mut self, rhs: U512) -> U512 {
-        let self_digits = self.digits.clone();
-        arithmetic::mul(&self_digits, &rhs.digits, &mut self.digits);
+        let self_digits = self.digits;
+        arithmetic::mul_low_8(&self_digits, &rhs.digits, &mut self.digits);
         self
     }
 }
 
 impl ops::MulAssign for U512 {
     fn mul_assign(&mut self, rhs: U512) {
-        let self_digits = self.digits.clone();
-        arithmetic::mul(&self_digits, &rhs.digits, &mut self.digits);
+        let self_digits = self.digits;
+        arithmetic::mul_low_8(&self_digits, &rhs.digits, &mut self.digits);
     }
 }
 
@@ -319,10 +479,6 @@ impl cmp::PartialEq for U512 {
     fn eq(&self, other: &U512) -> bool {
         self.digits == other.digits
     }
-
-    fn ne(&self, other: &U512) -> bool {
-        self.digits != other.digits
-    }
 }
 
 impl cmp::Eq for U512 {}
@@ -335,7 +491,7 @@ impl cmp::Ord for U512 {
 
 impl cmp::PartialOrd for U512 {
     fn partial_cmp(&self, other: &U512) -> Option<cmp::Ordering> {
-        Some(self.cmp(&other))
+        Some(self.cmp(other))
     }
 }
 
@@ -376,6 +532,50 @@ mod tests {
         assert_eq!(res, bytes);
     }
 
+    #[test]
+    fn from_hash() {
+        // The P-256 order is just under 2^256, so an all ones hash is
+        // truncated and then reduced once
+        let n = U512::from_hex_be(b"ffffffff00000000ffffffffffffffffbce6faada7179e84f3b9cac2fc632551");
+        let ones = [0xff; 64];
+        assert_eq!(U512::from_hash_be(&ones, n), (U512::from_u64(1) << 256) - U512::from_u64(1) - n);
+        assert_eq!(U512::from_hash_be(&ones[..16], n), (U512::from_u64(1) << 128) - U512::from_u64(1));
+        assert_eq!(U512::from_hash_be(&n.to_bytes_be()[32..], n), U512::zero());
+        // A 21 bit modulus keeps the leftmost 21 bits of the 3 bytes it needs
+        let m = U512::from_u64(0x100001);
+        assert_eq!(U512::from_hash_be(&[0xab, 0xcd, 0xef, 0x12], m), U512::from_u64(0xabcdef >> 3) % m);
+        assert_eq!(U512::from_hash_be(&[], m), U512::zero());
+    }
+
+    #[test]
+    fn carry_through_max_digit() {
+        let x = U512::from_hex_be(b"ffffffffffffffffffffffffffffffff");
+        let one = U512::from_u64(1);
+        assert_eq!(one + x, x + one);
+        assert_eq!(U512::zero() - x + x, U512::zero());
+        assert_eq!((x + one) >> 128, one);
+    }
+
+    #[test]
+    fn divide_full_width() {
+        let x = !U512::zero();
+        let y = (U512::from_u64(1) << 511) + U512::from_u64(1);
+        assert_eq!(x / y, U512::from_u64(1));
+        assert_eq!(x % y, x - y);
+    }
+
+    #[test]
+    fn mul_wide() {
+        let max = !U512::zero();
+        let one = U512::from_u64(1);
+        // (2^512 - 1)^2 = 2^1024 - 2^513 + 1
+        assert_eq!(max.mul_wide(max), (one, max - one));
+        assert_eq!(max.mul_wide(one), (max, U512::zero()));
+        assert_eq!(max.square_wide(), (one, max - one));
+        let x = U512::from_hex_be(b"26dc5c6ce94a4b44f330b5d9bbd77cbf958416295cf7e1ce6bccdc18ff8c07b6");
+        assert_eq!(x.square_wide(), x.mul_wide(x));
+    }
+
     #[test]
     fn from_hex_be() {
         let a =
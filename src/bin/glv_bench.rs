@@ -0,0 +1,37 @@
+// Times secp256k1 scalar multiplication with and without the GLV
+// endomorphism. Run with `cargo run --release --bin glv_bench`.
+extern crate crypto_int;
+
+use std::time::Instant;
+
+use crypto_int::ec::{AffinePoint, JacobianPoint, Secp256k1};
+use crypto_int::U512;
+
+const ITERATIONS: u32 = 200;
+
+fn time<F: FnMut() -> JacobianPoint<Secp256k1>>(name: &str, mut f: F) -> f64 {
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        if f().is_identity() {
+            println!("uhoh");
+        }
+    }
+    let micros = start.elapsed().as_secs_f64() * 1e6 / ITERATIONS as f64;
+    println!("{:<28} {:>10.1} us", name, micros);
+    micros
+}
+
+fn main() {
+    let g = JacobianPoint::from(AffinePoint::<Secp256k1>::generator());
+    let p = g.mul_vartime(U512::from_u64(0x1234_5678_9abc_def0));
+    let k = U512::from_hex_be(b"c51e4753afdec1e6b6c6a5b992f43f8dd0c7a8933072708b6522468b2ffb06fd");
+    let l = U512::from_hex_be(b"9f2c83f4b9d7e0b21a03ab7ea9cc7b8a4c8d6e3f1a2b3c4d5e6f708192a3b4c5");
+
+    let plain = time("wNAF", || p.mul_vartime(k));
+    let glv = time("GLV", || p.mul_glv(k));
+    println!("speedup: {:.2}x\n", plain / glv);
+
+    let plain = time("two wNAF multiplications", || g.mul_vartime(k) + p.mul_vartime(l));
+    let glv = time("GLV double mul", || g.double_mul_glv(k, &p, l));
+    println!("speedup: {:.2}x", plain / glv);
+}
@@ -0,0 +1,38 @@
+// Times the U512 multiplication kernels. Run with
+// `cargo run --release --bin mul_bench`.
+extern crate crypto_int;
+
+use std::time::Instant;
+
+use crypto_int::{Montgomery, U512};
+
+const ITERATIONS: u32 = 10_000_000;
+
+// Each result feeds the next call, so none of them can be skipped
+fn time<F: FnMut(U512) -> U512>(name: &str, x: U512, mut f: F) {
+    let start = Instant::now();
+    let mut acc = x;
+    for _ in 0..ITERATIONS {
+        acc = f(acc);
+    }
+    let nanos = start.elapsed().as_secs_f64() * 1e9 / ITERATIONS as f64;
+    if acc.is_zero() {
+        println!("uhoh");
+    }
+    println!("{:<24} {:>8.1} ns", name, nanos);
+}
+
+fn main() {
+    let x = U512::from_hex_be(b"26dc5c6ce94a4b44f330b5d9bbd77cbf958416295cf7e1ce6bccdc18ff8c07b6\
+                                26dc5c6ce94a4b44f330b5d9bbd77cbf958416295cf7e1ce6bccdc18ff8c07b6");
+    let y = U512::from_hex_be(b"9f2c83f4b9d7e0b21a03ab7ea9cc7b8a4c8d6e3f1a2b3c4d5e6f708192a3b4c5\
+                                c51e4753afdec1e6b6c6a5b992f43f8dd0c7a8933072708b6522468b2ffb06fd");
+    // 2^512 - 569, the largest prime below 2^512
+    let m = Montgomery::new((!U512::zero()) - U512::from_u64(568));
+
+    time("U512 * (low half)", x, |a| a * y);
+    time("mul_wide", x, |a| a.mul_wide(y).1 ^ a);
+    time("square_wide", x, |a| a.square_wide().1 ^ a);
+    time("Montgomery mul", m.to_mont(x), |a| m.mul(a, y));
+    time("Montgomery square", m.to_mont(x), |a| m.square(a));
+}
@@ -0,0 +1,81 @@
+//! Constant time helpers: comparisons that don't branch on the values, for
+//! the MAC and padding checks in `ecdh` and `rsa`, and the fixed window
+//! scalar multiplication shared by the curve point types.
+
+use std::cmp;
+use std::ops::Add;
+
+use bigint::U512;
+
+// Scalars are processed in fixed windows of this many bits
+const WINDOW: usize = 4;
+
+/// Whether a == b. Compares every byte, so the time taken doesn't show
+/// where a forged tag or a bad padding first differs.
+pub fn ct_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+/// 0xff if x is zero and 0 otherwise, without a branch.
+pub fn ct_is_zero(x: u8) -> u8 {
+    (u16::from(x).wrapping_sub(1) >> 8) as u8
+}
+
+/// A point type that `mul_fixed_window` can work with.
+pub trait WindowPoint: Copy + Add<Output = Self> {
+    fn identity() -> Self;
+    fn double(&self) -> Self;
+    /// Sets self to other if choice is true, without branching on choice.
+    fn conditional_assign(&mut self, other: &Self, choice: bool);
+}
+
+/// table[idx] for a secret idx. Reads every entry so the memory access
+/// doesn't depend on idx.
+pub fn lookup<P: WindowPoint>(table: &[P], idx: usize) -> P {
+    let mut entry = P::identity();
+    for (i, val) in table.iter().enumerate() {
+        entry.conditional_assign(val, i == idx);
+    }
+    entry
+}
+
+/// k * point in constant time for k < 2^bits, with a fixed window of 4
+/// bits. Every window costs the same doublings, one lookup and one
+/// addition, whatever its digit.
+pub fn mul_fixed_window<P: WindowPoint>(point: &P, k: U512, bits: usize) -> P {
+    // table[i] = i * point
+    let mut table = [P::identity(); 1 << WINDOW];
+    for idx in 1..table.len() {
+        table[idx] = table[idx - 1] + *point;
+    }
+
+    let mut ans = P::identity();
+    for window_idx in (0..bits.div_ceil(WINDOW)).rev() {
+        for _ in 0..WINDOW {
+            ans = ans.double();
+        }
+        let low = window_idx * WINDOW;
+        let mut digit = 0;
+        for bit_idx in (low..cmp::min(low + WINDOW, 512)).rev() {
+            digit = (digit << 1) | k.bit(bit_idx) as usize;
+        }
+        ans = ans + lookup(&table, digit);
+    }
+    ans
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ct() {
+        assert!(ct_eq(b"abc", b"abc"));
+        assert!(!ct_eq(b"abc", b"abd"));
+        assert!(!ct_eq(b"abc", b"ab"));
+        assert!(ct_eq(b"", b""));
+        assert_eq!(ct_is_zero(0), 0xff);
+        assert_eq!(ct_is_zero(1), 0);
+        assert_eq!(ct_is_zero(0xff), 0);
+    }
+}
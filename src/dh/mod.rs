@@ -0,0 +1,169 @@
+//! Finite field Diffie-Hellman, for older protocols that still use it. The
+//! standard groups are 1536 to 8192 bits, so everything here is `BigUint`.
+//!
+//! Groups are mod a safe prime p = 2q + 1 with q prime, and the generator
+//! has order q. Public values are checked to be in that subgroup too, so a
+//! peer can't learn anything about the private key by sending a value of
+//! small order.
+
+use std::sync::OnceLock;
+
+use rand::Rng;
+
+use bigint::BigUint;
+
+mod groups;
+
+/// A Diffie-Hellman group: a safe prime p = 2q + 1, and a generator g of
+/// the subgroup of order q.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Group {
+    p: BigUint,
+    q: BigUint,
+    g: BigUint,
+}
+
+macro_rules! standard_groups {
+    ($($name:ident => $prime:ident, $doc:expr;)*) => {
+        impl Group {
+            $(
+                #[doc = $doc]
+                pub fn $name() -> &'static Group {
+                    static GROUP: OnceLock<Group> = OnceLock::new();
+                    GROUP.get_or_init(|| Group::safe_prime(groups::parse(groups::$prime), BigUint::from_u64(2)))
+                }
+            )*
+        }
+    };
+}
+
+standard_groups! {
+    ffdhe2048 => FFDHE2048, "The 2048 bit ffdhe2048 group from RFC 7919, used by TLS.";
+    ffdhe3072 => FFDHE3072, "The 3072 bit ffdhe3072 group from RFC 7919.";
+    ffdhe4096 => FFDHE4096, "The 4096 bit ffdhe4096 group from RFC 7919.";
+    ffdhe6144 => FFDHE6144, "The 6144 bit ffdhe6144 group from RFC 7919.";
+    ffdhe8192 => FFDHE8192, "The 8192 bit ffdhe8192 group from RFC 7919.";
+    modp1536 => MODP1536, "The 1536 bit MODP group 5 from RFC 3526, used by IKE.";
+    modp2048 => MODP2048, "The 2048 bit MODP group 14 from RFC 3526.";
+    modp3072 => MODP3072, "The 3072 bit MODP group 15 from RFC 3526.";
+    modp4096 => MODP4096, "The 4096 bit MODP group 16 from RFC 3526.";
+    modp6144 => MODP6144, "The 6144 bit MODP group 17 from RFC 3526.";
+    modp8192 => MODP8192, "The 8192 bit MODP group 18 from RFC 3526.";
+}
+
+impl Group {
+    /// The group mod the safe prime p with generator g, or None if g
+    /// doesn't have order q = (p - 1) / 2. p isn't tested for primality,
+    /// which is slow, so it has to come from somewhere trusted.
+    pub fn new(p: BigUint, g: BigUint) -> Option<Group> {
+        if p.is_even() || p.bits() < 3 {
+            return None;
+        }
+        let group = Group::safe_prime(p, g);
+        if !group.in_subgroup(&group.g) {
+            return None;
+        }
+        Some(group)
+    }
+
+    fn safe_prime(p: BigUint, g: BigUint) -> Group {
+        let q = p.clone() >> 1;
+        Group { p, q, g }
+    }
+
+    pub fn p(&self) -> &BigUint {
+        &self.p
+    }
+
+    pub fn q(&self) -> &BigUint {
+        &self.q
+    }
+
+    pub fn g(&self) -> &BigUint {
+        &self.g
+    }
+
+    // 1 < y < p - 1 and y^q = 1, which leaves exactly the elements of order q
+    fn in_subgroup(&self, y: &BigUint) -> bool {
+        let one = BigUint::from_u64(1);
+        *y > one && *y < &self.p - &one && y.pow_mod(&self.q, &self.p) == one
+    }
+}
+
+/// A random private key in [1, q).
+pub fn generate_key<R: Rng>(group: &Group, rng: &mut R) -> BigUint {
+    BigUint::random_in_range(&BigUint::from_u64(1), &group.q, rng)
+}
+
+/// The public value g^x for the private key x, in constant time.
+pub fn public_key(group: &Group, private_key: &BigUint) -> BigUint {
+    assert!(is_valid_key(group, private_key), "private key must be in [1, q)");
+    group.g.pow_mod_ct(private_key, &group.p)
+}
+
+/// Whether y is a valid public value in the group: 1 < y < p - 1 and
+/// y^q = 1 mod p.
+pub fn is_valid_public_key(group: &Group, y: &BigUint) -> bool {
+    group.in_subgroup(y)
+}
+
+/// The shared secret between private_key and the peer's public value y:
+/// y^private_key mod p, big endian and zero padded to the length of p as in
+/// RFC 7919. None if y isn't a valid public value. The exponentiation is
+/// constant time.
+pub fn dh(group: &Group, private_key: &BigUint, peer: &BigUint) -> Option<Vec<u8>> {
+    assert!(is_valid_key(group, private_key), "private key must be in [1, q)");
+    if !is_valid_public_key(group, peer) {
+        return None;
+    }
+    let secret = peer.pow_mod_ct(private_key, &group.p).to_bytes_be();
+    let mut out = vec![0; group.p.bits().div_ceil(8) - secret.len()];
+    out.extend_from_slice(&secret);
+    Some(out)
+}
+
+fn is_valid_key(group: &Group, k: &BigUint) -> bool {
+    !k.is_zero() && *k < group.q
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn standard_groups() {
+        let groups = [
+            (Group::ffdhe2048(), 2048), (Group::ffdhe3072(), 3072), (Group::ffdhe4096(), 4096),
+            (Group::ffdhe6144(), 6144), (Group::ffdhe8192(), 8192), (Group::modp1536(), 1536),
+            (Group::modp2048(), 2048), (Group::modp3072(), 3072), (Group::modp4096(), 4096),
+            (Group::modp6144(), 6144), (Group::modp8192(), 8192),
+        ];
+        for &(group, bits) in groups.iter() {
+            assert_eq!(group.p().bits(), bits);
+            assert_eq!(&(group.q().clone() << 1) + &BigUint::from_u64(1), *group.p());
+        }
+        // The generator check is slow for the big groups
+        let group = Group::ffdhe2048();
+        assert_eq!(Group::new(group.p().clone(), group.g().clone()).as_ref(), Some(group));
+        let group = Group::modp2048();
+        assert_eq!(Group::new(group.p().clone(), group.g().clone()).as_ref(), Some(group));
+    }
+
+    #[test]
+    fn public_values() {
+        let group = Group::ffdhe2048();
+        let p = group.p();
+        let one = BigUint::from_u64(1);
+        // p = 7 mod 8, so -1 and -2 aren't squares and don't have order q
+        for y in [BigUint::zero(), one.clone(), p - &one, p.clone(), p - &BigUint::from_u64(2), p + &one].iter() {
+            assert!(!is_valid_public_key(group, y));
+        }
+        assert!(is_valid_public_key(group, &BigUint::from_u64(4)));
+        assert_eq!(dh(group, &BigUint::from_u64(5), &(p - &one)), None);
+
+        // 5 = -2 mod 7 doesn't generate the subgroup of order 3
+        assert_eq!(Group::new(BigUint::from_u64(7), BigUint::from_u64(5)), None);
+        assert!(Group::new(BigUint::from_u64(7), BigUint::from_u64(2)).is_some());
+        assert_eq!(Group::new(BigUint::from_u64(8), BigUint::from_u64(2)), None);
+    }
+}
@@ -0,0 +1,338 @@
+//! DSA signatures from FIPS 186-4, for checking signatures from older
+//! systems. Domain parameters can be generated and validated from a seed and
+//! counter as in appendix A.1.1.2, with the generator derived from the seed
+//! as in appendix A.2.3. Like `ecdsa`, signing takes the hash of the message
+//! and uses the deterministic nonces of RFC 6979.
+
+use std::convert::TryFrom;
+
+use rand::Rng;
+
+use bigint::{BigUint, Montgomery, U512};
+use ecdsa::{bits2int, byte_len, int2octets, NonceGenerator};
+use hash::Digest;
+
+// The (L, N) sizes allowed by section 4.2, and the Miller-Rabin rounds for p
+// and q from appendix C.3 table C.1
+const SIZES: [(usize, usize, usize, usize); 4] = [
+    (1024, 160, 40, 40),
+    (2048, 224, 56, 56),
+    (2048, 256, 56, 64),
+    (3072, 256, 64, 64),
+];
+
+fn rounds(l: usize, n: usize) -> Option<(usize, usize)> {
+    SIZES.iter().find(|size| size.0 == l && size.1 == n).map(|size| (size.2, size.3))
+}
+
+/// DSA domain parameters: primes p and q with q dividing p - 1, and a
+/// generator g of the subgroup of order q.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Parameters {
+    p: BigUint,
+    q: U512,
+    g: BigUint,
+}
+
+impl Parameters {
+    /// The parameters (p, q, g), or None if they're malformed. The sizes of
+    /// p and q have to be one of the (L, N) pairs FIPS 186-4 allows, q has
+    /// to divide p - 1, and g has to be in [2, p - 1] with g^q = 1. All the
+    /// cheap checks come before the exponentiation. p and q aren't tested
+    /// for primality, `verify_primes` does that given their seed.
+    pub fn new(p: BigUint, q: U512, g: BigUint) -> Option<Parameters> {
+        rounds(p.bits(), q.bits())?;
+        let one = BigUint::from_u64(1);
+        let q_big = BigUint::from(q);
+        if p.is_even() || q.is_even() || !(&(&p - &one) % &q_big).is_zero() {
+            return None;
+        }
+        if g <= one || g >= p || g.pow_mod(&q_big, &p) != one {
+            return None;
+        }
+        Some(Parameters { p, q, g })
+    }
+
+    pub fn p(&self) -> &BigUint {
+        &self.p
+    }
+
+    pub fn q(&self) -> U512 {
+        self.q
+    }
+
+    pub fn g(&self) -> &BigUint {
+        &self.g
+    }
+}
+
+/// The seed p and q were generated from, and the counter p was found at.
+/// With these anyone can check the primes were generated honestly rather
+/// than chosen.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DomainSeed {
+    seed: Vec<u8>,
+    counter: usize,
+}
+
+impl DomainSeed {
+    pub fn new(seed: Vec<u8>, counter: usize) -> DomainSeed {
+        DomainSeed { seed, counter }
+    }
+
+    pub fn seed(&self) -> &[u8] {
+        &self.seed
+    }
+
+    pub fn counter(&self) -> usize {
+        self.counter
+    }
+}
+
+/// Generates domain parameters with an l bit p and n bit q using the hash
+/// function D: the primes from `generate_primes`, and the generator with
+/// index 1 from `derive_generator`.
+pub fn generate_parameters<D: Digest, R: Rng>(l: usize, n: usize, rng: &mut R) -> (Parameters, DomainSeed) {
+    let (p, q, domain_seed) = generate_primes::<D, R>(l, n, rng);
+    let g = derive_generator::<D>(&p, q, &domain_seed.seed, 1).expect("no generator for the primes");
+    (Parameters { p, q, g }, domain_seed)
+}
+
+/// Generates an l bit prime p and an n bit prime q dividing p - 1 from a
+/// random n bit seed, as in appendix A.1.1.2. D's output has to be at least
+/// n bits long.
+pub fn generate_primes<D: Digest, R: Rng>(l: usize, n: usize, rng: &mut R) -> (BigUint, U512, DomainSeed) {
+    assert!(rounds(l, n).is_some(), "(L, N) is not an allowed size");
+    assert!(D::OUTPUT_LEN * 8 >= n, "the hash is shorter than q");
+    loop {
+        let mut seed = vec![0u8; n / 8];
+        rng.fill_bytes(&mut seed);
+        if let Some((p, q, counter)) = primes_from_seed::<D, R>(l, n, &seed, rng) {
+            return (p, q, DomainSeed { seed, counter });
+        }
+    }
+}
+
+/// Whether p and q were generated from the seed with D, as in appendix
+/// A.1.1.3: generating from the seed gives the same q, and the same p on the
+/// same counter.
+pub fn verify_primes<D: Digest, R: Rng>(p: &BigUint, q: U512, domain_seed: &DomainSeed, rng: &mut R) -> bool {
+    let (l, n) = (p.bits(), q.bits());
+    if rounds(l, n).is_none() || D::OUTPUT_LEN * 8 < n || domain_seed.seed.len() * 8 < n {
+        return false;
+    }
+    match primes_from_seed::<D, R>(l, n, &domain_seed.seed, rng) {
+        Some((p_seed, q_seed, counter)) => p_seed == *p && q_seed == q && counter == domain_seed.counter,
+        None => false,
+    }
+}
+
+// Steps 6 to 11 of appendix A.1.1.2: q from the hash of the seed, then p
+// from hashes of the seed plus an offset. The first prime p within 4L tries
+// wins, and None means q wasn't prime or there was no such p.
+fn primes_from_seed<D: Digest, R: Rng>(l: usize, n: usize, seed: &[u8], rng: &mut R) -> Option<(BigUint, U512, usize)> {
+    let (p_rounds, q_rounds) = rounds(l, n).unwrap();
+    let one = BigUint::from_u64(1);
+
+    // q = 2^(N - 1) + U + 1 - (U mod 2), which sets the top and bottom bits
+    // of U = Hash(seed) mod 2^(N - 1)
+    let u = U512::from_bytes_be(hash::<D>(seed)) % (U512::from_u64(1) << (n - 1));
+    let q = u | (U512::from_u64(1) << (n - 1)) | U512::from_u64(1);
+    if !BigUint::from(q).is_probable_prime(q_rounds, rng) {
+        return None;
+    }
+
+    // W is the low L - 1 bits of V_0 + V_1 2^outlen + ... where V_j is the
+    // hash of seed + offset + j mod 2^seedlen
+    let outlen = D::OUTPUT_LEN * 8;
+    let blocks = l.div_ceil(outlen);
+    let seed_int = BigUint::from_bytes_be(seed.to_vec());
+    let seed_modulus = one.clone() << (seed.len() * 8);
+    let top = one.clone() << (l - 1);
+    let two_q = BigUint::from(q) << 1;
+    let mut offset = 1;
+    for counter in 0..4 * l {
+        let mut w = BigUint::zero();
+        for j in 0..blocks {
+            let v_seed = &(&seed_int + &BigUint::from_u64((offset + j) as u64)) % &seed_modulus;
+            let v = BigUint::from_bytes_be(hash::<D>(&to_bytes(&v_seed, seed.len())));
+            w = &w + &(v << (j * outlen));
+        }
+        offset += blocks;
+
+        // p = X - (X mod 2q - 1) for X = W + 2^(L - 1), so p = 1 mod 2q
+        let x = &(&w % &top) + &top;
+        let p = &(&x - &(&x % &two_q)) + &one;
+        if p >= top && p.is_probable_prime(p_rounds, rng) {
+            return Some((p, q, counter));
+        }
+    }
+    None
+}
+
+/// The generator for p and q derived from the domain parameter seed, as in
+/// appendix A.2.3: Hash(seed || "ggen" || index || count)^((p - 1) / q) for
+/// the first 16 bit count that doesn't give 1. Different indexes give
+/// independent generators for different uses. None if q doesn't divide
+/// p - 1.
+pub fn derive_generator<D: Digest>(p: &BigUint, q: U512, seed: &[u8], index: u8) -> Option<BigUint> {
+    let one = BigUint::from_u64(1);
+    let q = BigUint::from(q);
+    let p_minus_one = p - &one;
+    if q.is_zero() || !(&p_minus_one % &q).is_zero() {
+        return None;
+    }
+    let e = &p_minus_one / &q;
+    for count in 1..=u16::MAX {
+        let mut data = seed.to_vec();
+        data.extend_from_slice(b"ggen");
+        data.push(index);
+        data.extend_from_slice(&count.to_be_bytes());
+        let g = BigUint::from_bytes_be(hash::<D>(&data)).pow_mod(&e, p);
+        if g > one {
+            return Some(g);
+        }
+    }
+    None
+}
+
+/// A DSA signature (r, s). Both have to be in [1, q) to verify.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Signature {
+    r: U512,
+    s: U512,
+}
+
+impl Signature {
+    pub fn new(r: U512, s: U512) -> Signature {
+        Signature { r, s }
+    }
+
+    pub fn r(&self) -> U512 {
+        self.r
+    }
+
+    pub fn s(&self) -> U512 {
+        self.s
+    }
+}
+
+/// A random private key in [1, q).
+pub fn generate_key<R: Rng>(params: &Parameters, rng: &mut R) -> U512 {
+    U512::random_in_range(U512::from_u64(1), params.q, rng)
+}
+
+/// The public key g^x mod p for the private key x, in constant time.
+pub fn public_key(params: &Parameters, private_key: U512) -> BigUint {
+    assert!(is_valid_key(params, private_key), "private key must be in [1, q)");
+    params.g.pow_mod_ct(&BigUint::from(private_key), &params.p)
+}
+
+/// Signs message_hash, which must be the output of D, with the nonce chosen
+/// as in RFC 6979 using HMAC with D. The exponentiation with the nonce is
+/// constant time.
+pub fn sign<D: Digest>(params: &Parameters, private_key: U512, message_hash: &[u8]) -> Signature {
+    assert!(is_valid_key(params, private_key), "private key must be in [1, q)");
+    let q = params.q;
+    let m = Montgomery::new(q);
+    let h = U512::from_hash_be(message_hash, q);
+
+    let mut nonces = NonceGenerator::<D>::new(&int2octets(q, private_key), &int2octets(q, h));
+    loop {
+        let k = bits2int(q, &nonces.generate(byte_len(q)));
+        if !is_valid_key(params, k) {
+            nonces.reject();
+            continue;
+        }
+
+        // r = (g^k mod p) mod q, and s = (h + x * r) / k
+        let gk = params.g.pow_mod_ct(&BigUint::from(k), &params.p);
+        let r = U512::try_from(&gk % &BigUint::from(q)).unwrap();
+        let k_inv = m.pow_ct(m.to_mont(k), q - U512::from_u64(2));
+        let s = m.mul(k_inv, m.add(h, m.mul(m.to_mont(r), private_key)));
+        if !r.is_zero() && !s.is_zero() {
+            return Signature { r, s };
+        }
+        nonces.reject();
+    }
+}
+
+/// Whether signature is a valid signature of message_hash under public_key.
+pub fn verify(params: &Parameters, public_key: &BigUint, message_hash: &[u8], signature: &Signature) -> bool {
+    let (r, s) = (signature.r, signature.s);
+    if !is_valid_key(params, r) || !is_valid_key(params, s) {
+        return false;
+    }
+    if *public_key <= BigUint::from_u64(1) || *public_key >= params.p {
+        return false;
+    }
+
+    let q = params.q;
+    let m = Montgomery::new(q);
+    let h = U512::from_hash_be(message_hash, q);
+    let w = m.pow(m.to_mont(s), q - U512::from_u64(2));
+    let u1 = BigUint::from(m.mul(w, h));
+    let u2 = BigUint::from(m.mul(w, r));
+
+    // v = (g^u1 y^u2 mod p) mod q
+    let p = &params.p;
+    let v = &(&params.g.pow_mod(&u1, p) * &public_key.pow_mod(&u2, p)) % p;
+    &v % &BigUint::from(q) == BigUint::from(r)
+}
+
+fn is_valid_key(params: &Parameters, k: U512) -> bool {
+    !k.is_zero() && k < params.q
+}
+
+fn hash<D: Digest>(data: &[u8]) -> Vec<u8> {
+    let mut hasher = D::new();
+    hasher.update(data);
+    hasher.finalize().as_ref().to_vec()
+}
+
+// x as a big endian string of len bytes, which it has to fit in
+fn to_bytes(x: &BigUint, len: usize) -> Vec<u8> {
+    let bytes = x.to_bytes_be();
+    let mut out = vec![0; len - bytes.len()];
+    out.extend_from_slice(&bytes);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hash::Sha256;
+    use rand::thread_rng;
+
+    #[test]
+    fn generate_and_validate() {
+        let mut rng = thread_rng();
+        let (params, domain_seed) = generate_parameters::<Sha256, _>(1024, 160, &mut rng);
+        let (p, q, g) = (params.p().clone(), params.q(), params.g().clone());
+        assert_eq!((p.bits(), q.bits()), (1024, 160));
+        assert!(verify_primes::<Sha256, _>(&p, q, &domain_seed, &mut rng));
+        assert_eq!(Parameters::new(p.clone(), q, g.clone()).as_ref(), Some(&params));
+
+        let one = BigUint::from_u64(1);
+        let two = U512::from_u64(2);
+        // g has to be in [2, p - 1] and have order q, and p - 1 has order 2
+        for bad_g in [one.clone(), &p - &one, p.clone(), BigUint::zero()].iter() {
+            assert_eq!(Parameters::new(p.clone(), q, bad_g.clone()), None);
+        }
+        // q has to divide p - 1, and both have to be odd and the right size
+        assert_eq!(Parameters::new(p.clone(), q + two, g.clone()), None);
+        assert_eq!(Parameters::new(&p + &one, q, g.clone()), None);
+        assert_eq!(Parameters::new(p.clone() << 1024, q, g.clone()), None);
+        assert_eq!(Parameters::new(p.clone(), q >> 8, g.clone()), None);
+
+        let x = generate_key(&params, &mut rng);
+        let y = public_key(&params, x);
+        let hash = [0x5a; 32];
+        let signature = sign::<Sha256>(&params, x, &hash);
+        assert!(verify(&params, &y, &hash, &signature));
+        assert!(!verify(&params, &y, &[0x5b; 32], &signature));
+        let s = params.q() - signature.s();
+        assert!(!verify(&params, &y, &hash, &Signature::new(signature.r(), s)));
+        assert!(!verify(&params, &y, &hash, &Signature::new(signature.r() + params.q(), signature.s())));
+    }
+}
@@ -0,0 +1,71 @@
+use std::sync::OnceLock;
+
+use bigint::U512;
+use super::{AffinePoint, Curve, Field, FixedBaseTable};
+
+// Every curve's field() and base_table() are the same apart from the type,
+// and each needs its own statics.
+macro_rules! curve_statics {
+    ($curve:ident) => {
+        fn field() -> &'static Field {
+            static FIELD: OnceLock<Field> = OnceLock::new();
+            FIELD.get_or_init(Field::new::<$curve>)
+        }
+
+        fn base_table() -> &'static FixedBaseTable<$curve> {
+            static TABLE: OnceLock<FixedBaseTable<$curve>> = OnceLock::new();
+            TABLE.get_or_init(|| FixedBaseTable::new(&AffinePoint::generator()))
+        }
+    };
+}
+
+/// The Koblitz curve used by Bitcoin, from SEC 2.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Secp256k1;
+
+impl Curve for Secp256k1 {
+    const P: U512 = U512::from_hex_be(b"fffffffffffffffffffffffffffffffffffffffffffffffffffffffefffffc2f");
+    const A: U512 = U512::zero();
+    const B: U512 = U512::from_u64(7);
+    const N: U512 = U512::from_hex_be(b"fffffffffffffffffffffffffffffffebaaedce6af48a03bbfd25e8cd0364141");
+    const GX: U512 = U512::from_hex_be(b"79be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798");
+    const GY: U512 = U512::from_hex_be(b"483ada7726a3c4655da4fbfc0e1108a8fd17b448a68554199c47d08ffb10d4b8");
+
+    curve_statics!(Secp256k1);
+}
+
+/// NIST P-256, also known as secp256r1, from FIPS 186-4.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct P256;
+
+impl Curve for P256 {
+    const P: U512 = U512::from_hex_be(b"ffffffff00000001000000000000000000000000ffffffffffffffffffffffff");
+    const A: U512 = U512::from_hex_be(b"ffffffff00000001000000000000000000000000fffffffffffffffffffffffc");
+    const B: U512 = U512::from_hex_be(b"5ac635d8aa3a93e7b3ebbd55769886bc651d06b0cc53b0f63bce3c3e27d2604b");
+    const N: U512 = U512::from_hex_be(b"ffffffff00000000ffffffffffffffffbce6faada7179e84f3b9cac2fc632551");
+    const GX: U512 = U512::from_hex_be(b"6b17d1f2e12c4247f8bce6e563a440f277037d812deb33a0f4a13945d898c296");
+    const GY: U512 = U512::from_hex_be(b"4fe342e2fe1a7f9b8ee7eb4a7c0f9e162bce33576b315ececbb6406837bf51f5");
+
+    curve_statics!(P256);
+}
+
+/// NIST P-384, also known as secp384r1, from FIPS 186-4.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct P384;
+
+impl Curve for P384 {
+    const P: U512 = U512::from_hex_be(b"fffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffe\
+                                        ffffffff0000000000000000ffffffff");
+    const A: U512 = U512::from_hex_be(b"fffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffe\
+                                        ffffffff0000000000000000fffffffc");
+    const B: U512 = U512::from_hex_be(b"b3312fa7e23ee7e4988e056be3f82d19181d9c6efe8141120314088f5013875a\
+                                        c656398d8a2ed19d2a85c8edd3ec2aef");
+    const N: U512 = U512::from_hex_be(b"ffffffffffffffffffffffffffffffffffffffffffffffffc7634d81f4372ddf\
+                                        581a0db248b0a77aecec196accc52973");
+    const GX: U512 = U512::from_hex_be(b"aa87ca22be8b05378eb1c71ef320ad746e1d3b628ba79b9859f741e082542a38\
+                                         5502f25dbf55296c3a545e3872760ab7");
+    const GY: U512 = U512::from_hex_be(b"3617de4a96262c6f5d9e98bf9292dc29f8f41dbd289a147ce9da3113b5f0b8c0\
+                                         0a60b1ce1d7e819d7a431d7c90ea0e5f");
+
+    curve_statics!(P384);
+}
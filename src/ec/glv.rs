@@ -0,0 +1,145 @@
+//! The GLV method for secp256k1. The map (x, y) -> (beta * x, y), for a cube
+//! root of unity beta mod p, is the same as multiplying by a cube root of
+//! unity lambda mod n, and costs a single field multiplication. Splitting k
+//! into k1 + k2 * lambda with both halves around 128 bits turns k * P into
+//! k1 * P + k2 * (lambda * P), which needs half as many doublings.
+
+use std::convert::TryFrom;
+
+use bigint::{I512, U512};
+use super::{Curve, JacobianPoint, Secp256k1};
+
+// A basis of short vectors (a, b) with a + b * lambda = 0 mod n, from
+// libsecp256k1. b2 = a1, and b1 is negative.
+const A1: U512 = U512::from_hex_be(b"3086d221a7d46bcde86c90e49284eb15");
+const MINUS_B1: U512 = U512::from_hex_be(b"e4437ed6010e88286f547fa90abfe4c3");
+const A2: U512 = U512::from_hex_be(b"0114ca50f7a8e2f3f657c1108d9d44cfd8");
+const B2: U512 = A1;
+
+fn signed(x: U512) -> I512 {
+    I512::try_from(x).unwrap()
+}
+
+impl Secp256k1 {
+    /// A cube root of unity mod n, the eigenvalue of the endomorphism.
+    pub const LAMBDA: U512 = U512::from_hex_be(b"5363ad4cc05c30e0a5261c028812645a122e22ea20816678df02967c1b23bd72");
+    /// A cube root of unity mod p: lambda * (x, y) = (beta * x, y).
+    pub const BETA: U512 = U512::from_hex_be(b"7ae96a2b657c07106e64479eac3434e99cf0497512f58995c1396c28719501ee");
+
+    /// Splits k into (k1, k2) with k1 + k2 * lambda = k mod n, where both
+    /// are at most about 2^128 in absolute value. Rounds k against the basis
+    /// vectors, so it isn't constant time.
+    pub fn decompose_scalar(k: U512) -> (I512, I512) {
+        let n = Secp256k1::N;
+        let k = if k >= n { k % n } else { k };
+        // c1 = round(b2 * k / n) and c2 = round(-b1 * k / n)
+        let half_n = n >> 1;
+        let c1 = (B2 * k + half_n) / n;
+        let c2 = (MINUS_B1 * k + half_n) / n;
+
+        // (k1, k2) = (k, 0) - c1 * (a1, b1) - c2 * (a2, b2)
+        let k1 = signed(k) - signed(c1) * signed(A1) - signed(c2) * signed(A2);
+        let k2 = signed(c1) * signed(MINUS_B1) - signed(c2) * signed(B2);
+        (k1, k2)
+    }
+}
+
+impl JacobianPoint<Secp256k1> {
+    /// lambda * self, which is just (beta * x, y).
+    pub fn endomorphism(&self) -> JacobianPoint<Secp256k1> {
+        let m = &Secp256k1::field().mont;
+        self.scale_x(m.to_mont(Secp256k1::BETA))
+    }
+
+    /// k * self with the GLV method. Not constant time.
+    pub fn mul_glv(&self, k: U512) -> JacobianPoint<Secp256k1> {
+        let (k1, k2) = Secp256k1::decompose_scalar(k);
+        mul_multi(&[*self, self.endomorphism()], &[k1, k2])
+    }
+
+    /// a * self + b * other, sharing the doublings between all four halves
+    /// of the scalars. This is what signature verification needs. Not
+    /// constant time.
+    pub fn double_mul_glv(&self, a: U512, other: &JacobianPoint<Secp256k1>, b: U512)
+                          -> JacobianPoint<Secp256k1> {
+        let (a1, a2) = Secp256k1::decompose_scalar(a);
+        let (b1, b2) = Secp256k1::decompose_scalar(b);
+        mul_multi(&[*self, self.endomorphism(), *other, other.endomorphism()], &[a1, a2, b1, b2])
+    }
+}
+
+// The sum of scalars[i] * points[i], with Straus' method: every sum of a
+// subset of the points is precomputed, then one pass of double and add over
+// the bits of all the scalars at once adds the subset whose bits are set.
+fn mul_multi<C: Curve>(points: &[JacobianPoint<C>], scalars: &[I512]) -> JacobianPoint<C> {
+    // Negative scalars negate their point instead
+    let points: Vec<JacobianPoint<C>> = points.iter().zip(scalars)
+        .map(|(point, k)| if k.is_negative() { -*point } else { *point })
+        .collect();
+    let scalars: Vec<U512> = scalars.iter().map(|k| k.unsigned_abs()).collect();
+
+    // table[mask] is the sum of the points whose bits are set in mask
+    let mut table = vec![JacobianPoint::identity(); 1 << points.len()];
+    for mask in 1..table.len() {
+        let low = mask.trailing_zeros() as usize;
+        table[mask] = table[mask & (mask - 1)] + points[low];
+    }
+
+    let bits = scalars.iter().map(|k| k.bits()).max().unwrap_or(0);
+    let mut ans = JacobianPoint::identity();
+    for bit_idx in (0..bits).rev() {
+        ans = ans.double();
+        let mut mask = 0;
+        for (idx, k) in scalars.iter().enumerate() {
+            mask |= (k.bit(bit_idx) as usize) << idx;
+        }
+        if mask != 0 {
+            ans = ans + table[mask];
+        }
+    }
+    ans
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ec::AffinePoint;
+
+    // k1 + k2 * lambda mod n
+    fn recombine(k1: I512, k2: I512) -> U512 {
+        let n = signed(Secp256k1::N);
+        let ans = (k1 + (k2 % n) * signed(Secp256k1::LAMBDA)).rem_euclid(n);
+        U512::try_from(ans).unwrap()
+    }
+
+    #[test]
+    fn decompose() {
+        let bound = signed(U512::from_u64(1) << 129);
+        let mut k = U512::from_hex_be(b"c51e4753afdec1e6b6c6a5b992f43f8dd0c7a8933072708b6522468b2ffb06fd");
+        for _ in 0..100 {
+            let (k1, k2) = Secp256k1::decompose_scalar(k);
+            assert!(k1.abs() < bound && k2.abs() < bound);
+            assert_eq!(recombine(k1, k2), k % Secp256k1::N);
+            k = k * k % Secp256k1::N + U512::from_u64(7);
+        }
+        for &k in [U512::zero(), U512::from_u64(1), Secp256k1::N - U512::from_u64(1), Secp256k1::LAMBDA].iter() {
+            let (k1, k2) = Secp256k1::decompose_scalar(k);
+            assert_eq!(recombine(k1, k2), k);
+        }
+    }
+
+    #[test]
+    fn endomorphism() {
+        let g = JacobianPoint::from(AffinePoint::<Secp256k1>::generator());
+        assert_eq!(g.endomorphism(), g.mul_vartime(Secp256k1::LAMBDA));
+        assert!(g.endomorphism().to_affine().is_on_curve());
+
+        let k = U512::from_hex_be(b"fe2c83f4b9d7e0b21a03ab7ea9cc7b8a4c8d6e3f1a2b3c4d5e6f708192a3b4c5");
+        let p = g.mul_vartime(U512::from_u64(12345));
+        assert_eq!(g.mul_glv(k), g.mul_vartime(k));
+        assert_eq!(p.mul_glv(k), p.mul_vartime(k));
+        assert!(g.mul_glv(Secp256k1::N).is_identity());
+        assert!(g.mul_glv(U512::zero()).is_identity());
+        assert_eq!(g.double_mul_glv(k, &p, Secp256k1::LAMBDA), g.mul_vartime(k) + p.mul_vartime(Secp256k1::LAMBDA));
+    }
+}
@@ -0,0 +1,174 @@
+//! Elliptic curves in short Weierstrass form, y^2 = x^3 + ax + b over the
+//! integers mod a prime p.
+
+use std::fmt;
+
+use bigint::{Montgomery, U512};
+
+mod curves;
+mod glv;
+mod point;
+mod sec1;
+mod table;
+
+pub use self::curves::{P256, P384, Secp256k1};
+pub use self::point::{AffinePoint, JacobianPoint, ProjectivePoint};
+pub use self::table::FixedBaseTable;
+
+/// The parameters of a curve. Implementations are empty marker types, so
+/// points on different curves are different types.
+pub trait Curve: Copy + Clone + fmt::Debug + PartialEq + Eq + 'static {
+    /// The field prime.
+    const P: U512;
+    const A: U512;
+    const B: U512;
+    /// The order of the generator. The formulas used here need it to be an
+    /// odd prime, and the curve's cofactor to be 1.
+    const N: U512;
+    const GX: U512;
+    const GY: U512;
+
+    /// The field arithmetic for this curve. This should be built with
+    /// `Field::new` only once, e.g. in a `OnceLock`.
+    fn field() -> &'static Field;
+
+    /// The precomputed multiples of the generator, which should also be
+    /// built only once.
+    fn base_table() -> &'static FixedBaseTable<Self>;
+}
+
+/// Arithmetic mod a curve's prime, with the constants the point formulas
+/// need. Everything here is in Montgomery form.
+#[derive(Copy, Clone, Debug)]
+pub struct Field {
+    pub(crate) mont: Montgomery,
+    pub(crate) a: U512,
+    pub(crate) b: U512,
+    // 3b, which shows up in the complete formulas
+    b3: U512,
+    // p - 2, for inverting by Fermat's little theorem
+    p_minus_2: U512,
+}
+
+impl Field {
+    pub fn new<C: Curve>() -> Field {
+        let mont = Montgomery::new(C::P);
+        let b = mont.to_mont(C::B);
+        Field {
+            mont,
+            a: mont.to_mont(C::A),
+            b,
+            b3: mont.add(mont.add(b, b), b),
+            p_minus_2: C::P - U512::from_u64(2),
+        }
+    }
+
+    fn inv(&self, x: U512) -> U512 {
+        self.mont.pow(x, self.p_minus_2)
+    }
+
+    // x^3 + ax + b for x in Montgomery form
+    fn curve_rhs(&self, x: U512) -> U512 {
+        let m = &self.mont;
+        // (x^2 + a) * x + b
+        m.add(m.mul(m.add(m.square(x), self.a), x), self.b)
+    }
+
+    // A square root of x in Montgomery form, or None if x isn't a square.
+    // Either root may be returned. Not constant time.
+    fn sqrt(&self, x: U512) -> Option<U512> {
+        let m = &self.mont;
+        if x.is_zero() {
+            return Some(x);
+        }
+        let one = U512::from_u64(1);
+        let p = m.modulus();
+        let root = if p.bit(1) {
+            // p = 3 mod 4, so x^((p + 1) / 4) squares to x^((p - 1) / 2) * x,
+            // which is x when x is a square
+            m.pow(x, (p + one) >> 2)
+        } else {
+            // Tonelli-Shanks, with p - 1 = q * 2^s for odd q
+            let mut s = 1;
+            while !(p - one).bit(s) {
+                s += 1;
+            }
+            let q = (p - one) >> s;
+
+            // Any non-square will do
+            let mut z = m.add(m.one(), m.one());
+            while m.pow(z, (p - one) >> 1) == m.one() {
+                z = m.add(z, m.one());
+            }
+
+            let mut c = m.pow(z, q);
+            let mut t = m.pow(x, q);
+            let mut root = m.pow(x, (q + one) >> 1);
+            while t != m.one() {
+                // The least i with t^(2^i) = 1, which is s when x isn't a
+                // square
+                let mut i = 0;
+                let mut t2i = t;
+                while t2i != m.one() {
+                    t2i = m.square(t2i);
+                    i += 1;
+                    if i == s {
+                        return None;
+                    }
+                }
+                let mut b = c;
+                for _ in 0..s - i - 1 {
+                    b = m.square(b);
+                }
+                root = m.mul(root, b);
+                c = m.square(b);
+                t = m.mul(t, c);
+                s = i;
+            }
+            root
+        };
+
+        if m.square(root) == x {
+            Some(root)
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn check_sqrt(p: U512) {
+        let mont = Montgomery::new(p);
+        let field = Field {
+            mont,
+            a: U512::zero(),
+            b: U512::zero(),
+            b3: U512::zero(),
+            p_minus_2: p - U512::from_u64(2),
+        };
+        let mut non_squares = 0;
+        for idx in 1..50 {
+            let x = mont.to_mont(U512::from_u64(idx));
+            let root = field.sqrt(mont.square(x)).unwrap();
+            assert!(root == x || root == mont.neg(x));
+            match field.sqrt(x) {
+                Some(root) => assert_eq!(mont.square(root), x),
+                None => non_squares += 1,
+            }
+        }
+        assert!(non_squares > 0);
+        assert_eq!(field.sqrt(U512::zero()), Some(U512::zero()));
+    }
+
+    #[test]
+    fn sqrt() {
+        // 3 mod 4
+        check_sqrt(<P256 as Curve>::P);
+        // 5 mod 8, and P-224's prime, which is 1 mod 2^96
+        check_sqrt(U512::from_hex_be(b"7fffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffed"));
+        check_sqrt(U512::from_hex_be(b"ffffffffffffffffffffffffffffffff000000000000000000000001"));
+    }
+}
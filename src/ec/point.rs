@@ -0,0 +1,524 @@
+use std::cmp;
+use std::marker::PhantomData;
+use std::ops;
+
+use bigint::U512;
+use ct::{mul_fixed_window, WindowPoint};
+use super::Curve;
+
+/// A point in affine coordinates (x, y), or the point at infinity. The
+/// coordinates are plain integers mod p.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct AffinePoint<C: Curve> {
+    // Both zero for the point at infinity
+    x: U512,
+    y: U512,
+    infinity: bool,
+    curve: PhantomData<C>,
+}
+
+/// A point in Jacobian coordinates, (X : Y : Z) for the affine point
+/// (X / Z^2, Y / Z^3). Doubling is cheap, but addition has to check for the
+/// special cases, so these are for public values only.
+#[derive(Copy, Clone, Debug)]
+pub struct JacobianPoint<C: Curve> {
+    // In Montgomery form, with Z = 0 for the point at infinity
+    x: U512,
+    y: U512,
+    z: U512,
+    curve: PhantomData<C>,
+}
+
+/// A point in homogeneous projective coordinates, (X : Y : Z) for the affine
+/// point (X / Z, Y / Z). Uses the complete formulas of Renes, Costello and
+/// Batina, which give the right answer for every pair of inputs, including
+/// the point at infinity and adding a point to itself, without branching.
+#[derive(Copy, Clone, Debug)]
+pub struct ProjectivePoint<C: Curve> {
+    // In Montgomery form, with Z = 0 for the point at infinity
+    x: U512,
+    y: U512,
+    z: U512,
+    curve: PhantomData<C>,
+}
+
+impl<C: Curve> AffinePoint<C> {
+    pub fn identity() -> AffinePoint<C> {
+        AffinePoint {
+            x: U512::zero(),
+            y: U512::zero(),
+            infinity: true,
+            curve: PhantomData,
+        }
+    }
+
+    pub fn generator() -> AffinePoint<C> {
+        AffinePoint {
+            x: C::GX,
+            y: C::GY,
+            infinity: false,
+            curve: PhantomData,
+        }
+    }
+
+    /// The point (x, y), or None if it isn't on the curve.
+    pub fn new(x: U512, y: U512) -> Option<AffinePoint<C>> {
+        let point = AffinePoint {
+            x,
+            y,
+            infinity: false,
+            curve: PhantomData,
+        };
+        if point.is_on_curve() {
+            Some(point)
+        } else {
+            None
+        }
+    }
+
+    /// (x, y), or None for the point at infinity.
+    pub fn coordinates(&self) -> Option<(U512, U512)> {
+        if self.infinity {
+            None
+        } else {
+            Some((self.x, self.y))
+        }
+    }
+
+    pub fn is_identity(&self) -> bool {
+        self.infinity
+    }
+
+    /// Whether the coordinates are reduced mod p and satisfy the curve
+    /// equation. The point at infinity is always on the curve.
+    pub fn is_on_curve(&self) -> bool {
+        if self.infinity {
+            return true;
+        }
+        if self.x >= C::P || self.y >= C::P {
+            return false;
+        }
+
+        let f = C::field();
+        let m = &f.mont;
+        m.square(m.to_mont(self.y)) == f.curve_rhs(m.to_mont(self.x))
+    }
+
+    /// k * G for the generator G, in constant time, using the curve's
+    /// `FixedBaseTable`. This is several times faster than `mul` once the
+    /// table is built, which happens on first use.
+    pub fn mul_generator(k: U512) -> AffinePoint<C> {
+        C::base_table().mul(k).to_affine()
+    }
+
+    /// k * self, in constant time. See `ProjectivePoint::mul`.
+    pub fn mul(&self, k: U512) -> AffinePoint<C> {
+        ProjectivePoint::from(*self).mul(k).to_affine()
+    }
+}
+
+impl<C: Curve> ops::Neg for AffinePoint<C> {
+    type Output = AffinePoint<C>;
+    fn neg(self) -> AffinePoint<C> {
+        AffinePoint {
+            y: C::field().mont.neg(self.y),
+            ..self
+        }
+    }
+}
+
+impl<C: Curve> JacobianPoint<C> {
+    pub fn identity() -> JacobianPoint<C> {
+        let one = C::field().mont.one();
+        JacobianPoint {
+            x: one,
+            y: one,
+            z: U512::zero(),
+            curve: PhantomData,
+        }
+    }
+
+    pub fn is_identity(&self) -> bool {
+        self.z.is_zero()
+    }
+
+    /// The affine point, which costs an inversion.
+    pub fn to_affine(&self) -> AffinePoint<C> {
+        if self.is_identity() {
+            return AffinePoint::identity();
+        }
+        let f = C::field();
+        let m = &f.mont;
+        let z_inv = f.inv(self.z);
+        let z_inv2 = m.square(z_inv);
+        AffinePoint {
+            x: m.from_mont(m.mul(self.x, z_inv2)),
+            y: m.from_mont(m.mul(self.y, m.mul(z_inv2, z_inv))),
+            infinity: false,
+            curve: PhantomData,
+        }
+    }
+
+    /// 2 * self, with the dbl-2007-bl formulas.
+    pub fn double(&self) -> JacobianPoint<C> {
+        let f = C::field();
+        let m = &f.mont;
+        let xx = m.square(self.x);
+        let yy = m.square(self.y);
+        let yyyy = m.square(yy);
+        let zz = m.square(self.z);
+        // s = 4 * x * y^2
+        let s = m.sub(m.sub(m.square(m.add(self.x, yy)), xx), yyyy);
+        let s = m.add(s, s);
+        // The slope of the tangent is mm / 2yz
+        let mm = m.add(m.add(m.add(xx, xx), xx), m.mul(f.a, m.square(zz)));
+        let x = m.sub(m.square(mm), m.add(s, s));
+        let yyyy8 = m.add(yyyy, yyyy);
+        let yyyy8 = m.add(yyyy8, yyyy8);
+        let yyyy8 = m.add(yyyy8, yyyy8);
+        let y = m.sub(m.mul(mm, m.sub(s, x)), yyyy8);
+        // 2 * y * z, which is zero for the point at infinity
+        let z = m.sub(m.sub(m.square(m.add(self.y, self.z)), yy), zz);
+        JacobianPoint { x, y, z, curve: PhantomData }
+    }
+
+    // (factor * x, y) for factor in Montgomery form. This is only a point
+    // on the curve for special factors, like a cube root of unity when a = 0.
+    pub(super) fn scale_x(&self, factor: U512) -> JacobianPoint<C> {
+        JacobianPoint {
+            x: C::field().mont.mul(self.x, factor),
+            ..*self
+        }
+    }
+
+    /// k * self by double and add over the width 5 NAF of k, which adds
+    /// one of the odd multiples up to 15 * self for about one bit in six.
+    /// Not constant time.
+    pub fn mul_vartime(&self, k: U512) -> JacobianPoint<C> {
+        // table[i] = (2i + 1) * self
+        let double = self.double();
+        let mut table = [*self; 8];
+        for idx in 1..table.len() {
+            table[idx] = table[idx - 1] + double;
+        }
+
+        let mut ans = JacobianPoint::identity();
+        for &digit in k.to_wnaf(5).iter().rev() {
+            ans = ans.double();
+            if digit > 0 {
+                ans = ans + table[digit as usize / 2];
+            } else if digit < 0 {
+                ans = ans + -table[digit.unsigned_abs() as usize / 2];
+            }
+        }
+        ans
+    }
+}
+
+impl<C: Curve> From<AffinePoint<C>> for JacobianPoint<C> {
+    fn from(point: AffinePoint<C>) -> JacobianPoint<C> {
+        if point.infinity {
+            return JacobianPoint::identity();
+        }
+        let m = &C::field().mont;
+        JacobianPoint {
+            x: m.to_mont(point.x),
+            y: m.to_mont(point.y),
+            z: m.one(),
+            curve: PhantomData,
+        }
+    }
+}
+
+// The add-2007-bl formulas, which don't work when either point is at infinity
+// or when the points have the same x coordinate, so those cases are checked
+// first.
+impl<C: Curve> ops::Add for JacobianPoint<C> {
+    type Output = JacobianPoint<C>;
+    fn add(self, rhs: JacobianPoint<C>) -> JacobianPoint<C> {
+        if self.is_identity() {
+            return rhs;
+        }
+        if rhs.is_identity() {
+            return self;
+        }
+
+        let f = C::field();
+        let m = &f.mont;
+        let z1z1 = m.square(self.z);
+        let z2z2 = m.square(rhs.z);
+        let u1 = m.mul(self.x, z2z2);
+        let u2 = m.mul(rhs.x, z1z1);
+        let s1 = m.mul(self.y, m.mul(rhs.z, z2z2));
+        let s2 = m.mul(rhs.y, m.mul(self.z, z1z1));
+        let h = m.sub(u2, u1);
+        let r = m.sub(s2, s1);
+        if h.is_zero() {
+            return if r.is_zero() {
+                self.double()
+            } else {
+                JacobianPoint::identity()
+            };
+        }
+
+        let r = m.add(r, r);
+        let i = m.square(m.add(h, h));
+        let j = m.mul(h, i);
+        let v = m.mul(u1, i);
+        let x = m.sub(m.sub(m.square(r), j), m.add(v, v));
+        let s1j = m.mul(s1, j);
+        let y = m.sub(m.mul(r, m.sub(v, x)), m.add(s1j, s1j));
+        let z = m.mul(m.sub(m.sub(m.square(m.add(self.z, rhs.z)), z1z1), z2z2), h);
+        JacobianPoint { x, y, z, curve: PhantomData }
+    }
+}
+
+impl<C: Curve> ops::Neg for JacobianPoint<C> {
+    type Output = JacobianPoint<C>;
+    fn neg(self) -> JacobianPoint<C> {
+        JacobianPoint {
+            y: C::field().mont.neg(self.y),
+            ..self
+        }
+    }
+}
+
+// Equal as points, not as coordinates: X1 Z2^2 = X2 Z1^2 and
+// Y1 Z2^3 = Y2 Z1^3.
+impl<C: Curve> cmp::PartialEq for JacobianPoint<C> {
+    fn eq(&self, other: &JacobianPoint<C>) -> bool {
+        if self.is_identity() || other.is_identity() {
+            return self.is_identity() == other.is_identity();
+        }
+        let m = &C::field().mont;
+        let z1z1 = m.square(self.z);
+        let z2z2 = m.square(other.z);
+        m.mul(self.x, z2z2) == m.mul(other.x, z1z1)
+            && m.mul(self.y, m.mul(other.z, z2z2)) == m.mul(other.y, m.mul(self.z, z1z1))
+    }
+}
+
+impl<C: Curve> cmp::Eq for JacobianPoint<C> {}
+
+impl<C: Curve> ProjectivePoint<C> {
+    pub fn identity() -> ProjectivePoint<C> {
+        ProjectivePoint {
+            x: U512::zero(),
+            y: C::field().mont.one(),
+            z: U512::zero(),
+            curve: PhantomData,
+        }
+    }
+
+    pub fn is_identity(&self) -> bool {
+        self.z.is_zero()
+    }
+
+    /// The affine point, which costs an inversion.
+    pub fn to_affine(&self) -> AffinePoint<C> {
+        if self.is_identity() {
+            return AffinePoint::identity();
+        }
+        let f = C::field();
+        let m = &f.mont;
+        let z_inv = f.inv(self.z);
+        AffinePoint {
+            x: m.from_mont(m.mul(self.x, z_inv)),
+            y: m.from_mont(m.mul(self.y, z_inv)),
+            infinity: false,
+            curve: PhantomData,
+        }
+    }
+
+    /// 2 * self, with algorithm 3 of the complete formulas paper.
+    pub fn double(&self) -> ProjectivePoint<C> {
+        let f = C::field();
+        let m = &f.mont;
+        let t0 = m.square(self.x);
+        let t1 = m.square(self.y);
+        let t2 = m.square(self.z);
+        let t3 = m.mul(self.x, self.y);
+        let t3 = m.add(t3, t3);
+        let z3 = m.mul(self.x, self.z);
+        let z3 = m.add(z3, z3);
+        let x3 = m.mul(f.a, z3);
+        let y3 = m.add(x3, m.mul(f.b3, t2));
+        let x3 = m.sub(t1, y3);
+        let y3 = m.mul(x3, m.add(t1, y3));
+        let x3 = m.mul(t3, x3);
+        let z3 = m.mul(f.b3, z3);
+        let t2 = m.mul(f.a, t2);
+        let t3 = m.add(m.mul(f.a, m.sub(t0, t2)), z3);
+        let t0 = m.add(m.add(m.add(t0, t0), t0), t2);
+        let y3 = m.add(y3, m.mul(t0, t3));
+        let t2 = m.mul(self.y, self.z);
+        let t2 = m.add(t2, t2);
+        let x3 = m.sub(x3, m.mul(t2, t3));
+        let z3 = m.mul(t2, t1);
+        let z3 = m.add(z3, z3);
+        let z3 = m.add(z3, z3);
+        ProjectivePoint { x: x3, y: y3, z: z3, curve: PhantomData }
+    }
+
+    /// k * self in constant time, with a fixed window over as many bits as
+    /// the group order has. k is reduced mod the order first, which is the
+    /// only step that depends on k, and only for k that are too big anyway.
+    pub fn mul(&self, k: U512) -> ProjectivePoint<C> {
+        let k = if k >= C::N { k % C::N } else { k };
+        mul_fixed_window(self, k, C::N.bits())
+    }
+
+    // Sets self to other if choice is true, without branching on choice
+    pub(crate) fn conditional_assign(&mut self, other: &ProjectivePoint<C>, choice: bool) {
+        self.x.conditional_assign(&other.x, choice);
+        self.y.conditional_assign(&other.y, choice);
+        self.z.conditional_assign(&other.z, choice);
+    }
+}
+
+impl<C: Curve> From<AffinePoint<C>> for ProjectivePoint<C> {
+    fn from(point: AffinePoint<C>) -> ProjectivePoint<C> {
+        if point.infinity {
+            return ProjectivePoint::identity();
+        }
+        let m = &C::field().mont;
+        ProjectivePoint {
+            x: m.to_mont(point.x),
+            y: m.to_mont(point.y),
+            z: m.one(),
+            curve: PhantomData,
+        }
+    }
+}
+
+// Algorithm 1 of the complete formulas paper, for any a.
+impl<C: Curve> ops::Add for ProjectivePoint<C> {
+    type Output = ProjectivePoint<C>;
+    fn add(self, rhs: ProjectivePoint<C>) -> ProjectivePoint<C> {
+        let f = C::field();
+        let m = &f.mont;
+        let (x1, y1, z1) = (self.x, self.y, self.z);
+        let (x2, y2, z2) = (rhs.x, rhs.y, rhs.z);
+
+        let t0 = m.mul(x1, x2);
+        let t1 = m.mul(y1, y2);
+        let t2 = m.mul(z1, z2);
+        // t3 = x1 y2 + x2 y1, t4 = x1 z2 + x2 z1, t5 = y1 z2 + y2 z1
+        let t3 = m.sub(m.mul(m.add(x1, y1), m.add(x2, y2)), m.add(t0, t1));
+        let t4 = m.sub(m.mul(m.add(x1, z1), m.add(x2, z2)), m.add(t0, t2));
+        let t5 = m.sub(m.mul(m.add(y1, z1), m.add(y2, z2)), m.add(t1, t2));
+
+        let z3 = m.add(m.mul(f.b3, t2), m.mul(f.a, t4));
+        let x3 = m.sub(t1, z3);
+        let z3 = m.add(t1, z3);
+        let y3 = m.mul(x3, z3);
+        let t1 = m.add(m.add(t0, t0), t0);
+        let t2 = m.mul(f.a, t2);
+        let t4 = m.mul(f.b3, t4);
+        let t1 = m.add(t1, t2);
+        let t2 = m.mul(f.a, m.sub(t0, t2));
+        let t4 = m.add(t4, t2);
+        let y3 = m.add(y3, m.mul(t1, t4));
+        let x3 = m.sub(m.mul(t3, x3), m.mul(t5, t4));
+        let z3 = m.add(m.mul(t5, z3), m.mul(t3, t1));
+        ProjectivePoint { x: x3, y: y3, z: z3, curve: PhantomData }
+    }
+}
+
+impl<C: Curve> ops::Neg for ProjectivePoint<C> {
+    type Output = ProjectivePoint<C>;
+    fn neg(self) -> ProjectivePoint<C> {
+        ProjectivePoint {
+            y: C::field().mont.neg(self.y),
+            ..self
+        }
+    }
+}
+
+impl<C: Curve> WindowPoint for ProjectivePoint<C> {
+    fn identity() -> ProjectivePoint<C> {
+        ProjectivePoint::identity()
+    }
+
+    fn double(&self) -> ProjectivePoint<C> {
+        ProjectivePoint::double(self)
+    }
+
+    fn conditional_assign(&mut self, other: &ProjectivePoint<C>, choice: bool) {
+        ProjectivePoint::conditional_assign(self, other, choice)
+    }
+}
+
+// Equal as points: X1 Z2 = X2 Z1 and Y1 Z2 = Y2 Z1. This also works for the
+// point at infinity, whose Y is never zero.
+impl<C: Curve> cmp::PartialEq for ProjectivePoint<C> {
+    fn eq(&self, other: &ProjectivePoint<C>) -> bool {
+        let m = &C::field().mont;
+        m.mul(self.x, other.z) == m.mul(other.x, self.z)
+            && m.mul(self.y, other.z) == m.mul(other.y, self.z)
+    }
+}
+
+impl<C: Curve> cmp::Eq for ProjectivePoint<C> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ec::{P256, P384, Secp256k1};
+
+    fn check_group<C: Curve>() {
+        let g = AffinePoint::<C>::generator();
+        assert!(g.is_on_curve());
+        assert!(!(-g).is_identity() && (-g).is_on_curve());
+        assert_eq!(AffinePoint::new(C::GX, C::GY), Some(g));
+        assert_eq!(AffinePoint::<C>::new(C::GX, C::GY + U512::from_u64(1)), None);
+
+        // n * G is the identity, and (n - 1) * G = -G
+        let n_minus_1 = C::N - U512::from_u64(1);
+        assert!(g.mul(C::N).is_identity());
+        assert_eq!(g.mul(n_minus_1), -g);
+        let jac = JacobianPoint::from(g);
+        assert!(jac.mul_vartime(C::N).is_identity());
+        assert_eq!(jac.mul_vartime(n_minus_1), -jac);
+
+        // The complete formulas handle every special case of addition
+        let proj = ProjectivePoint::from(g);
+        let id = ProjectivePoint::<C>::identity();
+        assert_eq!(proj + id, proj);
+        assert_eq!(id + proj, proj);
+        assert!((id + id).is_identity() && id.double().is_identity());
+        assert!((proj + -proj).is_identity());
+        assert_eq!(proj + proj, proj.double());
+        assert_eq!(JacobianPoint::from(g) + JacobianPoint::from(g), jac.double());
+        assert!((jac + -jac).is_identity());
+
+        // Both coordinate systems agree
+        let mut p = proj;
+        let mut j = jac;
+        for _ in 0..10 {
+            p = p.double() + proj;
+            j = j.double() + jac;
+            assert_eq!(p.to_affine(), j.to_affine());
+            assert!(p.to_affine().is_on_curve());
+        }
+        let k = U512::from_hex_be(b"c51e4753afdec1e6b6c6a5b992f43f8dd0c7a8933072708b6522468b2ffb06fd");
+        assert_eq!(g.mul(k), jac.mul_vartime(k).to_affine());
+        assert_eq!(g.mul(k + C::N), g.mul(k));
+        assert!(g.mul(U512::zero()).is_identity());
+    }
+
+    #[test]
+    fn secp256k1() {
+        check_group::<Secp256k1>();
+    }
+
+    #[test]
+    fn p256() {
+        check_group::<P256>();
+    }
+
+    #[test]
+    fn p384() {
+        check_group::<P384>();
+    }
+}
@@ -0,0 +1,73 @@
+//! Point encoding from SEC 1 section 2.3.3. Coordinates are big endian and
+//! as long as the field prime, after a tag byte: 0x04 for x and y, 0x02 or
+//! 0x03 for x alone with the parity of y, and 0x06 or 0x07 for the "hybrid"
+//! form with both. The point at infinity is the single byte 0x00.
+
+use bigint::U512;
+use super::{AffinePoint, Curve};
+
+// The length of an encoded coordinate
+fn coordinate_len<C: Curve>() -> usize {
+    C::P.bits().div_ceil(8)
+}
+
+fn to_bytes<C: Curve>(x: U512) -> Vec<u8> {
+    x.to_bytes_be()[64 - coordinate_len::<C>()..].to_vec()
+}
+
+impl<C: Curve> AffinePoint<C> {
+    /// The SEC 1 encoding of this point, compressed to x and one bit of y,
+    /// or uncompressed. Hybrid encodings are accepted by `from_sec1_bytes`
+    /// but never produced.
+    pub fn to_sec1_bytes(&self, compressed: bool) -> Vec<u8> {
+        let (x, y) = match self.coordinates() {
+            Some(coordinates) => coordinates,
+            None => return vec![0],
+        };
+        if compressed {
+            let mut out = vec![2 | y.bit(0) as u8];
+            out.extend(to_bytes::<C>(x));
+            out
+        } else {
+            let mut out = vec![4];
+            out.extend(to_bytes::<C>(x));
+            out.extend(to_bytes::<C>(y));
+            out
+        }
+    }
+
+    /// The point with this SEC 1 encoding, in any of the forms. None if the
+    /// length or tag is wrong, a coordinate isn't less than p, the point
+    /// isn't on the curve, or a hybrid encoding has the wrong parity for y.
+    pub fn from_sec1_bytes(bytes: &[u8]) -> Option<AffinePoint<C>> {
+        let len = coordinate_len::<C>();
+        let (&tag, rest) = bytes.split_first()?;
+        match (tag, rest.len()) {
+            (0, 0) => Some(AffinePoint::identity()),
+            (2, l) | (3, l) if l == len => decompress(U512::from_bytes_be(rest.to_vec()), tag & 1 == 1),
+            (4, l) | (6, l) | (7, l) if l == 2 * len => {
+                let x = U512::from_bytes_be(rest[..len].to_vec());
+                let y = U512::from_bytes_be(rest[len..].to_vec());
+                if tag != 4 && y.bit(0) != (tag & 1 == 1) {
+                    return None;
+                }
+                AffinePoint::new(x, y)
+            }
+            _ => None,
+        }
+    }
+}
+
+// The point with this x coordinate and the parity of y, solving the curve
+// equation for y
+fn decompress<C: Curve>(x: U512, odd: bool) -> Option<AffinePoint<C>> {
+    if x >= C::P {
+        return None;
+    }
+    let f = C::field();
+    let m = &f.mont;
+    let y = m.from_mont(f.sqrt(f.curve_rhs(m.to_mont(x)))?);
+    // If y is zero there's no odd choice, and p - 0 is rejected by new
+    let y = if y.bit(0) == odd { y } else { C::P - y };
+    AffinePoint::new(x, y)
+}
@@ -0,0 +1,95 @@
+//! Precomputed multiples of a fixed point, so that multiplying it needs no
+//! doublings at all. Worth it for the generator, which key generation and
+//! signing multiply every time.
+
+use bigint::U512;
+use ct::lookup;
+use super::{AffinePoint, Curve, ProjectivePoint};
+
+// Scalars are recoded into signed digits of this many bits
+const WINDOW: usize = 4;
+// Entries per row, for digit magnitudes 1 to 2^(WINDOW - 1)
+const ROW_LEN: usize = 1 << (WINDOW - 1);
+
+/// The multiples of a fixed point P needed to multiply it by any scalar. Row
+/// i holds j * 16^i * P for j from 1 to 8. A scalar is split into signed
+/// base 16 digits with `U512::to_signed_window`, and k * P is the sum of
+/// one entry from each row, negated for negative digits.
+///
+/// Every lookup reads the whole row, and the recoding doesn't branch on the
+/// scalar, so multiplication is constant time.
+#[derive(Clone, Debug)]
+pub struct FixedBaseTable<C: Curve> {
+    rows: Vec<[ProjectivePoint<C>; ROW_LEN]>,
+}
+
+impl<C: Curve> FixedBaseTable<C> {
+    pub fn new(point: &AffinePoint<C>) -> FixedBaseTable<C> {
+        // A digit for each window of a scalar below n, and one more for the
+        // carry out of the top window
+        let count = C::N.bits().div_ceil(WINDOW) + 1;
+        let mut rows = Vec::with_capacity(count);
+        let mut base = ProjectivePoint::from(*point);
+        for _ in 0..count {
+            let mut row = [base; ROW_LEN];
+            for idx in 1..ROW_LEN {
+                row[idx] = row[idx - 1] + base;
+            }
+            // 16 * base is 2 * (8 * base)
+            base = row[ROW_LEN - 1].double();
+            rows.push(row);
+        }
+        FixedBaseTable { rows }
+    }
+
+    /// k * P, in constant time. k is reduced mod n first.
+    pub fn mul(&self, k: U512) -> ProjectivePoint<C> {
+        let k = if k >= C::N { k % C::N } else { k };
+        // Digits past the last row are zero, since k < n
+        let digits = k.to_signed_window(WINDOW);
+
+        let mut ans = ProjectivePoint::identity();
+        for (row, &digit) in self.rows.iter().zip(digits.iter()) {
+            let magnitude = digit.unsigned_abs() as usize;
+            // A zero digit matches no entry and leaves the identity
+            let mut entry = lookup(row, magnitude.wrapping_sub(1));
+            let negated = -entry;
+            entry.conditional_assign(&negated, digit < 0);
+            ans = ans + entry;
+        }
+        ans
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{thread_rng, Rng};
+
+    use super::*;
+    use ec::{P256, P384, Secp256k1};
+
+    fn check_table<C: Curve>() {
+        let mut rng = thread_rng();
+        let g = AffinePoint::<C>::generator();
+        let p = g.mul(U512::from_u64(0x5eed));
+        let table = FixedBaseTable::new(&p);
+
+        let mut scalars = vec![U512::zero(), U512::from_u64(1), U512::from_u64(8), U512::from_u64(9),
+                               C::N - U512::from_u64(1), C::N, C::N + U512::from_u64(3), !U512::zero()];
+        for _ in 0..10 {
+            scalars.push(U512::random_in_range(U512::zero(), C::N, &mut rng));
+            scalars.push(rng.gen());
+        }
+        for &k in scalars.iter() {
+            assert_eq!(table.mul(k).to_affine(), p.mul(k));
+            assert_eq!(AffinePoint::<C>::mul_generator(k), g.mul(k));
+        }
+    }
+
+    #[test]
+    fn matches_variable_base() {
+        check_table::<Secp256k1>();
+        check_table::<P256>();
+        check_table::<P384>();
+    }
+}
@@ -0,0 +1,125 @@
+//! Elliptic curve Diffie-Hellman over the curves in `ec`, and ECIES hybrid
+//! encryption built on it.
+
+use std::marker::PhantomData;
+
+use rand::Rng;
+
+use bigint::U512;
+use ct::ct_eq;
+use ec::{AffinePoint, Curve};
+use hash::{hkdf_expand, hkdf_extract, hmac, Digest};
+
+/// The shared secret between private_key and the peer's public key: the x
+/// coordinate of private_key * peer, big endian and as long as the field
+/// prime. None if the peer's key is the point at infinity or isn't on the
+/// curve. The multiplication is constant time.
+pub fn ecdh<C: Curve>(private_key: U512, peer: &AffinePoint<C>) -> Option<Vec<u8>> {
+    assert!(!private_key.is_zero() && private_key < C::N, "private key must be in [1, n)");
+    if peer.is_identity() || !peer.is_on_curve() {
+        return None;
+    }
+    // The cofactor is 1, so this is only the identity if private_key is 0
+    let (x, _) = peer.mul(private_key).coordinates()?;
+    Some(x.to_bytes_be()[64 - coordinate_len::<C>()..].to_vec())
+}
+
+fn coordinate_len<C: Curve>() -> usize {
+    C::P.bits().div_ceil(8)
+}
+
+/// A symmetric cipher for the body of an ECIES message. Every message gets
+/// a fresh key, so ciphers don't need a nonce.
+pub trait Cipher {
+    const KEY_LEN: usize;
+
+    fn encrypt(key: &[u8], plaintext: &[u8]) -> Vec<u8>;
+    /// None if the ciphertext is malformed, or fails the cipher's own
+    /// authentication if it has any.
+    fn decrypt(key: &[u8], ciphertext: &[u8]) -> Option<Vec<u8>>;
+}
+
+/// A stream cipher that XORs the data with HMAC(key, counter) for a 64 bit
+/// big endian block counter. It has no authentication of its own, which
+/// ECIES adds with its MAC.
+#[derive(Copy, Clone, Debug)]
+pub struct HmacStream<D: Digest> {
+    digest: PhantomData<D>,
+}
+
+impl<D: Digest> HmacStream<D> {
+    fn apply(key: &[u8], data: &[u8]) -> Vec<u8> {
+        let mut out = Vec::with_capacity(data.len());
+        for (counter, chunk) in data.chunks(D::OUTPUT_LEN).enumerate() {
+            let block = hmac::<D>(key, &(counter as u64).to_be_bytes());
+            out.extend(chunk.iter().zip(block.as_ref()).map(|(a, b)| a ^ b));
+        }
+        out
+    }
+}
+
+impl<D: Digest> Cipher for HmacStream<D> {
+    const KEY_LEN: usize = D::OUTPUT_LEN;
+
+    fn encrypt(key: &[u8], plaintext: &[u8]) -> Vec<u8> {
+        HmacStream::<D>::apply(key, plaintext)
+    }
+
+    fn decrypt(key: &[u8], ciphertext: &[u8]) -> Option<Vec<u8>> {
+        Some(HmacStream::<D>::apply(key, ciphertext))
+    }
+}
+
+/// ECIES on curve C, with D for HKDF and HMAC and S for the message body.
+/// A ciphertext is an uncompressed ephemeral public key, the body, and an
+/// HMAC of the body. The cipher and MAC keys come from HKDF over the ECDH
+/// shared secret, with the ephemeral key as the info string.
+#[derive(Copy, Clone, Debug)]
+pub struct Ecies<C: Curve, D: Digest, S: Cipher> {
+    params: PhantomData<(C, D, S)>,
+}
+
+impl<C: Curve, D: Digest, S: Cipher> Ecies<C, D, S> {
+    /// Encrypts plaintext to public_key, or None if the key is the point at
+    /// infinity or isn't on the curve.
+    pub fn encrypt<R: Rng>(public_key: &AffinePoint<C>, plaintext: &[u8], rng: &mut R) -> Option<Vec<u8>> {
+        let ephemeral = U512::random_in_range(U512::from_u64(1), C::N, rng);
+        let ephemeral_public = AffinePoint::<C>::mul_generator(ephemeral).to_sec1_bytes(false);
+        let secret = ecdh(ephemeral, public_key)?;
+        let (cipher_key, mac_key) = Ecies::<C, D, S>::keys(&secret, &ephemeral_public);
+
+        let body = S::encrypt(&cipher_key, plaintext);
+        let tag = hmac::<D>(&mac_key, &body);
+        let mut out = ephemeral_public;
+        out.extend(body);
+        out.extend_from_slice(tag.as_ref());
+        Some(out)
+    }
+
+    /// The plaintext, or None if the ciphertext wasn't made for this key or
+    /// has been changed.
+    pub fn decrypt(private_key: U512, ciphertext: &[u8]) -> Option<Vec<u8>> {
+        let point_len = 1 + 2 * coordinate_len::<C>();
+        if ciphertext.len() < point_len + D::OUTPUT_LEN {
+            return None;
+        }
+        let (ephemeral_public, rest) = ciphertext.split_at(point_len);
+        let (body, tag) = rest.split_at(rest.len() - D::OUTPUT_LEN);
+
+        let ephemeral = AffinePoint::<C>::from_sec1_bytes(ephemeral_public)?;
+        let secret = ecdh(private_key, &ephemeral)?;
+        let (cipher_key, mac_key) = Ecies::<C, D, S>::keys(&secret, ephemeral_public);
+        if !ct_eq(hmac::<D>(&mac_key, body).as_ref(), tag) {
+            return None;
+        }
+        S::decrypt(&cipher_key, body)
+    }
+
+    // The cipher key and the MAC key
+    fn keys(secret: &[u8], ephemeral_public: &[u8]) -> (Vec<u8>, Vec<u8>) {
+        let prk = hkdf_extract::<D>(&[], secret);
+        let mut okm = hkdf_expand::<D>(prk.as_ref(), ephemeral_public, S::KEY_LEN + D::OUTPUT_LEN);
+        let mac_key = okm.split_off(S::KEY_LEN);
+        (okm, mac_key)
+    }
+}
@@ -0,0 +1,214 @@
+//! ECDSA signatures from FIPS 186-4 over the curves in `ec`, with the
+//! deterministic nonces of RFC 6979. Signing and verifying take the hash of
+//! the message rather than the message, so any hash function can be used.
+
+use std::marker::PhantomData;
+
+use rand::Rng;
+
+use bigint::{Montgomery, U512};
+use ec::{AffinePoint, Curve, JacobianPoint};
+use hash::{hmac, Digest, Hmac};
+
+/// An ECDSA signature (r, s), where both are in [1, n) for the curve order n.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Signature<C: Curve> {
+    r: U512,
+    s: U512,
+    curve: PhantomData<C>,
+}
+
+impl<C: Curve> Signature<C> {
+    /// The signature (r, s), or None if either is out of range.
+    pub fn new(r: U512, s: U512) -> Option<Signature<C>> {
+        let zero = U512::zero();
+        if r == zero || s == zero || r >= C::N || s >= C::N {
+            return None;
+        }
+        Some(Signature { r, s, curve: PhantomData })
+    }
+
+    pub fn r(&self) -> U512 {
+        self.r
+    }
+
+    pub fn s(&self) -> U512 {
+        self.s
+    }
+
+    /// Whether s is at most n / 2. (r, n - s) is just as valid as (r, s), so
+    /// Bitcoin only accepts the low s form to make signatures unique.
+    pub fn is_low_s(&self) -> bool {
+        self.s <= C::N >> 1
+    }
+
+    /// The low s form of this signature, which verifies the same way.
+    pub fn normalize_s(&self) -> Signature<C> {
+        if self.is_low_s() {
+            *self
+        } else {
+            Signature { s: C::N - self.s, ..*self }
+        }
+    }
+}
+
+/// A random private key in [1, n).
+pub fn generate_key<C: Curve, R: Rng>(rng: &mut R) -> U512 {
+    U512::random_in_range(U512::from_u64(1), C::N, rng)
+}
+
+/// The public key d * G for the private key d, in constant time.
+pub fn public_key<C: Curve>(private_key: U512) -> AffinePoint<C> {
+    assert!(is_valid_key::<C>(private_key), "private key must be in [1, n)");
+    AffinePoint::mul_generator(private_key)
+}
+
+/// Signs message_hash, which must be the output of D, with the nonce chosen
+/// as in RFC 6979 using HMAC with D. Signing the same hash with the same key
+/// always gives the same signature. The nonce is multiplied in constant
+/// time.
+pub fn sign<C: Curve, D: Digest>(private_key: U512, message_hash: &[u8]) -> Signature<C> {
+    assert!(is_valid_key::<C>(private_key), "private key must be in [1, n)");
+    let m = Montgomery::new(C::N);
+    let len = byte_len(C::N);
+    let h = U512::from_hash_be(message_hash, C::N);
+
+    let mut nonces = NonceGenerator::<D>::new(&int2octets(C::N, private_key), &int2octets(C::N, h));
+    loop {
+        let k = bits2int(C::N, &nonces.generate(len));
+        if !is_valid_key::<C>(k) {
+            nonces.reject();
+            continue;
+        }
+
+        // k is in [1, n), so k * G isn't the point at infinity
+        let (x, _) = AffinePoint::<C>::mul_generator(k).coordinates().unwrap();
+        let r = m.reduce_512(x);
+
+        // s = (h + r * d) / k, inverting k by Fermat's little theorem
+        let k_inv = m.pow_ct(m.to_mont(k), C::N - U512::from_u64(2));
+        let s = m.mul(k_inv, m.add(h, m.mul(m.to_mont(r), private_key)));
+        if let Some(signature) = Signature::new(r, s) {
+            return signature;
+        }
+        nonces.reject();
+    }
+}
+
+/// `sign`, but always giving the low s form of the signature, as Bitcoin
+/// needs for secp256k1.
+pub fn sign_low_s<C: Curve, D: Digest>(private_key: U512, message_hash: &[u8]) -> Signature<C> {
+    sign::<C, D>(private_key, message_hash).normalize_s()
+}
+
+/// Whether signature is a valid signature of message_hash under public_key.
+/// Both low and high s forms are accepted.
+pub fn verify<C: Curve>(public_key: &AffinePoint<C>, message_hash: &[u8], signature: &Signature<C>) -> bool {
+    if public_key.is_identity() {
+        return false;
+    }
+
+    let m = Montgomery::new(C::N);
+    let h = U512::from_hash_be(message_hash, C::N);
+    let s_inv = m.pow(m.to_mont(signature.s), C::N - U512::from_u64(2));
+    let u1 = m.mul(s_inv, h);
+    let u2 = m.mul(s_inv, signature.r);
+
+    let g = JacobianPoint::from(AffinePoint::<C>::generator());
+    let q = JacobianPoint::from(*public_key);
+    match (g.mul_vartime(u1) + q.mul_vartime(u2)).to_affine().coordinates() {
+        Some((x, _)) => m.reduce_512(x) == signature.r,
+        None => false,
+    }
+}
+
+fn is_valid_key<C: Curve>(k: U512) -> bool {
+    !k.is_zero() && k < C::N
+}
+
+// The length of the order n in bytes
+pub(crate) fn byte_len(n: U512) -> usize {
+    n.bits().div_ceil(8)
+}
+
+// The leftmost bits of bytes as an integer, as many as the order n has,
+// from RFC 6979 section 2.3.2.
+pub(crate) fn bits2int(n: U512, bytes: &[u8]) -> U512 {
+    let bits = n.bits();
+    let len = bytes.len().min(byte_len(n));
+    let x = U512::from_bytes_be(bytes[..len].to_vec());
+    if len * 8 > bits {
+        x >> (len * 8 - bits)
+    } else {
+        x
+    }
+}
+
+// x as a big endian string the length of the order n
+pub(crate) fn int2octets(n: U512, x: U512) -> Vec<u8> {
+    x.to_bytes_be()[64 - byte_len(n)..].to_vec()
+}
+
+// The HMAC_DRBG from RFC 6979 section 3.2, seeded with the private key and
+// the reduced message hash. DSA uses it too.
+pub(crate) struct NonceGenerator<D: Digest> {
+    k: Vec<u8>,
+    v: Vec<u8>,
+    digest: PhantomData<D>,
+}
+
+impl<D: Digest> NonceGenerator<D> {
+    pub(crate) fn new(key: &[u8], hash: &[u8]) -> NonceGenerator<D> {
+        let mut drbg = NonceGenerator {
+            k: vec![0; D::OUTPUT_LEN],
+            v: vec![1; D::OUTPUT_LEN],
+            digest: PhantomData,
+        };
+        drbg.update(&[&[0], key, hash]);
+        drbg.update(&[&[1], key, hash]);
+        drbg
+    }
+
+    // K = HMAC_K(V || parts), then V = HMAC_K(V)
+    fn update(&mut self, parts: &[&[u8]]) {
+        let mut mac = Hmac::<D>::new(&self.k);
+        mac.update(&self.v);
+        for part in parts {
+            mac.update(part);
+        }
+        self.k = mac.finalize().as_ref().to_vec();
+        self.v = hmac::<D>(&self.k, &self.v).as_ref().to_vec();
+    }
+
+    // The next len bytes of output
+    pub(crate) fn generate(&mut self, len: usize) -> Vec<u8> {
+        let mut out = Vec::with_capacity(len + D::OUTPUT_LEN);
+        while out.len() < len {
+            self.v = hmac::<D>(&self.k, &self.v).as_ref().to_vec();
+            out.extend_from_slice(&self.v);
+        }
+        out.truncate(len);
+        out
+    }
+
+    // Moves on after output that didn't give a usable nonce
+    pub(crate) fn reject(&mut self) {
+        self.update(&[&[0]]);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ec::{P256, P384};
+
+    #[test]
+    fn bits2int_truncates() {
+        let hash = [0xffu8; 64];
+        // Only the leftmost 256 and 384 bits are kept
+        assert_eq!(bits2int(P256::N, &hash), (U512::from_u64(1) << 256) - U512::from_u64(1));
+        assert_eq!(bits2int(P384::N, &hash), (U512::from_u64(1) << 384) - U512::from_u64(1));
+        // Shorter hashes are used as they are
+        assert_eq!(bits2int(P384::N, &hash[..32]), (U512::from_u64(1) << 256) - U512::from_u64(1));
+    }
+}
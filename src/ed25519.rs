@@ -0,0 +1,369 @@
+//! Ed25519 signatures from RFC 8032, over the twisted Edwards curve
+//! -x^2 + y^2 = 1 + d x^2 y^2 mod 2^255 - 19. Points use extended
+//! coordinates, where the addition formulas are complete, so the same code
+//! handles doubling and the identity without any branches.
+
+use std::cmp;
+use std::ops;
+use std::sync::OnceLock;
+
+use rand::Rng;
+
+use bigint::{Montgomery, U512};
+use ct::{mul_fixed_window, WindowPoint};
+use hash::{sha512, Sha512};
+use xdh::P25519;
+
+// The order of the base point, 2^252 + 27742317777372353535851937790883648493
+pub(crate) const L: U512 = U512::from_hex_be(b"1000000000000000000000000000000014def9dea2f79cd65812631a5cf5d3ed");
+// -121665 / 121666
+const D: U512 = U512::from_hex_be(b"52036cee2b6ffe738cc740797779e89800700a4d4141d8ab75eb4dca135978a3");
+const BASE_X: U512 = U512::from_hex_be(b"216936d3cd6e53fec0a4e231fdd6dc5c692cc7609525a7b2c9562d608f25d51a");
+// 4 / 5
+const BASE_Y: U512 = U512::from_hex_be(b"6666666666666666666666666666666666666666666666666666666666666658");
+
+// Arithmetic mod p, with the constants in Montgomery form.
+pub(crate) struct Field {
+    pub(crate) mont: Montgomery,
+    pub(crate) d: U512,
+    d2: U512,
+    // A square root of -1, which is 2^((p - 1) / 4)
+    pub(crate) sqrt_m1: U512,
+    pub(crate) p_minus_2: U512,
+    // (p - 5) / 8, for square roots
+    pub(crate) sqrt_exp: U512,
+}
+
+pub(crate) fn field() -> &'static Field {
+    static FIELD: OnceLock<Field> = OnceLock::new();
+    FIELD.get_or_init(|| {
+        let mont = Montgomery::new(P25519);
+        let d = mont.to_mont(D);
+        let one = U512::from_u64(1);
+        Field {
+            mont,
+            d,
+            d2: mont.add(d, d),
+            sqrt_m1: mont.pow(mont.to_mont(U512::from_u64(2)), (P25519 - one) >> 2),
+            p_minus_2: P25519 - U512::from_u64(2),
+            sqrt_exp: (P25519 - U512::from_u64(5)) >> 3,
+        }
+    })
+}
+
+// Arithmetic mod L
+fn scalars() -> &'static Montgomery {
+    static SCALARS: OnceLock<Montgomery> = OnceLock::new();
+    SCALARS.get_or_init(|| Montgomery::new(L))
+}
+
+// x mod L for any 512 bit x, such as a hash, in constant time
+pub(crate) fn reduce_scalar(x: U512) -> U512 {
+    scalars().reduce_512(x)
+}
+
+fn hash_to_scalar(parts: &[&[u8]]) -> U512 {
+    let mut hasher = Sha512::new();
+    for part in parts {
+        hasher.update(part);
+    }
+    reduce_scalar(U512::from_bytes_le(hasher.finalize().to_vec()))
+}
+
+/// A point in extended coordinates (X : Y : Z : T), for the affine point
+/// (X / Z, Y / Z) with T = XY / Z. Everything is in Montgomery form.
+#[derive(Copy, Clone, Debug)]
+pub(crate) struct EdwardsPoint {
+    pub(crate) x: U512,
+    pub(crate) y: U512,
+    pub(crate) z: U512,
+    pub(crate) t: U512,
+}
+
+impl EdwardsPoint {
+    pub(crate) fn identity() -> EdwardsPoint {
+        let one = field().mont.one();
+        EdwardsPoint {
+            x: U512::zero(),
+            y: one,
+            z: one,
+            t: U512::zero(),
+        }
+    }
+
+    pub(crate) fn base() -> EdwardsPoint {
+        let m = &field().mont;
+        let (x, y) = (m.to_mont(BASE_X), m.to_mont(BASE_Y));
+        EdwardsPoint { x, y, z: m.one(), t: m.mul(x, y) }
+    }
+
+    // The dbl-2008-hwcd formulas for a = -1
+    fn double(&self) -> EdwardsPoint {
+        let m = &field().mont;
+        let a = m.square(self.x);
+        let b = m.square(self.y);
+        let c = m.square(self.z);
+        let c = m.add(c, c);
+        let h = m.add(a, b);
+        let e = m.sub(h, m.square(m.add(self.x, self.y)));
+        let g = m.sub(a, b);
+        let f = m.add(c, g);
+        EdwardsPoint {
+            x: m.mul(e, f),
+            y: m.mul(g, h),
+            z: m.mul(f, g),
+            t: m.mul(e, h),
+        }
+    }
+
+    // k * self in constant time, for k < 2^256
+    pub(crate) fn mul(&self, k: U512) -> EdwardsPoint {
+        mul_fixed_window(self, k, 256)
+    }
+
+    pub(crate) fn conditional_assign(&mut self, other: &EdwardsPoint, choice: bool) {
+        self.x.conditional_assign(&other.x, choice);
+        self.y.conditional_assign(&other.y, choice);
+        self.z.conditional_assign(&other.z, choice);
+        self.t.conditional_assign(&other.t, choice);
+    }
+
+    // y in little endian, with the low bit of x in the top bit
+    fn encode(&self) -> [u8; 32] {
+        let f = field();
+        let m = &f.mont;
+        let z_inv = m.pow(self.z, f.p_minus_2);
+        let x = m.from_mont(m.mul(self.x, z_inv));
+        let y = m.from_mont(m.mul(self.y, z_inv));
+
+        let mut out = [0u8; 32];
+        out.copy_from_slice(&y.to_bytes_le()[..32]);
+        out[31] |= (x.bit(0) as u8) << 7;
+        out
+    }
+
+    // Section 5.1.3 of RFC 8032. None if y is not reduced, or there is no x
+    // for it.
+    fn decode(bytes: &[u8; 32]) -> Option<EdwardsPoint> {
+        let x_0 = bytes[31] >> 7 == 1;
+        let mut y_bytes = *bytes;
+        y_bytes[31] &= 0x7f;
+        let y = U512::from_bytes_le(y_bytes.to_vec());
+        if y >= P25519 {
+            return None;
+        }
+
+        // x^2 = u / v, and the candidate root is u v^3 (u v^7)^((p - 5) / 8)
+        let f = field();
+        let m = &f.mont;
+        let y = m.to_mont(y);
+        let yy = m.square(y);
+        let u = m.sub(yy, m.one());
+        let v = m.add(m.mul(f.d, yy), m.one());
+        let v3 = m.mul(m.square(v), v);
+        let v7 = m.mul(m.square(v3), v);
+        let mut x = m.mul(m.mul(u, v3), m.pow(m.mul(u, v7), f.sqrt_exp));
+
+        let vxx = m.mul(v, m.square(x));
+        if vxx == m.neg(u) {
+            x = m.mul(x, f.sqrt_m1);
+        } else if vxx != u {
+            return None;
+        }
+
+        let x_plain = m.from_mont(x);
+        if x_plain.is_zero() && x_0 {
+            return None;
+        }
+        if x_plain.bit(0) != x_0 {
+            x = m.neg(x);
+        }
+        Some(EdwardsPoint { x, y, z: m.one(), t: m.mul(x, y) })
+    }
+}
+
+// The add-2008-hwcd-3 formulas for a = -1, which are complete.
+impl ops::Add for EdwardsPoint {
+    type Output = EdwardsPoint;
+    fn add(self, rhs: EdwardsPoint) -> EdwardsPoint {
+        let m = &field().mont;
+        let a = m.mul(m.sub(self.y, self.x), m.sub(rhs.y, rhs.x));
+        let b = m.mul(m.add(self.y, self.x), m.add(rhs.y, rhs.x));
+        let c = m.mul(m.mul(self.t, field().d2), rhs.t);
+        let d = m.mul(self.z, rhs.z);
+        let d = m.add(d, d);
+        let e = m.sub(b, a);
+        let f = m.sub(d, c);
+        let g = m.add(d, c);
+        let h = m.add(b, a);
+        EdwardsPoint {
+            x: m.mul(e, f),
+            y: m.mul(g, h),
+            z: m.mul(f, g),
+            t: m.mul(e, h),
+        }
+    }
+}
+
+impl ops::Neg for EdwardsPoint {
+    type Output = EdwardsPoint;
+    fn neg(self) -> EdwardsPoint {
+        let m = &field().mont;
+        EdwardsPoint {
+            x: m.neg(self.x),
+            t: m.neg(self.t),
+            ..self
+        }
+    }
+}
+
+impl WindowPoint for EdwardsPoint {
+    fn identity() -> EdwardsPoint {
+        EdwardsPoint::identity()
+    }
+
+    fn double(&self) -> EdwardsPoint {
+        EdwardsPoint::double(self)
+    }
+
+    fn conditional_assign(&mut self, other: &EdwardsPoint, choice: bool) {
+        EdwardsPoint::conditional_assign(self, other, choice)
+    }
+}
+
+// Equal as points: X1 Z2 = X2 Z1 and Y1 Z2 = Y2 Z1
+impl cmp::PartialEq for EdwardsPoint {
+    fn eq(&self, other: &EdwardsPoint) -> bool {
+        let m = &field().mont;
+        m.mul(self.x, other.z) == m.mul(other.x, self.z)
+            && m.mul(self.y, other.z) == m.mul(other.y, self.z)
+    }
+}
+
+/// An Ed25519 secret key, expanded from its 32 byte seed.
+#[derive(Clone)]
+pub struct SigningKey {
+    seed: [u8; 32],
+    // The clamped secret scalar
+    scalar: U512,
+    // The second half of the seed's hash, which keeps nonces secret
+    prefix: [u8; 32],
+    public_key: [u8; 32],
+}
+
+impl SigningKey {
+    pub fn from_bytes(seed: [u8; 32]) -> SigningKey {
+        let hash = sha512(&seed);
+        let mut scalar = [0u8; 32];
+        scalar.copy_from_slice(&hash[..32]);
+        scalar[0] &= 248;
+        scalar[31] &= 127;
+        scalar[31] |= 64;
+        let scalar = U512::from_bytes_le(scalar.to_vec());
+
+        let mut prefix = [0u8; 32];
+        prefix.copy_from_slice(&hash[32..]);
+        SigningKey {
+            seed,
+            scalar,
+            prefix,
+            public_key: EdwardsPoint::base().mul(scalar).encode(),
+        }
+    }
+
+    pub fn generate<R: Rng>(rng: &mut R) -> SigningKey {
+        let mut seed = [0u8; 32];
+        rng.fill_bytes(&mut seed);
+        SigningKey::from_bytes(seed)
+    }
+
+    /// The 32 byte seed this key was made from.
+    pub fn to_bytes(&self) -> [u8; 32] {
+        self.seed
+    }
+
+    pub fn public_key(&self) -> [u8; 32] {
+        self.public_key
+    }
+
+    /// The 64 byte signature R || S of message. Signing is deterministic,
+    /// with the nonce derived from the key and the message.
+    pub fn sign(&self, message: &[u8]) -> [u8; 64] {
+        let r = hash_to_scalar(&[&self.prefix, message]);
+        let big_r = EdwardsPoint::base().mul(r).encode();
+        let k = hash_to_scalar(&[&big_r, &self.public_key, message]);
+
+        // S = r + k * s mod L
+        let m = scalars();
+        let s = m.add(r, m.mul(m.to_mont(k), self.scalar));
+
+        let mut signature = [0u8; 64];
+        signature[..32].copy_from_slice(&big_r);
+        signature[32..].copy_from_slice(&s.to_bytes_le()[..32]);
+        signature
+    }
+}
+
+/// Whether signature is a valid signature of message under public_key.
+/// Uses the cofactored check [8][S]B = [8]R + [8][k]A from RFC 8032.
+pub fn verify(public_key: &[u8; 32], message: &[u8], signature: &[u8; 64]) -> bool {
+    let a = match EdwardsPoint::decode(public_key) {
+        Some(a) => a,
+        None => return false,
+    };
+    let mut big_r = [0u8; 32];
+    big_r.copy_from_slice(&signature[..32]);
+    let r = match EdwardsPoint::decode(&big_r) {
+        Some(r) => r,
+        None => return false,
+    };
+    let s = U512::from_bytes_le(signature[32..].to_vec());
+    if s >= L {
+        return false;
+    }
+
+    let k = hash_to_scalar(&[&big_r, public_key, message]);
+    let lhs = EdwardsPoint::base().mul(s);
+    let rhs = r + a.mul(k);
+    lhs.double().double().double() == rhs.double().double().double()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::thread_rng;
+
+    #[test]
+    fn group() {
+        let b = EdwardsPoint::base();
+        assert_eq!(b.mul(L), EdwardsPoint::identity());
+        assert_eq!(b + b, b.double());
+        assert_eq!(b + -b, EdwardsPoint::identity());
+        assert_eq!(b + EdwardsPoint::identity(), b);
+        assert_eq!(EdwardsPoint::decode(&b.encode()), Some(b));
+        assert_eq!(EdwardsPoint::decode(&(-b).encode()), Some(-b));
+
+        // y = p is not reduced, and y = 2 has no x
+        let mut bytes = [0xff; 32];
+        bytes[0] = 0xed;
+        bytes[31] = 0x7f;
+        assert!(EdwardsPoint::decode(&bytes).is_none());
+        let mut bytes = [0; 32];
+        bytes[0] = 2;
+        assert!(EdwardsPoint::decode(&bytes).is_none());
+    }
+
+    #[test]
+    fn reduce() {
+        let x = !U512::zero();
+        assert_eq!(reduce_scalar(x), x % L);
+        assert_eq!(reduce_scalar(L), U512::zero());
+        let mut rng = thread_rng();
+        for _ in 0..100 {
+            let mut bytes = vec![0; 64];
+            rng.fill_bytes(&mut bytes);
+            let x = U512::from_bytes_le(bytes);
+            assert_eq!(reduce_scalar(x), x % L);
+        }
+    }
+}
@@ -0,0 +1,394 @@
+//! Ed448 signatures from RFC 8032, over the Edwards curve
+//! x^2 + y^2 = 1 + d x^2 y^2 mod 2^448 - 2^224 - 1 with d = -39081. Points
+//! use projective coordinates, where the addition formulas are complete.
+//! Supports context strings, and Ed448ph which signs a SHAKE256 hash of the
+//! message instead of the message itself.
+
+use std::cmp;
+use std::ops;
+use std::sync::OnceLock;
+
+use rand::Rng;
+
+use bigint::{Montgomery, U512};
+use ct::{mul_fixed_window, WindowPoint};
+use hash::{shake256, Shake256};
+use xdh::P448;
+
+// The order of the base point,
+// 2^446 - 13818066809895115352007386748515426880336692474882178609894547503885
+const L: U512 = U512::from_hex_be(b"3fffffffffffffffffffffffffffffffffffffffffffffffffffffff\
+                                    7cca23e9c44edb49aed63690216cc2728dc58f552378c292ab5844f3");
+const D: U512 = U512::from_hex_be(b"fffffffffffffffffffffffffffffffffffffffffffffffffffffffe\
+                                    ffffffffffffffffffffffffffffffffffffffffffffffffffff6756");
+const BASE_X: U512 = U512::from_hex_be(b"4f1970c66bed0ded221d15a622bf36da9e146570470f1767ea6de324\
+                                         a3d3a46412ae1af72ab66511433b80e18b00938e2626a82bc70cc05e");
+const BASE_Y: U512 = U512::from_hex_be(b"693f46716eb6bc248876203756c9c7624bea73736ca3984087789c1e\
+                                         05a0c2d73ad3ff1ce67c39c4fdbd132c4ed7c8ad9808795bf230fa14");
+
+// Scalars are all less than 2^448
+const SCALAR_BITS: usize = 448;
+
+// Arithmetic mod p, with d in Montgomery form.
+struct Field {
+    mont: Montgomery,
+    d: U512,
+    p_minus_2: U512,
+    // (p - 3) / 4, for square roots
+    sqrt_exp: U512,
+}
+
+fn field() -> &'static Field {
+    static FIELD: OnceLock<Field> = OnceLock::new();
+    FIELD.get_or_init(|| {
+        let mont = Montgomery::new(P448);
+        Field {
+            mont,
+            d: mont.to_mont(D),
+            p_minus_2: P448 - U512::from_u64(2),
+            sqrt_exp: (P448 - U512::from_u64(3)) >> 2,
+        }
+    })
+}
+
+// Arithmetic mod L
+fn scalars() -> &'static Montgomery {
+    static SCALARS: OnceLock<Montgomery> = OnceLock::new();
+    SCALARS.get_or_init(|| Montgomery::new(L))
+}
+
+fn hash_to_scalar(parts: &[&[u8]]) -> U512 {
+    let mut hasher = Shake256::new();
+    for part in parts {
+        hasher.update(part);
+    }
+    let mut hash = [0u8; 114];
+    hasher.finalize(&mut hash);
+    reduce_wide(&hash)
+}
+
+// The 114 byte hash outputs are too big for a U512, so split them as
+// low + high * 2^512. Converting to Montgomery form multiplies by 2^512 mod
+// L, which takes care of high since it's 400 bits and less than L.
+fn reduce_wide(bytes: &[u8; 114]) -> U512 {
+    let m = scalars();
+    let low = U512::from_bytes_le(bytes[..64].to_vec());
+    let high = U512::from_bytes_le(bytes[64..].to_vec());
+    m.add(m.reduce_512(low), m.to_mont(high))
+}
+
+// The prefix that separates Ed448 from Ed448ph, and signatures with
+// different contexts.
+fn dom4(prehashed: bool, context: &[u8]) -> Vec<u8> {
+    assert!(context.len() <= 255, "Ed448 contexts are at most 255 bytes");
+    let mut ans = b"SigEd448".to_vec();
+    ans.push(prehashed as u8);
+    ans.push(context.len() as u8);
+    ans.extend_from_slice(context);
+    ans
+}
+
+fn prehash(message: &[u8]) -> [u8; 64] {
+    let mut ans = [0u8; 64];
+    shake256(message, &mut ans);
+    ans
+}
+
+/// A point in projective coordinates (X : Y : Z), for the affine point
+/// (X / Z, Y / Z). Everything is in Montgomery form.
+#[derive(Copy, Clone, Debug)]
+struct EdwardsPoint {
+    x: U512,
+    y: U512,
+    z: U512,
+}
+
+impl EdwardsPoint {
+    fn identity() -> EdwardsPoint {
+        let one = field().mont.one();
+        EdwardsPoint { x: U512::zero(), y: one, z: one }
+    }
+
+    fn base() -> EdwardsPoint {
+        let m = &field().mont;
+        EdwardsPoint { x: m.to_mont(BASE_X), y: m.to_mont(BASE_Y), z: m.one() }
+    }
+
+    // Section 5.2.4 of RFC 8032
+    fn double(&self) -> EdwardsPoint {
+        let m = &field().mont;
+        let b = m.square(m.add(self.x, self.y));
+        let c = m.square(self.x);
+        let d = m.square(self.y);
+        let e = m.add(c, d);
+        let h = m.square(self.z);
+        let j = m.sub(e, m.add(h, h));
+        EdwardsPoint {
+            x: m.mul(m.sub(b, e), j),
+            y: m.mul(e, m.sub(c, d)),
+            z: m.mul(e, j),
+        }
+    }
+
+    // k * self in constant time, for k < 2^448
+    fn mul(&self, k: U512) -> EdwardsPoint {
+        mul_fixed_window(self, k, SCALAR_BITS)
+    }
+
+    fn conditional_assign(&mut self, other: &EdwardsPoint, choice: bool) {
+        self.x.conditional_assign(&other.x, choice);
+        self.y.conditional_assign(&other.y, choice);
+        self.z.conditional_assign(&other.z, choice);
+    }
+
+    // y in 57 little endian bytes, with the low bit of x in the top bit
+    fn encode(&self) -> [u8; 57] {
+        let f = field();
+        let m = &f.mont;
+        let z_inv = m.pow(self.z, f.p_minus_2);
+        let x = m.from_mont(m.mul(self.x, z_inv));
+        let y = m.from_mont(m.mul(self.y, z_inv));
+
+        let mut out = [0u8; 57];
+        out.copy_from_slice(&y.to_bytes_le()[..57]);
+        out[56] |= (x.bit(0) as u8) << 7;
+        out
+    }
+
+    // Section 5.2.3 of RFC 8032. None if y is not reduced, or there is no x
+    // for it.
+    fn decode(bytes: &[u8; 57]) -> Option<EdwardsPoint> {
+        let x_0 = bytes[56] >> 7 == 1;
+        let mut y_bytes = *bytes;
+        y_bytes[56] &= 0x7f;
+        let y = U512::from_bytes_le(y_bytes.to_vec());
+        if y >= P448 {
+            return None;
+        }
+
+        // x^2 = u / v, and the candidate root is u^3 v (u^5 v^3)^((p - 3) / 4)
+        let f = field();
+        let m = &f.mont;
+        let y = m.to_mont(y);
+        let yy = m.square(y);
+        let u = m.sub(yy, m.one());
+        let v = m.sub(m.mul(f.d, yy), m.one());
+        let u3v = m.mul(m.mul(m.square(u), u), v);
+        let u5v3 = m.mul(m.mul(u3v, m.square(u)), m.square(v));
+        let mut x = m.mul(u3v, m.pow(u5v3, f.sqrt_exp));
+        if m.mul(v, m.square(x)) != u {
+            return None;
+        }
+
+        let x_plain = m.from_mont(x);
+        if x_plain.is_zero() && x_0 {
+            return None;
+        }
+        if x_plain.bit(0) != x_0 {
+            x = m.neg(x);
+        }
+        Some(EdwardsPoint { x, y, z: m.one() })
+    }
+}
+
+// Section 5.2.4 of RFC 8032. These are complete, so they also double.
+impl ops::Add for EdwardsPoint {
+    type Output = EdwardsPoint;
+    fn add(self, rhs: EdwardsPoint) -> EdwardsPoint {
+        let m = &field().mont;
+        let a = m.mul(self.z, rhs.z);
+        let b = m.square(a);
+        let c = m.mul(self.x, rhs.x);
+        let d = m.mul(self.y, rhs.y);
+        let e = m.mul(m.mul(field().d, c), d);
+        let f = m.sub(b, e);
+        let g = m.add(b, e);
+        let h = m.mul(m.add(self.x, self.y), m.add(rhs.x, rhs.y));
+        EdwardsPoint {
+            x: m.mul(m.mul(a, f), m.sub(m.sub(h, c), d)),
+            y: m.mul(m.mul(a, g), m.sub(d, c)),
+            z: m.mul(f, g),
+        }
+    }
+}
+
+impl ops::Neg for EdwardsPoint {
+    type Output = EdwardsPoint;
+    fn neg(self) -> EdwardsPoint {
+        EdwardsPoint {
+            x: field().mont.neg(self.x),
+            ..self
+        }
+    }
+}
+
+impl WindowPoint for EdwardsPoint {
+    fn identity() -> EdwardsPoint {
+        EdwardsPoint::identity()
+    }
+
+    fn double(&self) -> EdwardsPoint {
+        EdwardsPoint::double(self)
+    }
+
+    fn conditional_assign(&mut self, other: &EdwardsPoint, choice: bool) {
+        EdwardsPoint::conditional_assign(self, other, choice)
+    }
+}
+
+// Equal as points: X1 Z2 = X2 Z1 and Y1 Z2 = Y2 Z1
+impl cmp::PartialEq for EdwardsPoint {
+    fn eq(&self, other: &EdwardsPoint) -> bool {
+        let m = &field().mont;
+        m.mul(self.x, other.z) == m.mul(other.x, self.z)
+            && m.mul(self.y, other.z) == m.mul(other.y, self.z)
+    }
+}
+
+/// An Ed448 secret key, expanded from its 57 byte seed.
+#[derive(Clone)]
+pub struct SigningKey {
+    seed: [u8; 57],
+    // The clamped secret scalar
+    scalar: U512,
+    // The second half of the seed's hash, which keeps nonces secret
+    prefix: [u8; 57],
+    public_key: [u8; 57],
+}
+
+impl SigningKey {
+    pub fn from_bytes(seed: [u8; 57]) -> SigningKey {
+        let mut hash = [0u8; 114];
+        shake256(&seed, &mut hash);
+        let mut scalar = [0u8; 57];
+        scalar.copy_from_slice(&hash[..57]);
+        scalar[0] &= 252;
+        scalar[55] |= 128;
+        scalar[56] = 0;
+        let scalar = U512::from_bytes_le(scalar.to_vec());
+
+        let mut prefix = [0u8; 57];
+        prefix.copy_from_slice(&hash[57..]);
+        SigningKey {
+            seed,
+            scalar,
+            prefix,
+            public_key: EdwardsPoint::base().mul(scalar).encode(),
+        }
+    }
+
+    pub fn generate<R: Rng>(rng: &mut R) -> SigningKey {
+        let mut seed = [0u8; 57];
+        rng.fill_bytes(&mut seed);
+        SigningKey::from_bytes(seed)
+    }
+
+    /// The 57 byte seed this key was made from.
+    pub fn to_bytes(&self) -> [u8; 57] {
+        self.seed
+    }
+
+    pub fn public_key(&self) -> [u8; 57] {
+        self.public_key
+    }
+
+    /// The 114 byte signature R || S of message. context may be empty, and
+    /// must be at most 255 bytes. Signing is deterministic.
+    pub fn sign(&self, message: &[u8], context: &[u8]) -> [u8; 114] {
+        self.sign_with(&dom4(false, context), message)
+    }
+
+    /// An Ed448ph signature, which signs the SHAKE256 hash of message.
+    pub fn sign_prehashed(&self, message: &[u8], context: &[u8]) -> [u8; 114] {
+        self.sign_with(&dom4(true, context), &prehash(message))
+    }
+
+    fn sign_with(&self, dom: &[u8], message: &[u8]) -> [u8; 114] {
+        let r = hash_to_scalar(&[dom, &self.prefix, message]);
+        let big_r = EdwardsPoint::base().mul(r).encode();
+        let k = hash_to_scalar(&[dom, &big_r, &self.public_key, message]);
+
+        // S = r + k * s mod L
+        let m = scalars();
+        let s = m.add(r, m.mul(m.to_mont(k), self.scalar));
+
+        let mut signature = [0u8; 114];
+        signature[..57].copy_from_slice(&big_r);
+        signature[57..].copy_from_slice(&s.to_bytes_le()[..57]);
+        signature
+    }
+}
+
+/// Whether signature is a valid Ed448 signature of message with the given
+/// context under public_key. Uses the cofactored check from RFC 8032.
+pub fn verify(public_key: &[u8; 57], message: &[u8], context: &[u8], signature: &[u8; 114]) -> bool {
+    verify_with(&dom4(false, context), public_key, message, signature)
+}
+
+/// Whether signature is a valid Ed448ph signature of message.
+pub fn verify_prehashed(public_key: &[u8; 57], message: &[u8], context: &[u8],
+                        signature: &[u8; 114]) -> bool {
+    verify_with(&dom4(true, context), public_key, &prehash(message), signature)
+}
+
+fn verify_with(dom: &[u8], public_key: &[u8; 57], message: &[u8], signature: &[u8; 114]) -> bool {
+    let a = match EdwardsPoint::decode(public_key) {
+        Some(a) => a,
+        None => return false,
+    };
+    let mut big_r = [0u8; 57];
+    big_r.copy_from_slice(&signature[..57]);
+    let r = match EdwardsPoint::decode(&big_r) {
+        Some(r) => r,
+        None => return false,
+    };
+    let s = U512::from_bytes_le(signature[57..].to_vec());
+    if s >= L {
+        return false;
+    }
+
+    // [4][S]B = [4]R + [4][k]A
+    let k = hash_to_scalar(&[dom, &big_r, public_key, message]);
+    let lhs = EdwardsPoint::base().mul(s);
+    let rhs = r + a.mul(k);
+    lhs.double().double() == rhs.double().double()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bigint::BigUint;
+    use rand::thread_rng;
+
+    #[test]
+    fn group() {
+        let b = EdwardsPoint::base();
+        assert_eq!(b.mul(L), EdwardsPoint::identity());
+        assert_eq!(b + b, b.double());
+        assert_eq!(b + -b, EdwardsPoint::identity());
+        assert_eq!(EdwardsPoint::decode(&b.encode()), Some(b));
+        assert_eq!(EdwardsPoint::decode(&(-b).encode()), Some(-b));
+
+        // Bits 448 to 454 make y too big
+        let mut bytes = b.encode();
+        bytes[56] |= 1;
+        assert_eq!(EdwardsPoint::decode(&bytes), None);
+    }
+
+    #[test]
+    fn wide_reduce() {
+        let l = BigUint::from(L);
+        for byte in [0x00, 0x5a, 0xff].iter() {
+            let bytes = [*byte; 114];
+            let expected = BigUint::from_bytes_le(bytes.to_vec()) % l.clone();
+            assert_eq!(BigUint::from(reduce_wide(&bytes)), expected);
+        }
+        let mut rng = thread_rng();
+        for _ in 0..100 {
+            let mut bytes = [0; 114];
+            rng.fill_bytes(&mut bytes);
+            let expected = BigUint::from_bytes_le(bytes.to_vec()) % l.clone();
+            assert_eq!(BigUint::from(reduce_wide(&bytes)), expected);
+        }
+    }
+}
@@ -0,0 +1,87 @@
+//! The expand_message functions from RFC 9380 section 5.3, which stretch a
+//! message into any number of uniformly random bytes, bound to a domain
+//! separation tag (DST). Hashing to curves is built on these.
+
+use super::{Digest, Shake256};
+
+const OVERSIZE_PREFIX: &[u8] = b"H2C-OVERSIZE-DST-";
+
+// The DST followed by its length, which must fit in a byte. Longer DSTs are
+// replaced by a hash of them first, as section 5.3.3 describes.
+fn dst_prime(dst: &[u8]) -> Vec<u8> {
+    let mut out = dst.to_vec();
+    out.push(dst.len() as u8);
+    out
+}
+
+// H("H2C-OVERSIZE-DST-" || DST) for a DST over 255 bytes
+fn xmd_dst<D: Digest>(dst: &[u8]) -> Vec<u8> {
+    if dst.len() <= 255 {
+        return dst.to_vec();
+    }
+    let mut hasher = D::new();
+    hasher.update(OVERSIZE_PREFIX);
+    hasher.update(dst);
+    hasher.finalize().as_ref().to_vec()
+}
+
+// The same with ceil(2 * k / 8) bytes of SHAKE256, for a security level of
+// k bits
+fn xof_dst(dst: &[u8], security_bits: usize) -> Vec<u8> {
+    if dst.len() <= 255 {
+        return dst.to_vec();
+    }
+    let mut hasher = Shake256::new();
+    hasher.update(OVERSIZE_PREFIX);
+    hasher.update(dst);
+    let mut out = vec![0u8; (2 * security_bits).div_ceil(8)];
+    hasher.finalize(&mut out);
+    out
+}
+
+/// expand_message_xmd: len bytes from a hash function like SHA-256. len can
+/// be at most 255 times the hash length, and 65535. A DST over 255 bytes is
+/// hashed down to one that fits.
+pub fn expand_message_xmd<D: Digest>(msg: &[u8], dst: &[u8], len: usize) -> Vec<u8> {
+    let blocks = len.div_ceil(D::OUTPUT_LEN);
+    assert!(blocks <= 255 && len <= 65535, "expand_message_xmd output is too long");
+    let dst_prime = dst_prime(&xmd_dst::<D>(dst));
+
+    // b_0 = H(Z_pad || msg || I2OSP(len, 2) || 0 || DST_prime)
+    let mut hasher = D::new();
+    hasher.update(&vec![0u8; D::BLOCK_LEN]);
+    hasher.update(msg);
+    hasher.update(&(len as u16).to_be_bytes());
+    hasher.update(&[0]);
+    hasher.update(&dst_prime);
+    let b_0 = hasher.finalize();
+
+    // b_1 = H(b_0 || 1 || DST_prime), and b_i = H((b_0 XOR b_(i-1)) || i || DST_prime)
+    let mut out = Vec::with_capacity(blocks * D::OUTPUT_LEN);
+    let mut block = vec![0u8; D::OUTPUT_LEN];
+    for idx in 1..=blocks {
+        let mut hasher = D::new();
+        let mixed: Vec<u8> = b_0.as_ref().iter().zip(block.iter()).map(|(a, b)| a ^ b).collect();
+        hasher.update(&mixed);
+        hasher.update(&[idx as u8]);
+        hasher.update(&dst_prime);
+        block = hasher.finalize().as_ref().to_vec();
+        out.extend_from_slice(&block);
+    }
+    out.truncate(len);
+    out
+}
+
+/// expand_message_xof with SHAKE256: len bytes, at most 65535. A DST over
+/// 255 bytes is hashed down to one that fits, with a length that depends on
+/// the security level in bits.
+pub fn expand_message_xof(msg: &[u8], dst: &[u8], len: usize, security_bits: usize) -> Vec<u8> {
+    assert!(len <= 65535, "expand_message_xof output is too long");
+    let mut hasher = Shake256::new();
+    hasher.update(msg);
+    hasher.update(&(len as u16).to_be_bytes());
+    hasher.update(&dst_prime(&xof_dst(dst, security_bits)));
+    let mut out = vec![0u8; len];
+    hasher.finalize(&mut out);
+    out
+}
@@ -0,0 +1,31 @@
+//! HKDF from RFC 5869, which derives keys from a shared secret using HMAC.
+
+use super::{hmac, Digest, Hmac};
+
+/// The extract step: a pseudorandom key made from the input key material
+/// and an optional salt, which may be empty.
+pub fn hkdf_extract<D: Digest>(salt: &[u8], ikm: &[u8]) -> D::Output {
+    // An empty salt is the same as a block of zeros to HMAC
+    hmac::<D>(salt, ikm)
+}
+
+/// The expand step: len bytes of output from a pseudorandom key, bound to
+/// info. len can be at most 255 times the hash length.
+pub fn hkdf_expand<D: Digest>(prk: &[u8], info: &[u8], len: usize) -> Vec<u8> {
+    assert!(len <= 255 * D::OUTPUT_LEN, "HKDF output is too long");
+    let mut out = Vec::with_capacity(len + D::OUTPUT_LEN);
+    let mut block: Vec<u8> = Vec::new();
+    let mut counter = 1u8;
+    while out.len() < len {
+        // T(i) = HMAC(PRK, T(i - 1) || info || i)
+        let mut mac = Hmac::<D>::new(prk);
+        mac.update(&block);
+        mac.update(info);
+        mac.update(&[counter]);
+        block = mac.finalize().as_ref().to_vec();
+        out.extend_from_slice(&block);
+        counter = counter.wrapping_add(1);
+    }
+    out.truncate(len);
+    out
+}
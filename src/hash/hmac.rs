@@ -0,0 +1,49 @@
+//! HMAC from RFC 2104, over any `Digest`.
+
+use super::Digest;
+
+/// An HMAC computation in progress.
+#[derive(Clone)]
+pub struct Hmac<D: Digest> {
+    // Already fed the key XORed with the inner and outer pads
+    inner: D,
+    outer: D,
+}
+
+impl<D: Digest> Hmac<D> {
+    pub fn new(key: &[u8]) -> Hmac<D> {
+        // Keys longer than a block are hashed first, and all keys are padded
+        // with zeros to a block
+        let mut block = if key.len() > D::BLOCK_LEN {
+            let mut hasher = D::new();
+            hasher.update(key);
+            hasher.finalize().as_ref().to_vec()
+        } else {
+            key.to_vec()
+        };
+        block.resize(D::BLOCK_LEN, 0);
+
+        let mut inner = D::new();
+        inner.update(&block.iter().map(|b| b ^ 0x36).collect::<Vec<u8>>());
+        let mut outer = D::new();
+        outer.update(&block.iter().map(|b| b ^ 0x5c).collect::<Vec<u8>>());
+        Hmac { inner, outer }
+    }
+
+    pub fn update(&mut self, data: &[u8]) {
+        self.inner.update(data);
+    }
+
+    pub fn finalize(self) -> D::Output {
+        let mut outer = self.outer;
+        outer.update(self.inner.finalize().as_ref());
+        outer.finalize()
+    }
+}
+
+/// The HMAC of data under key, all at once.
+pub fn hmac<D: Digest>(key: &[u8], data: &[u8]) -> D::Output {
+    let mut mac = Hmac::<D>::new(key);
+    mac.update(data);
+    mac.finalize()
+}
@@ -0,0 +1,31 @@
+//! Hash functions needed by the signature schemes, with a streaming API:
+//! feed data in with `update` as it arrives and call `finalize` at the end.
+
+mod expand;
+mod hkdf;
+mod hmac;
+mod sha256;
+mod sha3;
+mod sha512;
+
+pub use self::expand::{expand_message_xmd, expand_message_xof};
+pub use self::hkdf::{hkdf_expand, hkdf_extract};
+pub use self::hmac::{hmac, Hmac};
+pub use self::sha256::{sha256, Sha256};
+pub use self::sha3::{shake256, Shake256};
+pub use self::sha512::{sha384, sha512, Sha384, Sha512};
+
+/// A hash function with a fixed size output, for code that works with any
+/// of them, like HMAC. The hash types also have these methods directly.
+pub trait Digest: Clone {
+    /// The digest, a byte array.
+    type Output: AsRef<[u8]> + Copy;
+    /// The length of the digest in bytes.
+    const OUTPUT_LEN: usize;
+    /// The size of the blocks fed to the compression function, in bytes.
+    const BLOCK_LEN: usize;
+
+    fn new() -> Self;
+    fn update(&mut self, data: &[u8]);
+    fn finalize(self) -> Self::Output;
+}
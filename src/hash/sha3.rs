@@ -0,0 +1,137 @@
+//! The Keccak sponge from FIPS 202, and the SHAKE256 extendable output
+//! function built on it.
+
+const ROUND_CONSTANTS: [u64; 24] = [
+    0x0000000000000001, 0x0000000000008082, 0x800000000000808a, 0x8000000080008000,
+    0x000000000000808b, 0x0000000080000001, 0x8000000080008081, 0x8000000000008009,
+    0x000000000000008a, 0x0000000000000088, 0x0000000080008009, 0x000000008000000a,
+    0x000000008000808b, 0x800000000000008b, 0x8000000000008089, 0x8000000000008003,
+    0x8000000000008002, 0x8000000000000080, 0x000000000000800a, 0x800000008000000a,
+    0x8000000080008081, 0x8000000000008080, 0x0000000080000001, 0x8000000080008008,
+];
+
+// ROTATIONS[x][y] is the rotation of lane (x, y) in the rho step
+const ROTATIONS: [[u32; 5]; 5] = [
+    [0, 36, 3, 41, 18],
+    [1, 44, 10, 45, 2],
+    [62, 6, 43, 15, 61],
+    [28, 55, 25, 21, 56],
+    [27, 20, 39, 8, 14],
+];
+
+// Lane (x, y) is state[x + 5 * y]
+fn keccak_f(state: &mut [u64; 25]) {
+    for rc in ROUND_CONSTANTS.iter() {
+        // theta: add the parity of two neighbouring columns to each lane
+        let mut parity = [0u64; 5];
+        for (x, p) in parity.iter_mut().enumerate() {
+            *p = state[x] ^ state[x + 5] ^ state[x + 10] ^ state[x + 15] ^ state[x + 20];
+        }
+        for x in 0..5 {
+            let d = parity[(x + 4) % 5] ^ parity[(x + 1) % 5].rotate_left(1);
+            for y in 0..5 {
+                state[x + 5 * y] ^= d;
+            }
+        }
+
+        // rho and pi: rotate each lane and move it to a new position
+        let mut moved = [0u64; 25];
+        for x in 0..5 {
+            for y in 0..5 {
+                moved[y + 5 * ((2 * x + 3 * y) % 5)] = state[x + 5 * y].rotate_left(ROTATIONS[x][y]);
+            }
+        }
+
+        // chi: the only nonlinear step, along each row
+        for y in 0..5 {
+            for x in 0..5 {
+                state[x + 5 * y] = moved[x + 5 * y]
+                    ^ (!moved[(x + 1) % 5 + 5 * y] & moved[(x + 2) % 5 + 5 * y]);
+            }
+        }
+
+        // iota
+        state[0] ^= rc;
+    }
+}
+
+// A sponge with the given rate in bytes. Input is XORed into the state a
+// byte at a time, and the permutation runs whenever a block is full.
+#[derive(Clone)]
+struct Sponge {
+    state: [u64; 25],
+    rate: usize,
+    // Position in the current block
+    pos: usize,
+}
+
+impl Sponge {
+    fn new(rate: usize) -> Sponge {
+        Sponge { state: [0; 25], rate, pos: 0 }
+    }
+
+    fn xor_byte(&mut self, idx: usize, byte: u8) {
+        self.state[idx / 8] ^= (byte as u64) << (8 * (idx % 8));
+    }
+
+    fn absorb(&mut self, data: &[u8]) {
+        for byte in data {
+            self.xor_byte(self.pos, *byte);
+            self.pos += 1;
+            if self.pos == self.rate {
+                keccak_f(&mut self.state);
+                self.pos = 0;
+            }
+        }
+    }
+
+    // Pads with the domain separation bits in suffix, then squeezes out as
+    // many bytes as out holds.
+    fn squeeze(mut self, suffix: u8, out: &mut [u8]) {
+        self.xor_byte(self.pos, suffix);
+        self.xor_byte(self.rate - 1, 0x80);
+        keccak_f(&mut self.state);
+
+        for block in out.chunks_mut(self.rate) {
+            for (idx, byte) in block.iter_mut().enumerate() {
+                *byte = (self.state[idx / 8] >> (8 * (idx % 8))) as u8;
+            }
+            keccak_f(&mut self.state);
+        }
+    }
+}
+
+/// A SHAKE256 computation in progress. The output can be any length.
+#[derive(Clone)]
+pub struct Shake256 {
+    sponge: Sponge,
+}
+
+impl Shake256 {
+    pub fn new() -> Shake256 {
+        // 1600 bits of state less a 512 bit capacity
+        Shake256 { sponge: Sponge::new(136) }
+    }
+
+    pub fn update(&mut self, data: &[u8]) {
+        self.sponge.absorb(data);
+    }
+
+    /// Fills out with the output.
+    pub fn finalize(self, out: &mut [u8]) {
+        self.sponge.squeeze(0x1f, out);
+    }
+}
+
+impl Default for Shake256 {
+    fn default() -> Shake256 {
+        Shake256::new()
+    }
+}
+
+/// The first out.len() bytes of the SHAKE256 output for data.
+pub fn shake256(data: &[u8], out: &mut [u8]) {
+    let mut hasher = Shake256::new();
+    hasher.update(data);
+    hasher.finalize(out);
+}
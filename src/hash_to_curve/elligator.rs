@@ -0,0 +1,171 @@
+//! Elligator 2 from section 6.7.1, for the Montgomery curves
+//! v^2 = u^3 + A u^2 + u that X25519 and X448 use. Their points aren't a
+//! type in this crate, so results are (u, v) coordinates: u in little endian
+//! is the encoding that `x25519` and `x448` take.
+
+use std::sync::OnceLock;
+
+use bigint::{Montgomery, U512};
+use xdh::{P25519, P448};
+use super::{field_elements, inv0, is_square, sgn0, sqrt, Expander};
+
+// A Montgomery curve with its hash to curve parameters, in Montgomery form
+struct MontgomeryCurve {
+    mont: Montgomery,
+    a: U512,
+    // A non-square
+    z: U512,
+    // The cofactor is 2^cofactor_bits
+    cofactor_bits: usize,
+    security_bits: usize,
+}
+
+fn curve25519() -> &'static MontgomeryCurve {
+    static CURVE: OnceLock<MontgomeryCurve> = OnceLock::new();
+    CURVE.get_or_init(|| {
+        let mont = Montgomery::new(P25519);
+        MontgomeryCurve {
+            mont,
+            a: mont.to_mont(U512::from_u64(486662)),
+            z: mont.to_mont(U512::from_u64(2)),
+            cofactor_bits: 3,
+            security_bits: 128,
+        }
+    })
+}
+
+fn curve448() -> &'static MontgomeryCurve {
+    static CURVE: OnceLock<MontgomeryCurve> = OnceLock::new();
+    CURVE.get_or_init(|| {
+        let mont = Montgomery::new(P448);
+        MontgomeryCurve {
+            mont,
+            a: mont.to_mont(U512::from_u64(156326)),
+            z: mont.neg(mont.one()),
+            cofactor_bits: 2,
+            security_bits: 224,
+        }
+    })
+}
+
+// An affine point in Montgomery form, or None for the point at infinity
+type Point = Option<(U512, U512)>;
+
+impl MontgomeryCurve {
+    // The straight line map from appendix F.3, which picks between the two
+    // candidates for u without branching
+    fn elligator2(&self, r: U512) -> (U512, U512) {
+        let m = &self.mont;
+        let rhs = |x| m.mul(m.add(m.mul(m.add(x, self.a), x), m.one()), x);
+
+        // x1 = -A / (1 + z r^2), or -A if that divides by zero
+        let minus_a = m.neg(self.a);
+        let mut x1 = m.mul(minus_a, inv0(m, m.add(m.one(), m.mul(self.z, m.square(r)))));
+        x1.conditional_assign(&minus_a, x1.is_zero());
+        let gx1 = rhs(x1);
+        let x2 = m.sub(minus_a, x1);
+        let gx2 = rhs(x2);
+
+        // y is negative for x1 and non-negative for x2
+        let use_x1 = is_square(m, gx1);
+        let (mut x, mut gx) = (x2, gx2);
+        x.conditional_assign(&x1, use_x1);
+        gx.conditional_assign(&gx1, use_x1);
+        let mut y = sqrt(m, gx);
+        y.conditional_assign(&m.neg(y), sgn0(m, y) != use_x1);
+        (x, y)
+    }
+
+    // Affine addition, which isn't constant time: the special cases only
+    // come up with negligible probability for hashed points
+    fn add(&self, p: Point, q: Point) -> Point {
+        let m = &self.mont;
+        let ((x1, y1), (x2, y2)) = match (p, q) {
+            (None, _) => return q,
+            (_, None) => return p,
+            (Some(p), Some(q)) => (p, q),
+        };
+        let slope = if x1 != x2 {
+            m.mul(m.sub(y2, y1), inv0(m, m.sub(x2, x1)))
+        } else if y1 == y2 && !y1.is_zero() {
+            // (3 x^2 + 2 A x + 1) / 2y
+            let xx = m.square(x1);
+            let num = m.add(m.add(m.add(xx, m.add(xx, xx)), m.mul(m.add(self.a, self.a), x1)), m.one());
+            m.mul(num, inv0(m, m.add(y1, y1)))
+        } else {
+            return None;
+        };
+        let x3 = m.sub(m.sub(m.sub(m.square(slope), self.a), x1), x2);
+        let y3 = m.sub(m.mul(slope, m.sub(x1, x3)), y1);
+        Some((x3, y3))
+    }
+
+    // Hashes to count field elements, maps them, adds the points and clears
+    // the cofactor
+    fn hash<E: Expander>(&self, msg: &[u8], dst: &[u8], count: usize) -> Option<(U512, U512)> {
+        let m = &self.mont;
+        let mut ans = None;
+        for r in field_elements::<E>(m, msg, dst, self.security_bits, count) {
+            ans = self.add(ans, Some(self.elligator2(m.to_mont(r))));
+        }
+        for _ in 0..self.cofactor_bits {
+            ans = self.add(ans, ans);
+        }
+        ans.map(|(x, y)| (m.from_mont(x), m.from_mont(y)))
+    }
+}
+
+/// hash_to_curve for Curve25519, as in curve25519_XMD:SHA-512_ELL2_RO_ with
+/// `Xmd<Sha512>`. The point is (u, v), or None for the point at infinity,
+/// which has negligible probability.
+pub fn hash_to_curve25519<E: Expander>(msg: &[u8], dst: &[u8]) -> Option<(U512, U512)> {
+    curve25519().hash::<E>(msg, dst, 2)
+}
+
+/// encode_to_curve for Curve25519, as in curve25519_XMD:SHA-512_ELL2_NU_.
+pub fn encode_to_curve25519<E: Expander>(msg: &[u8], dst: &[u8]) -> Option<(U512, U512)> {
+    curve25519().hash::<E>(msg, dst, 1)
+}
+
+/// hash_to_curve for Curve448, as in curve448_XOF:SHAKE256_ELL2_RO_ with
+/// `Shake256Xof`. The point is (u, v), or None for the point at infinity,
+/// which has negligible probability.
+pub fn hash_to_curve448<E: Expander>(msg: &[u8], dst: &[u8]) -> Option<(U512, U512)> {
+    curve448().hash::<E>(msg, dst, 2)
+}
+
+/// encode_to_curve for Curve448, as in curve448_XOF:SHAKE256_ELL2_NU_.
+pub fn encode_to_curve448<E: Expander>(msg: &[u8], dst: &[u8]) -> Option<(U512, U512)> {
+    curve448().hash::<E>(msg, dst, 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn on_curve(curve: &MontgomeryCurve, point: (U512, U512)) -> bool {
+        let m = &curve.mont;
+        let (x, y) = point;
+        m.square(y) == m.mul(m.add(m.mul(m.add(x, curve.a), x), m.one()), x)
+    }
+
+    #[test]
+    fn map_and_add() {
+        for curve in [curve25519(), curve448()].iter() {
+            let m = &curve.mont;
+            // 0 maps to (0, 0), which has order 2, and so does 1 on Curve448
+            // where 1 + z * 1^2 = 0
+            assert_eq!(curve.elligator2(U512::zero()), (U512::zero(), U512::zero()));
+            for idx in 2..12 {
+                let p = curve.elligator2(m.to_mont(U512::from_u64(idx)));
+                assert!(on_curve(curve, p));
+                let q = curve.elligator2(m.to_mont(U512::from_u64(idx + 100)));
+                let sum = curve.add(Some(p), Some(q)).unwrap();
+                assert!(on_curve(curve, sum));
+                assert!(on_curve(curve, curve.add(Some(p), Some(p)).unwrap()));
+                assert_eq!(curve.add(Some(sum), Some((q.0, m.neg(q.1)))), Some(p));
+                assert_eq!(curve.add(Some(p), Some((p.0, m.neg(p.1)))), None);
+            }
+        }
+    }
+}
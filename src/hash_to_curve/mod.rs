@@ -0,0 +1,183 @@
+//! Hashing byte strings to curve points, from RFC 9380. Nobody knows the
+//! discrete log of the points, which is what VRFs, PAKEs and the like need.
+//!
+//! `hash_to_curve` gives points that are indistinguishable from random, and
+//! `encode_to_curve` is about twice as fast but can only reach some of the
+//! points, and not uniformly. The maps from field elements to points are
+//! simplified SWU for the Weierstrass curves in `ec`, through a 3-isogeny
+//! for secp256k1, and Elligator 2 for Curve25519 and Curve448. They don't
+//! branch on the input, so they're safe for secrets like passwords.
+//!
+//! A suite from the RFC is a curve, an `Expander` and whether to use the
+//! random oracle (RO) or nonuniform (NU) construction. For example
+//! P256_XMD:SHA-256_SSWU_RO_ is `hash_to_curve::<P256, Xmd<Sha256>>`.
+
+use std::marker::PhantomData;
+
+use bigint::{Montgomery, U512};
+use ec::{AffinePoint, Curve, ProjectivePoint};
+use hash::{expand_message_xmd, expand_message_xof, Digest};
+
+mod elligator;
+mod sswu;
+
+pub use self::elligator::{encode_to_curve25519, encode_to_curve448, hash_to_curve25519, hash_to_curve448};
+
+/// An expand_message function from section 5.3, for `hash_to_field`. The
+/// security level in bits only matters to the XOF, for hashing long DSTs.
+pub trait Expander {
+    fn expand_message(msg: &[u8], dst: &[u8], len: usize, security_bits: usize) -> Vec<u8>;
+}
+
+/// expand_message_xmd with the hash function D.
+#[derive(Copy, Clone, Debug)]
+pub struct Xmd<D: Digest> {
+    digest: PhantomData<D>,
+}
+
+impl<D: Digest> Expander for Xmd<D> {
+    fn expand_message(msg: &[u8], dst: &[u8], len: usize, _security_bits: usize) -> Vec<u8> {
+        expand_message_xmd::<D>(msg, dst, len)
+    }
+}
+
+/// expand_message_xof with SHAKE256.
+#[derive(Copy, Clone, Debug)]
+pub struct Shake256Xof;
+
+impl Expander for Shake256Xof {
+    fn expand_message(msg: &[u8], dst: &[u8], len: usize, security_bits: usize) -> Vec<u8> {
+        expand_message_xof(msg, dst, len, security_bits)
+    }
+}
+
+/// hash_to_field from section 5.2: count integers mod the prime modulus,
+/// each reduced from L = ceil((bits(modulus) + k) / 8) bytes of E's output,
+/// for a security level of k bits. L can be at most 128.
+pub fn hash_to_field<E: Expander>(msg: &[u8], dst: &[u8], modulus: U512, security_bits: usize,
+                                  count: usize) -> Vec<U512> {
+    field_elements::<E>(&Montgomery::new(modulus), msg, dst, security_bits, count)
+}
+
+fn field_elements<E: Expander>(m: &Montgomery, msg: &[u8], dst: &[u8], security_bits: usize,
+                               count: usize) -> Vec<U512> {
+    let len = (m.modulus().bits() + security_bits).div_ceil(8);
+    assert!(len <= 128, "hash_to_field needs too many bytes per element");
+    let bytes = E::expand_message(msg, dst, count * len, security_bits);
+    bytes.chunks(len).map(|chunk| reduce(m, chunk)).collect()
+}
+
+// A big endian integer of up to 128 bytes mod m, in constant time
+fn reduce(m: &Montgomery, bytes: &[u8]) -> U512 {
+    // Split it into hi * 2^512 + lo. 2^512 is the Montgomery R, so to_mont
+    // multiplies by it.
+    let split = bytes.len().saturating_sub(64);
+    let hi = U512::from_bytes_be(bytes[..split].to_vec());
+    let lo = U512::from_bytes_be(bytes[split..].to_vec());
+    m.add(m.to_mont(m.reduce_512(hi)), m.reduce_512(lo))
+}
+
+// The helpers below are for values in Montgomery form, and are constant
+// time. Exponents are all public, so pow is fine.
+
+// 1 / x, or 0 for x = 0
+fn inv0(m: &Montgomery, x: U512) -> U512 {
+    m.pow(x, m.modulus() - U512::from_u64(2))
+}
+
+// Euler's criterion. Zero counts as a square.
+fn is_square(m: &Montgomery, x: U512) -> bool {
+    let legendre = m.pow(x, (m.modulus() - U512::from_u64(1)) >> 1);
+    legendre == m.one() || legendre.is_zero()
+}
+
+// A square root of x, which must be a square. Only primes that are 3 mod 4
+// or 5 mod 8 are handled, which is every curve here.
+fn sqrt(m: &Montgomery, x: U512) -> U512 {
+    let p = m.modulus();
+    if p.bit(1) {
+        // x^((p + 1) / 4) squares to x^((p - 1) / 2) * x = x
+        return m.pow(x, (p + U512::from_u64(1)) >> 2);
+    }
+    assert!(p.bit(2), "sqrt needs p = 3 mod 4 or 5 mod 8");
+    // x^((p + 3) / 8) squares to x or -x, and -x needs a factor of sqrt(-1),
+    // which is 2^((p - 1) / 4)
+    let mut root = m.pow(x, (p + U512::from_u64(3)) >> 3);
+    let two = m.add(m.one(), m.one());
+    let sqrt_m1 = m.pow(two, (p - U512::from_u64(1)) >> 2);
+    root.conditional_assign(&m.mul(root, sqrt_m1), m.square(root) != x);
+    root
+}
+
+// The sign of x from section 4.1, which for prime fields is its parity
+fn sgn0(m: &Montgomery, x: U512) -> bool {
+    m.from_mont(x).bit(0)
+}
+
+/// A curve with a map_to_curve function. The curves here all have cofactor
+/// 1, so there's no cofactor to clear.
+pub trait MapToCurve: Curve {
+    /// The security level k in bits, for `hash_to_field`.
+    const SECURITY_BITS: usize;
+
+    /// The deterministic map from an integer mod p to a point. On its own
+    /// this only reaches some of the points, and they aren't uniform.
+    fn map_to_curve(u: U512) -> AffinePoint<Self>;
+}
+
+/// hash_to_curve from section 3: hashes msg to two field elements, maps
+/// both and adds them, so the result is indistinguishable from a random
+/// point. dst separates different uses of the hash.
+pub fn hash_to_curve<C: MapToCurve, E: Expander>(msg: &[u8], dst: &[u8]) -> AffinePoint<C> {
+    let u = field_elements::<E>(&C::field().mont, msg, dst, C::SECURITY_BITS, 2);
+    let q0 = ProjectivePoint::from(C::map_to_curve(u[0]));
+    let q1 = ProjectivePoint::from(C::map_to_curve(u[1]));
+    (q0 + q1).to_affine()
+}
+
+/// encode_to_curve from section 3: maps a single field element hashed from
+/// msg. Faster than `hash_to_curve`, but not every point can be the result.
+pub fn encode_to_curve<C: MapToCurve, E: Expander>(msg: &[u8], dst: &[u8]) -> AffinePoint<C> {
+    let u = field_elements::<E>(&C::field().mont, msg, dst, C::SECURITY_BITS, 1);
+    C::map_to_curve(u[0])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bigint::BigUint;
+    use xdh::{P25519, P448};
+
+    #[test]
+    fn reduce_wide() {
+        let m = Montgomery::new(P448);
+        let check = |bytes: &[u8]| {
+            let expected = BigUint::from_bytes_be(bytes.to_vec()) % BigUint::from(P448);
+            assert_eq!(BigUint::from(reduce(&m, bytes)), expected);
+        };
+        check(&[0xff; 84]);
+        check(&[0xff; 128]);
+        check(&[0xab; 65]);
+        check(&[0xcd; 64]);
+        check(&[]);
+    }
+
+    #[test]
+    fn square_roots() {
+        for &p in [P25519, P448].iter() {
+            let m = Montgomery::new(p);
+            for idx in 0..20 {
+                let x = m.to_mont(U512::from_u64(idx));
+                assert!(is_square(&m, m.square(x)));
+                let root = sqrt(&m, m.square(x));
+                assert!(root == x || root == m.neg(x));
+            }
+            assert_eq!(inv0(&m, U512::zero()), U512::zero());
+        }
+        // 2 isn't a square mod 2^255 - 19, and -1 isn't mod 2^448 - 2^224 - 1
+        let m = Montgomery::new(P25519);
+        assert!(!is_square(&m, m.to_mont(U512::from_u64(2))));
+        let m = Montgomery::new(P448);
+        assert!(!is_square(&m, m.neg(m.one())));
+    }
+}
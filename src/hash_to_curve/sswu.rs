@@ -0,0 +1,171 @@
+//! The simplified Shallue-van de Woestijne-Ulas map from section 6.6.2, for
+//! y^2 = x^3 + ax + b with a and b nonzero. secp256k1 has a = 0, so it's
+//! mapped to a 3-isogenous curve first, as in section 6.6.3.
+
+use std::sync::OnceLock;
+
+use bigint::{Montgomery, U512};
+use ec::{AffinePoint, Curve, P256, P384, Secp256k1};
+use super::{inv0, is_square, sgn0, sqrt, MapToCurve};
+
+// The map for curve parameters a and b and a non-square z, with everything
+// in Montgomery form. This is the straight line version from appendix F.2,
+// selecting between the two candidates for x without branching.
+fn sswu(m: &Montgomery, a: U512, b: U512, z: U512, u: U512) -> (U512, U512) {
+    let one = m.one();
+    let rhs = |x| m.add(m.mul(m.add(m.square(x), a), x), b);
+
+    // tv1 = 1 / (z^2 u^4 + z u^2), and x1 = (-b / a) * (1 + tv1), except
+    // that x1 = b / (z a) when tv1 is zero
+    let zu2 = m.mul(z, m.square(u));
+    let tv1 = inv0(m, m.add(m.square(zu2), zu2));
+    let mut x1 = m.mul(m.neg(m.mul(b, inv0(m, a))), m.add(one, tv1));
+    x1.conditional_assign(&m.mul(b, inv0(m, m.mul(z, a))), tv1.is_zero());
+    let gx1 = rhs(x1);
+
+    // Otherwise x2 = z u^2 x1 is on the curve
+    let x2 = m.mul(zu2, x1);
+    let gx2 = rhs(x2);
+
+    let use_x1 = is_square(m, gx1);
+    let (mut x, mut gx) = (x2, gx2);
+    x.conditional_assign(&x1, use_x1);
+    gx.conditional_assign(&gx1, use_x1);
+    let mut y = sqrt(m, gx);
+    // y takes the sign of u
+    y.conditional_assign(&m.neg(y), sgn0(m, u) != sgn0(m, y));
+    (x, y)
+}
+
+// The map for curves where sswu works directly, with z given as a small
+// negative number
+fn map_direct<C: Curve>(u: U512, minus_z: u64) -> AffinePoint<C> {
+    let f = C::field();
+    let m = &f.mont;
+    let z = m.neg(m.to_mont(U512::from_u64(minus_z)));
+    let (x, y) = sswu(m, f.a, f.b, z, m.to_mont(u));
+    AffinePoint::new(m.from_mont(x), m.from_mont(y)).expect("SSWU point is not on the curve")
+}
+
+impl MapToCurve for P256 {
+    const SECURITY_BITS: usize = 128;
+
+    fn map_to_curve(u: U512) -> AffinePoint<P256> {
+        map_direct(u, 10)
+    }
+}
+
+impl MapToCurve for P384 {
+    const SECURITY_BITS: usize = 192;
+
+    fn map_to_curve(u: U512) -> AffinePoint<P384> {
+        map_direct(u, 12)
+    }
+}
+
+// The curve y^2 = x^3 + a'x + b' that's 3-isogenous to secp256k1, and the
+// coefficients of the isogeny's rational maps from appendix E.1, lowest
+// degree first. The denominators are monic, so their leading 1 is left out.
+const ISO_A: U512 = U512::from_hex_be(b"3f8731abdd661adca08a5558f0f5d272e953d363cb6f0e5d405447c01a444533");
+const ISO_B: u64 = 1771;
+const ISO_X_NUM: [U512; 4] = [
+    U512::from_hex_be(b"8e38e38e38e38e38e38e38e38e38e38e38e38e38e38e38e38e38e38daaaaa8c7"),
+    U512::from_hex_be(b"07d3d4c80bc321d5b9f315cea7fd44c5d595d2fc0bf63b92dfff1044f17c6581"),
+    U512::from_hex_be(b"534c328d23f234e6e2a413deca25caece4506144037c40314ecbd0b53d9dd262"),
+    U512::from_hex_be(b"8e38e38e38e38e38e38e38e38e38e38e38e38e38e38e38e38e38e38daaaaa88c"),
+];
+const ISO_X_DEN: [U512; 2] = [
+    U512::from_hex_be(b"d35771193d94918a9ca34ccbb7b640dd86cd409542f8487d9fe6b745781eb49b"),
+    U512::from_hex_be(b"edadc6f64383dc1df7c4b2d51b54225406d36b641f5e41bbc52a56612a8c6d14"),
+];
+const ISO_Y_NUM: [U512; 4] = [
+    U512::from_hex_be(b"4bda12f684bda12f684bda12f684bda12f684bda12f684bda12f684b8e38e23c"),
+    U512::from_hex_be(b"c75e0c32d5cb7c0fa9d0a54b12a0a6d5647ab046d686da6fdffc90fc201d71a3"),
+    U512::from_hex_be(b"29a6194691f91a73715209ef6512e576722830a201be2018a765e85a9ecee931"),
+    U512::from_hex_be(b"2f684bda12f684bda12f684bda12f684bda12f684bda12f684bda12f38e38d84"),
+];
+const ISO_Y_DEN: [U512; 3] = [
+    U512::from_hex_be(b"fffffffffffffffffffffffffffffffffffffffffffffffffffffffefffff93b"),
+    U512::from_hex_be(b"7a06534bb8bdb49fd5e9e6632722c2989467c1bfc8e8d978dfb425d2685c2573"),
+    U512::from_hex_be(b"6484aa716545ca2cf3a70c3fa8fe337e0a3d21162f0d6299a7bf8192bfd2a76f"),
+];
+
+// The constants above in Montgomery form, and z = -11
+struct Isogeny {
+    a: U512,
+    b: U512,
+    z: U512,
+    x_num: Vec<U512>,
+    x_den: Vec<U512>,
+    y_num: Vec<U512>,
+    y_den: Vec<U512>,
+}
+
+fn isogeny() -> &'static Isogeny {
+    static ISOGENY: OnceLock<Isogeny> = OnceLock::new();
+    ISOGENY.get_or_init(|| {
+        let m = &Secp256k1::field().mont;
+        let convert = |coefficients: &[U512]| coefficients.iter().map(|&c| m.to_mont(c)).collect();
+        Isogeny {
+            a: m.to_mont(ISO_A),
+            b: m.to_mont(U512::from_u64(ISO_B)),
+            z: m.neg(m.to_mont(U512::from_u64(11))),
+            x_num: convert(&ISO_X_NUM),
+            x_den: convert(&ISO_X_DEN),
+            y_num: convert(&ISO_Y_NUM),
+            y_den: convert(&ISO_Y_DEN),
+        }
+    })
+}
+
+// The polynomial with these coefficients at x, by Horner's rule, plus x^n
+// if it's monic with the leading coefficient left out
+fn polynomial(m: &Montgomery, coefficients: &[U512], monic: bool, x: U512) -> U512 {
+    let mut ans = if monic { m.one() } else { U512::zero() };
+    for &c in coefficients.iter().rev() {
+        ans = m.add(m.mul(ans, x), c);
+    }
+    ans
+}
+
+impl MapToCurve for Secp256k1 {
+    const SECURITY_BITS: usize = 128;
+
+    fn map_to_curve(u: U512) -> AffinePoint<Secp256k1> {
+        let m = &Secp256k1::field().mont;
+        let iso = isogeny();
+        let (x, y) = sswu(m, iso.a, iso.b, iso.z, m.to_mont(u));
+
+        let x_den = polynomial(m, &iso.x_den, true, x);
+        let y_den = polynomial(m, &iso.y_den, true, x);
+        // Only the points in the isogeny's kernel have a zero denominator,
+        // and they map to the point at infinity
+        if x_den.is_zero() || y_den.is_zero() {
+            return AffinePoint::identity();
+        }
+        let x_new = m.mul(polynomial(m, &iso.x_num, false, x), inv0(m, x_den));
+        let y_new = m.mul(m.mul(y, polynomial(m, &iso.y_num, false, x)), inv0(m, y_den));
+        AffinePoint::new(m.from_mont(x_new), m.from_mont(y_new)).expect("isogeny point is not on the curve")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn exceptional_inputs() {
+        // u = 0 makes tv1 zero, and u = p - 1 has the same square
+        for &u in [U512::zero(), U512::from_u64(1), <P256 as Curve>::P - U512::from_u64(1)].iter() {
+            assert!(P256::map_to_curve(u).is_on_curve());
+            assert!(P384::map_to_curve(u).is_on_curve());
+            assert!(Secp256k1::map_to_curve(u).is_on_curve());
+        }
+        // -u gives the same point with y negated
+        let u = U512::from_u64(12345);
+        let (x, y) = P256::map_to_curve(u).coordinates().unwrap();
+        let (neg_x, neg_y) = P256::map_to_curve(<P256 as Curve>::P - u).coordinates().unwrap();
+        assert_eq!(x, neg_x);
+        assert_eq!(y, <P256 as Curve>::P - neg_y);
+    }
+}
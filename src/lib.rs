@@ -1,5 +1,21 @@
 extern crate rand;
 
 mod bigint;
+mod ct;
+pub mod dh;
+pub mod dsa;
+pub mod ec;
+pub mod ecdh;
+pub mod ecdsa;
+pub mod ed25519;
+pub mod ed448;
+pub mod hash;
+pub mod hash_to_curve;
+pub mod ristretto255;
+pub mod rsa;
+pub mod schnorr;
+mod xdh;
 
-pub use bigint::U512;
+pub use bigint::{batch_inv_mod, multi_pow_mod, Montgomery, U512};
+pub use bigint::{BigUint, I512, ParseIntError, TryFromIntError};
+pub use xdh::{x25519, x448, X25519_BASE_POINT, X448_BASE_POINT};
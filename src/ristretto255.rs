@@ -0,0 +1,295 @@
+//! The ristretto255 group from RFC 9496. It has prime order, and is built
+//! from the Edwards25519 points that Ed25519 uses: each element stands for a
+//! coset of points that differ by 4-torsion. Encodings are canonical, and
+//! protocols don't have to clear a cofactor or worry about small subgroups.
+
+use std::cmp;
+use std::ops;
+use std::sync::OnceLock;
+
+use bigint::U512;
+use ed25519::{field, reduce_scalar, EdwardsPoint, L};
+use hash::{expand_message_xmd, Sha512};
+use xdh::P25519;
+
+/// The order of the group, 2^252 + 27742317777372353535851937790883648493.
+pub const ORDER: U512 = L;
+
+// sqrt(a * d - 1) for a = -1, the root RFC 9496 picks
+const SQRT_AD_MINUS_ONE: U512 = U512::from_hex_be(b"376931bf2b8348ac0f3cfcc931f5d1fdaf9d8e0c1b7854bd7e97f6a0497b2e1b");
+// 1 / sqrt(a - d)
+const INVSQRT_A_MINUS_D: U512 = U512::from_hex_be(b"786c8905cfaffca216c27b91fe01d8409d2f16175a4172be99c8fdaa805d40ea");
+// 1 - d^2
+const ONE_MINUS_D_SQ: U512 = U512::from_hex_be(b"029072a8b2b3e0d79994abddbe70dfe42c81a138cd5e350fe27c09c1945fc176");
+// (d - 1)^2
+const D_MINUS_ONE_SQ: U512 = U512::from_hex_be(b"5968b37af66c22414cdcd32f529b4eebd29e4a2cb01e199931ad5aaa44ed4d20");
+
+// The constants above, in Montgomery form
+struct Constants {
+    sqrt_ad_minus_one: U512,
+    invsqrt_a_minus_d: U512,
+    one_minus_d_sq: U512,
+    d_minus_one_sq: U512,
+}
+
+fn constants() -> &'static Constants {
+    static CONSTANTS: OnceLock<Constants> = OnceLock::new();
+    CONSTANTS.get_or_init(|| {
+        let m = &field().mont;
+        Constants {
+            sqrt_ad_minus_one: m.to_mont(SQRT_AD_MINUS_ONE),
+            invsqrt_a_minus_d: m.to_mont(INVSQRT_A_MINUS_D),
+            one_minus_d_sq: m.to_mont(ONE_MINUS_D_SQ),
+            d_minus_one_sq: m.to_mont(D_MINUS_ONE_SQ),
+        }
+    })
+}
+
+// Field elements are negative when their low bit is set
+fn is_negative(x: U512) -> bool {
+    field().mont.from_mont(x).bit(0)
+}
+
+fn ct_abs(x: U512) -> U512 {
+    let mut ans = x;
+    ans.conditional_assign(&field().mont.neg(x), is_negative(x));
+    ans
+}
+
+// Whether u / v is a square, and the non-negative sqrt(u / v) if it is or
+// sqrt(i * u / v) if it isn't, for i = sqrt(-1). Zero if u is zero, and
+// also if v is zero, in which case u / v doesn't count as a square unless u
+// is zero too.
+fn sqrt_ratio_m1(u: U512, v: U512) -> (bool, U512) {
+    let f = field();
+    let m = &f.mont;
+    let v3 = m.mul(m.square(v), v);
+    let v7 = m.mul(m.square(v3), v);
+    let mut r = m.mul(m.mul(u, v3), m.pow(m.mul(u, v7), f.sqrt_exp));
+
+    let check = m.mul(v, m.square(r));
+    let correct_sign = check == u;
+    let flipped_sign = check == m.neg(u);
+    let flipped_sign_i = check == m.neg(m.mul(u, f.sqrt_m1));
+    r.conditional_assign(&m.mul(r, f.sqrt_m1), flipped_sign || flipped_sign_i);
+    (correct_sign || flipped_sign, ct_abs(r))
+}
+
+// The Elligator map from a field element in Montgomery form to a point,
+// section 4.3.4
+fn map(t: U512) -> EdwardsPoint {
+    let f = field();
+    let c = constants();
+    let m = &f.mont;
+    let one = m.one();
+
+    let r = m.mul(f.sqrt_m1, m.square(t));
+    let u = m.mul(m.add(r, one), c.one_minus_d_sq);
+    let v = m.mul(m.sub(m.neg(one), m.mul(r, f.d)), m.add(r, f.d));
+    let (was_square, mut s) = sqrt_ratio_m1(u, v);
+    s.conditional_assign(&m.neg(ct_abs(m.mul(s, t))), !was_square);
+    let mut sign = m.neg(one);
+    sign.conditional_assign(&r, !was_square);
+    let n = m.sub(m.mul(m.mul(sign, m.sub(r, one)), c.d_minus_one_sq), v);
+
+    let w0 = m.mul(m.add(s, s), v);
+    let w1 = m.mul(n, c.sqrt_ad_minus_one);
+    let ss = m.square(s);
+    let w2 = m.sub(one, ss);
+    let w3 = m.add(one, ss);
+    EdwardsPoint {
+        x: m.mul(w0, w3),
+        y: m.mul(w2, w1),
+        z: m.mul(w1, w3),
+        t: m.mul(w0, w2),
+    }
+}
+
+/// An element of ristretto255. Equality compares the group elements, not
+/// the Edwards points standing for them.
+#[derive(Copy, Clone, Debug)]
+pub struct RistrettoPoint(EdwardsPoint);
+
+impl RistrettoPoint {
+    pub fn identity() -> RistrettoPoint {
+        RistrettoPoint(EdwardsPoint::identity())
+    }
+
+    /// The generator, which is the Ed25519 base point.
+    pub fn generator() -> RistrettoPoint {
+        RistrettoPoint(EdwardsPoint::base())
+    }
+
+    /// k * self in constant time. k is reduced mod the group order.
+    pub fn mul(&self, k: U512) -> RistrettoPoint {
+        RistrettoPoint(self.0.mul(reduce_scalar(k)))
+    }
+
+    /// The element with this encoding, or None if it isn't the canonical
+    /// encoding of any element. Section 4.3.1.
+    pub fn decode(bytes: &[u8; 32]) -> Option<RistrettoPoint> {
+        // s has to be reduced and non-negative
+        let s = U512::from_bytes_le(bytes.to_vec());
+        if s >= P25519 || s.bit(0) {
+            return None;
+        }
+
+        let f = field();
+        let m = &f.mont;
+        let s = m.to_mont(s);
+        let ss = m.square(s);
+        let u1 = m.sub(m.one(), ss);
+        let u2 = m.add(m.one(), ss);
+        let u2_sqr = m.square(u2);
+        let v = m.sub(m.neg(m.mul(f.d, m.square(u1))), u2_sqr);
+        let (was_square, invsqrt) = sqrt_ratio_m1(m.one(), m.mul(v, u2_sqr));
+
+        let den_x = m.mul(invsqrt, u2);
+        let den_y = m.mul(m.mul(invsqrt, den_x), v);
+        let x = ct_abs(m.mul(m.add(s, s), den_x));
+        let y = m.mul(u1, den_y);
+        let t = m.mul(x, y);
+        if !was_square || is_negative(t) || y.is_zero() {
+            return None;
+        }
+        Some(RistrettoPoint(EdwardsPoint { x, y, z: m.one(), t }))
+    }
+
+    /// The canonical 32 byte encoding, which is the same for every point in
+    /// the coset. Section 4.3.2.
+    pub fn encode(&self) -> [u8; 32] {
+        let f = field();
+        let m = &f.mont;
+        let EdwardsPoint { x: x0, y: y0, z: z0, t: t0 } = self.0;
+
+        let u1 = m.mul(m.add(z0, y0), m.sub(z0, y0));
+        let u2 = m.mul(x0, y0);
+        let (_, invsqrt) = sqrt_ratio_m1(m.one(), m.mul(u1, m.square(u2)));
+        let den1 = m.mul(invsqrt, u1);
+        let den2 = m.mul(invsqrt, u2);
+        let z_inv = m.mul(m.mul(den1, den2), t0);
+
+        // Rotate by the 4-torsion point (i, 0) when that makes x * y
+        // non-negative
+        let rotate = is_negative(m.mul(t0, z_inv));
+        let (mut x, mut y, mut den_inv) = (x0, y0, den2);
+        x.conditional_assign(&m.mul(y0, f.sqrt_m1), rotate);
+        y.conditional_assign(&m.mul(x0, f.sqrt_m1), rotate);
+        den_inv.conditional_assign(&m.mul(den1, constants().invsqrt_a_minus_d), rotate);
+
+        y.conditional_assign(&m.neg(y), is_negative(m.mul(x, z_inv)));
+        let s = m.from_mont(ct_abs(m.mul(den_inv, m.sub(z0, y))));
+
+        let mut out = [0u8; 32];
+        out.copy_from_slice(&s.to_bytes_le()[..32]);
+        out
+    }
+
+    /// The one-way map from 64 uniformly random bytes to an element, section
+    /// 4.3.4. Each half is mapped to a point, and the two are added.
+    pub fn from_uniform_bytes(bytes: &[u8; 64]) -> RistrettoPoint {
+        let m = &field().mont;
+        let half = |bytes: &[u8]| {
+            // The top bit is dropped
+            let mut t = bytes.to_vec();
+            t[31] &= 0x7f;
+            map(m.to_mont(U512::from_bytes_le(t)))
+        };
+        RistrettoPoint(half(&bytes[..32]) + half(&bytes[32..]))
+    }
+
+    /// hash_to_ristretto255 from RFC 9380 appendix B: 64 bytes from
+    /// expand_message_xmd with SHA-512, through `from_uniform_bytes`. dst
+    /// separates different uses of the hash. No one knows the discrete log
+    /// of the result.
+    pub fn hash_to_group(msg: &[u8], dst: &[u8]) -> RistrettoPoint {
+        let mut bytes = [0; 64];
+        bytes.copy_from_slice(&expand_message_xmd::<Sha512>(msg, dst, 64));
+        RistrettoPoint::from_uniform_bytes(&bytes)
+    }
+}
+
+impl ops::Add for RistrettoPoint {
+    type Output = RistrettoPoint;
+    fn add(self, rhs: RistrettoPoint) -> RistrettoPoint {
+        RistrettoPoint(self.0 + rhs.0)
+    }
+}
+
+impl ops::Sub for RistrettoPoint {
+    type Output = RistrettoPoint;
+    fn sub(self, rhs: RistrettoPoint) -> RistrettoPoint {
+        RistrettoPoint(self.0 + -rhs.0)
+    }
+}
+
+impl ops::Neg for RistrettoPoint {
+    type Output = RistrettoPoint;
+    fn neg(self) -> RistrettoPoint {
+        RistrettoPoint(-self.0)
+    }
+}
+
+// Section 4.3.3: X1 Y2 = Y1 X2 or Y1 Y2 = X1 X2
+impl cmp::PartialEq for RistrettoPoint {
+    fn eq(&self, other: &RistrettoPoint) -> bool {
+        let m = &field().mont;
+        let (a, b) = (&self.0, &other.0);
+        m.mul(a.x, b.y) == m.mul(a.y, b.x) || m.mul(a.y, b.y) == m.mul(a.x, b.x)
+    }
+}
+
+impl cmp::Eq for RistrettoPoint {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn torsion_cosets() {
+        // Adding any 4-torsion point gives the same element and encoding
+        let m = &field().mont;
+        let i = field().sqrt_m1;
+        let torsion = [
+            EdwardsPoint { x: U512::zero(), y: m.neg(m.one()), z: m.one(), t: U512::zero() },
+            EdwardsPoint { x: i, y: U512::zero(), z: m.one(), t: U512::zero() },
+            EdwardsPoint { x: m.neg(i), y: U512::zero(), z: m.one(), t: U512::zero() },
+        ];
+        let p = RistrettoPoint::generator().mul(U512::from_u64(12345));
+        for &point in torsion.iter() {
+            let q = RistrettoPoint(p.0 + point);
+            assert_eq!(q, p);
+            assert_eq!(q.encode(), p.encode());
+        }
+        assert!(p != RistrettoPoint::generator());
+    }
+
+    #[test]
+    fn group() {
+        let g = RistrettoPoint::generator();
+        let a = U512::from_u64(987654321);
+        assert_eq!(g.mul(ORDER), RistrettoPoint::identity());
+        assert_eq!(g.mul(ORDER + a), g.mul(a));
+        assert_eq!(g - g, RistrettoPoint::identity());
+        assert_eq!(g.mul(a) + g, g.mul(a + U512::from_u64(1)));
+        assert_eq!(-g.mul(a), g.mul(ORDER - a));
+
+        // Scalars as big as a hash are reduced first
+        let max = !U512::zero();
+        assert_eq!(g.mul(max), g.mul(max % ORDER));
+        let k = U512::from_hex_be(b"c51e4753afdec1e6b6c6a5b992f43f8dd0c7a8933072708b6522468b2ffb06fd\
+                                    9f2c83f4b9d7e0b21a03ab7ea9cc7b8a4c8d6e3f1a2b3c4d5e6f708192a3b4c5");
+        assert_eq!(g.mul(k), g.mul(k % ORDER));
+    }
+
+    #[test]
+    fn hash_to_group() {
+        let dst = b"QUUX-V01-CS02-with-ristretto255_XMD:SHA-512_R255MAP_RO_";
+        let p = RistrettoPoint::hash_to_group(b"abc", dst);
+        let mut bytes = [0; 64];
+        bytes.copy_from_slice(&expand_message_xmd::<Sha512>(b"abc", dst, 64));
+        assert_eq!(p, RistrettoPoint::from_uniform_bytes(&bytes));
+        assert_eq!(RistrettoPoint::decode(&p.encode()), Some(p));
+        assert!(RistrettoPoint::hash_to_group(b"abc", b"another DST") != p);
+    }
+}
@@ -0,0 +1,287 @@
+//! RSA from RFC 8017: OAEP encryption, PSS signatures, and PKCS #1 v1.5
+//! signature verification for talking to older systems. Like `ecdsa`, the
+//! signature functions take the hash of the message, not the message.
+//!
+//! The padding checks on decryption and verification look at every byte and
+//! only branch on the combined result, so the time taken doesn't show which
+//! check failed. That matters most for OAEP, where telling the errors apart
+//! lets an attacker decrypt ciphertexts (Manger's attack).
+
+use rand::Rng;
+
+use bigint::{BigUint, Crt};
+use hash::Digest;
+
+mod oaep;
+mod pkcs1v15;
+mod pss;
+
+pub use self::oaep::{oaep_decrypt, oaep_encrypt};
+pub use self::pkcs1v15::{pkcs1v15_verify, Pkcs1Digest};
+pub use self::pss::{pss_sign, pss_verify};
+
+/// An RSA public key: the modulus n and the public exponent e.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PublicKey {
+    n: BigUint,
+    e: BigUint,
+}
+
+impl PublicKey {
+    /// The public key (n, e), or None unless n and e are odd and
+    /// 3 <= e < n as in RFC 8017 section 3.1.
+    pub fn new(n: BigUint, e: BigUint) -> Option<PublicKey> {
+        if n.is_even() || e.is_even() || e < BigUint::from_u64(3) || e >= n {
+            return None;
+        }
+        Some(PublicKey { n, e })
+    }
+
+    pub fn n(&self) -> &BigUint {
+        &self.n
+    }
+
+    pub fn e(&self) -> &BigUint {
+        &self.e
+    }
+
+    /// The length of n in bytes, which is the length of ciphertexts and
+    /// signatures.
+    pub fn size(&self) -> usize {
+        self.n.bits().div_ceil(8)
+    }
+
+    /// RSAEP, which is also RSAVP1: m^e mod n, or None if m >= n.
+    pub fn rsaep(&self, m: &BigUint) -> Option<BigUint> {
+        if *m >= self.n {
+            return None;
+        }
+        Some(m.pow_mod(&self.e, &self.n))
+    }
+}
+
+/// An RSA private key, kept in the CRT form of RFC 8017 section 3.2 with
+/// the public key alongside.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PrivateKey {
+    public: PublicKey,
+    d: BigUint,
+    p: BigUint,
+    q: BigUint,
+    crt: Crt,
+}
+
+impl PrivateKey {
+    /// The private key with primes p and q and public exponent e, or None if
+    /// they don't make a valid key. p and q have to be the same size to
+    /// within a 64 bit digit, as they are for any normal key. They aren't
+    /// tested for primality, which is slow, so they have to come from
+    /// somewhere trusted.
+    pub fn new(p: BigUint, q: BigUint, e: BigUint) -> Option<PrivateKey> {
+        let one = BigUint::from_u64(1);
+        if p == q || p.is_even() || q.is_even() || p <= one || q <= one {
+            return None;
+        }
+        let public = PublicKey::new(&p * &q, e)?;
+        let (p1, q1) = (&p - &one, &q - &one);
+        let d = public.e.inv_mod(&(&p1 * &q1))?;
+        let qinv = q.inv_mod(&p)?;
+        let crt = Crt::new(&p, &q, &public.e, &(&d % &p1), &(&d % &q1), &qinv)?;
+        Some(PrivateKey { public, d, p, q, crt })
+    }
+
+    /// A random key with a modulus of exactly `bits` bits and e = 65537.
+    pub fn generate<R: Rng>(bits: usize, rng: &mut R) -> PrivateKey {
+        assert!(bits >= 1024 && bits.is_multiple_of(16), "bits must be a multiple of 16, at least 1024");
+        let e = BigUint::from_u64(65537);
+        loop {
+            let p = random_prime(bits / 2, &e, rng);
+            let q = random_prime(bits / 2, &e, rng);
+            if let Some(key) = PrivateKey::new(p, q, e.clone()) {
+                return key;
+            }
+        }
+    }
+
+    pub fn public_key(&self) -> &PublicKey {
+        &self.public
+    }
+
+    pub fn d(&self) -> &BigUint {
+        &self.d
+    }
+
+    pub fn p(&self) -> &BigUint {
+        &self.p
+    }
+
+    pub fn q(&self) -> &BigUint {
+        &self.q
+    }
+
+    /// RSADP, which is also RSASP1: c^d mod n, or None if c >= n. Uses the
+    /// CRT in constant time, blinded with a random value from rng, and
+    /// checks the result against the public key. None if that check fails,
+    /// which means something went wrong during the computation.
+    pub fn rsadp<R: Rng>(&self, c: &BigUint, rng: &mut R) -> Option<BigUint> {
+        self.rsadp_bytes(c, rng).map(|m| os2ip(&m))
+    }
+
+    // rsadp as the length of n in bytes, without the result ever going
+    // through a variable length encoding
+    fn rsadp_bytes<R: Rng>(&self, c: &BigUint, rng: &mut R) -> Option<Vec<u8>> {
+        if *c >= self.public.n {
+            return None;
+        }
+        self.crt.pow(c, self.public.size(), rng)
+    }
+}
+
+// A prime of exactly `bits` bits with the top two set, so the product of
+// two of them has exactly twice as many, and with p - 1 coprime to the prime
+// e. 40 Miller-Rabin rounds is well past what FIPS 186-4 table C.3 asks for.
+fn random_prime<R: Rng>(bits: usize, e: &BigUint, rng: &mut R) -> BigUint {
+    let one = BigUint::from_u64(1);
+    let mut bytes = vec![0; bits / 8];
+    loop {
+        rng.fill_bytes(&mut bytes);
+        bytes[0] |= 0xc0;
+        bytes[bits / 8 - 1] |= 1;
+        let p = BigUint::from_bytes_be(bytes.clone());
+        if !(&(&p - &one) % e).is_zero() && p.is_probable_prime(40, rng) {
+            return p;
+        }
+    }
+}
+
+/// I2OSP: x as a big endian string of exactly len bytes, or None if it
+/// doesn't fit.
+pub fn i2osp(x: &BigUint, len: usize) -> Option<Vec<u8>> {
+    let bytes = x.to_bytes_be();
+    if bytes.len() > len {
+        return None;
+    }
+    let mut out = vec![0; len - bytes.len()];
+    out.extend_from_slice(&bytes);
+    Some(out)
+}
+
+/// OS2IP: the big endian string as an integer.
+pub fn os2ip(bytes: &[u8]) -> BigUint {
+    BigUint::from_bytes_be(bytes.to_vec())
+}
+
+/// MGF1 from RFC 8017 appendix B.2.1: len bytes of hashes of the seed and a
+/// counter.
+pub fn mgf1<D: Digest>(seed: &[u8], len: usize) -> Vec<u8> {
+    let mut out = Vec::with_capacity(len + D::OUTPUT_LEN);
+    let mut counter = 0u32;
+    while out.len() < len {
+        let mut hasher = D::new();
+        hasher.update(seed);
+        hasher.update(&counter.to_be_bytes());
+        out.extend_from_slice(hasher.finalize().as_ref());
+        counter += 1;
+    }
+    out.truncate(len);
+    out
+}
+
+fn hash<D: Digest>(data: &[u8]) -> Vec<u8> {
+    let mut hasher = D::new();
+    hasher.update(data);
+    hasher.finalize().as_ref().to_vec()
+}
+
+fn xor(data: &mut [u8], mask: &[u8]) {
+    for (x, y) in data.iter_mut().zip(mask) {
+        *x ^= y;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hash::{sha384, Sha256, Sha384};
+    use rand::thread_rng;
+
+    #[test]
+    fn keys() {
+        let mut rng = thread_rng();
+        let key = PrivateKey::generate(1024, &mut rng);
+        let public = key.public_key();
+        assert_eq!(public.n().bits(), 1024);
+        assert_eq!(public.size(), 128);
+        let m = BigUint::random_in_range(&BigUint::zero(), public.n(), &mut rng);
+        assert_eq!(key.rsadp(&public.rsaep(&m).unwrap(), &mut rng), Some(m.clone()));
+        assert_eq!(public.rsaep(&key.rsadp(&m, &mut rng).unwrap()), Some(m));
+        assert_eq!(public.rsaep(public.n()), None);
+        assert_eq!(key.rsadp(public.n(), &mut rng), None);
+        for c in [BigUint::zero(), BigUint::from_u64(1), public.n() - &BigUint::from_u64(1)].iter() {
+            assert_eq!(key.rsadp_bytes(c, &mut rng), i2osp(&c.pow_mod(key.d(), public.n()), 128));
+        }
+
+        // A wrong dp stands in for a fault in the exponentiation mod p
+        let (p, q, e) = (key.p(), key.q(), public.e());
+        let one = BigUint::from_u64(1);
+        let dp = &(key.d() % &(p - &one)) + &one;
+        let dq = key.d() % &(q - &one);
+        let faulty = PrivateKey { crt: Crt::new(p, q, e, &dp, &dq, &q.inv_mod(p).unwrap()).unwrap(), ..key.clone() };
+        assert_eq!(faulty.rsadp(&BigUint::from_u64(2), &mut rng), None);
+
+        let (p, q, e) = (BigUint::from_u64(61), BigUint::from_u64(53), BigUint::from_u64(17));
+        assert_eq!(PrivateKey::new(p.clone(), q.clone(), e).unwrap().d(), &BigUint::from_u64(2753));
+        // 3 divides 60
+        assert_eq!(PrivateKey::new(p.clone(), q, BigUint::from_u64(3)), None);
+        assert_eq!(PrivateKey::new(p.clone(), p.clone(), BigUint::from_u64(17)), None);
+        // 2^64 + 13 is prime, but a digit longer than 61
+        let long = &(BigUint::from_u64(1) << 64) + &BigUint::from_u64(13);
+        assert_eq!(PrivateKey::new(p, long, BigUint::from_u64(17)), None);
+        assert_eq!(PublicKey::new(BigUint::from_u64(3233), BigUint::from_u64(65536)), None);
+        assert_eq!(PublicKey::new(BigUint::from_u64(3233), BigUint::from_u64(1)), None);
+    }
+
+    #[test]
+    fn encodings() {
+        let x = BigUint::from_u64(0x0102);
+        assert_eq!(i2osp(&x, 4), Some(vec![0, 0, 1, 2]));
+        assert_eq!(i2osp(&x, 1), None);
+        assert_eq!(i2osp(&BigUint::zero(), 2), Some(vec![0, 0]));
+        assert_eq!(os2ip(&[0, 0, 1, 2]), x);
+    }
+
+    #[test]
+    fn round_trips() {
+        let mut rng = thread_rng();
+        let key = PrivateKey::generate(1024, &mut rng);
+        let public = key.public_key();
+
+        // The longest message that fits is k - 2 * hLen - 2 bytes
+        for &len in [0, 1, 62].iter() {
+            let msg = vec![0x5a; len];
+            let ct = oaep_encrypt::<Sha256, _>(public, &msg, b"label", &mut rng).unwrap();
+            assert_eq!(ct.len(), 128);
+            assert_eq!(oaep_decrypt::<Sha256, _>(&key, &ct, b"label", &mut rng), Some(msg));
+            assert_eq!(oaep_decrypt::<Sha256, _>(&key, &ct, b"other", &mut rng), None);
+            let mut bad = ct.clone();
+            bad[100] ^= 1;
+            assert_eq!(oaep_decrypt::<Sha256, _>(&key, &bad, b"label", &mut rng), None);
+            assert_eq!(oaep_decrypt::<Sha256, _>(&key, &ct[1..], b"label", &mut rng), None);
+        }
+        assert_eq!(oaep_encrypt::<Sha256, _>(public, &[0; 63], b"", &mut rng), None);
+
+        let hash = sha384(b"message");
+        for &salt_len in [0, 20, 48].iter() {
+            let sig = pss_sign::<Sha384, _>(&key, &hash, salt_len, &mut rng).unwrap();
+            assert!(pss_verify::<Sha384>(public, &hash, &sig, salt_len));
+            assert!(!pss_verify::<Sha384>(public, &hash, &sig, salt_len + 1));
+            assert!(!pss_verify::<Sha384>(public, &sha384(b"massage"), &sig, salt_len));
+            let mut bad = sig.clone();
+            bad[0] ^= 1;
+            assert!(!pss_verify::<Sha384>(public, &hash, &bad, salt_len));
+        }
+        // emLen is 128 bytes for a 1024 bit modulus
+        assert!(pss_sign::<Sha384, _>(&key, &hash, 78, &mut rng).is_some());
+        assert_eq!(pss_sign::<Sha384, _>(&key, &hash, 79, &mut rng), None);
+    }
+}
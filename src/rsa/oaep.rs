@@ -0,0 +1,73 @@
+//! RSAES-OAEP from RFC 8017 section 7.1, with MGF1 over the same hash.
+
+use rand::Rng;
+
+use ct::{ct_eq, ct_is_zero};
+use hash::Digest;
+
+use super::{hash, i2osp, mgf1, os2ip, xor, PrivateKey, PublicKey};
+
+/// Encrypts msg to the public key, with an optional label that has to match
+/// on decryption. None if msg is longer than k - 2 * hLen - 2 bytes, where k
+/// is the length of the modulus.
+pub fn oaep_encrypt<D: Digest, R: Rng>(key: &PublicKey, msg: &[u8], label: &[u8], rng: &mut R) -> Option<Vec<u8>> {
+    let k = key.size();
+    let h_len = D::OUTPUT_LEN;
+    if k < 2 * h_len + 2 || msg.len() > k - 2 * h_len - 2 {
+        return None;
+    }
+
+    // DB = lHash || PS || 0x01 || M
+    let mut db = hash::<D>(label);
+    db.resize(k - h_len - msg.len() - 2, 0);
+    db.push(1);
+    db.extend_from_slice(msg);
+    let mut seed = vec![0; h_len];
+    rng.fill_bytes(&mut seed);
+    xor(&mut db, &mgf1::<D>(&seed, k - h_len - 1));
+    xor(&mut seed, &mgf1::<D>(&db, h_len));
+
+    let mut em = vec![0];
+    em.extend_from_slice(&seed);
+    em.extend_from_slice(&db);
+    let c = key.rsaep(&os2ip(&em)).expect("the encoded message starts with 0 so is less than n");
+    i2osp(&c, k)
+}
+
+/// Decrypts a ciphertext from `oaep_encrypt` with the same label. None if
+/// it doesn't decrypt, without saying why. rng blinds the private key
+/// operation.
+pub fn oaep_decrypt<D: Digest, R: Rng>(key: &PrivateKey, ciphertext: &[u8], label: &[u8], rng: &mut R)
+    -> Option<Vec<u8>> {
+    let k = key.public_key().size();
+    let h_len = D::OUTPUT_LEN;
+    if ciphertext.len() != k || k < 2 * h_len + 2 {
+        return None;
+    }
+    let em = key.rsadp_bytes(&os2ip(ciphertext), rng)?;
+
+    let (y, rest) = em.split_at(1);
+    let (masked_seed, masked_db) = rest.split_at(h_len);
+    let mut seed = masked_seed.to_vec();
+    xor(&mut seed, &mgf1::<D>(masked_db, h_len));
+    let mut db = masked_db.to_vec();
+    xor(&mut db, &mgf1::<D>(&seed, k - h_len - 1));
+
+    // Find the 0x01 after the zero padding without branching on the bytes,
+    // so the only timing difference is success or failure
+    let mut bad = !ct_is_zero(y[0]);
+    bad |= (ct_eq(&db[..h_len], &hash::<D>(label)) as u8).wrapping_sub(1);
+    let mut looking = 0xff;
+    let mut index = 0;
+    for (i, &b) in db.iter().enumerate().skip(h_len) {
+        let found = looking & ct_is_zero(b ^ 1);
+        index |= i & usize::from(found & 1).wrapping_neg();
+        bad |= looking & !found & !ct_is_zero(b);
+        looking &= !found;
+    }
+    bad |= looking;
+    if bad != 0 {
+        return None;
+    }
+    Some(db.split_off(index + 1))
+}
@@ -0,0 +1,73 @@
+//! RSASSA-PKCS1-v1_5 verification from RFC 8017 section 8.2.2, for
+//! signatures from systems that don't do PSS. There's no signing, since new
+//! signatures should use PSS.
+
+use ct::ct_eq;
+use hash::{Digest, Sha256, Sha384, Sha512};
+
+use super::{i2osp, os2ip, PublicKey};
+
+/// A hash with the DER encoded DigestInfo prefix that EMSA-PKCS1-v1_5 puts
+/// in front of the hash, from the notes in RFC 8017 section 9.2.
+pub trait Pkcs1Digest: Digest {
+    const DIGEST_INFO: &'static [u8];
+}
+
+impl Pkcs1Digest for Sha256 {
+    const DIGEST_INFO: &'static [u8] = &[
+        0x30, 0x31, 0x30, 0x0d, 0x06, 0x09, 0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04, 0x02, 0x01, 0x05, 0x00, 0x04,
+        0x20,
+    ];
+}
+
+impl Pkcs1Digest for Sha384 {
+    const DIGEST_INFO: &'static [u8] = &[
+        0x30, 0x41, 0x30, 0x0d, 0x06, 0x09, 0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04, 0x02, 0x02, 0x05, 0x00, 0x04,
+        0x30,
+    ];
+}
+
+impl Pkcs1Digest for Sha512 {
+    const DIGEST_INFO: &'static [u8] = &[
+        0x30, 0x51, 0x30, 0x0d, 0x06, 0x09, 0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04, 0x02, 0x03, 0x05, 0x00, 0x04,
+        0x40,
+    ];
+}
+
+/// Whether signature is a PKCS #1 v1.5 signature of message_hash. The
+/// expected encoding is built and compared whole rather than parsed, which
+/// rules out the parsing bugs behind Bleichenbacher's signature forgeries.
+pub fn pkcs1v15_verify<D: Pkcs1Digest>(key: &PublicKey, message_hash: &[u8], signature: &[u8]) -> bool {
+    assert_eq!(message_hash.len(), D::OUTPUT_LEN, "message_hash must be a hash from D");
+    let k = key.size();
+    let t_len = D::DIGEST_INFO.len() + D::OUTPUT_LEN;
+    if signature.len() != k || k < t_len + 11 {
+        return false;
+    }
+    let em = match key.rsaep(&os2ip(signature)).and_then(|m| i2osp(&m, k)) {
+        Some(em) => em,
+        None => return false,
+    };
+
+    // EM = 0x00 || 0x01 || PS || 0x00 || T, with PS all 0xff
+    let mut expected = vec![0, 1];
+    expected.resize(k - t_len - 1, 0xff);
+    expected.push(0);
+    expected.extend_from_slice(D::DIGEST_INFO);
+    expected.extend_from_slice(message_hash);
+    ct_eq(&em, &expected)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn digest_info() {
+        // The last byte is the length of the hash that follows
+        assert_eq!(Sha256::DIGEST_INFO[18] as usize, Sha256::OUTPUT_LEN);
+        assert_eq!(Sha384::DIGEST_INFO[18] as usize, Sha384::OUTPUT_LEN);
+        assert_eq!(Sha512::DIGEST_INFO[18] as usize, Sha512::OUTPUT_LEN);
+        assert_eq!(Sha512::DIGEST_INFO[1] as usize, Sha512::DIGEST_INFO.len() + Sha512::OUTPUT_LEN - 2);
+    }
+}
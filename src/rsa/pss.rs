@@ -0,0 +1,78 @@
+//! RSASSA-PSS from RFC 8017 section 8.1, with the EMSA-PSS encoding of
+//! section 9.1 and MGF1 over the same hash.
+
+use rand::Rng;
+
+use ct::{ct_eq, ct_is_zero};
+use hash::Digest;
+
+use super::{hash, i2osp, mgf1, os2ip, xor, PrivateKey, PublicKey};
+
+/// Signs the hash of a message with a random salt of salt_len bytes, which
+/// is usually hLen. None if the salt is longer than emLen - hLen - 2 bytes,
+/// where emLen is ceil((modBits - 1) / 8) as in RFC 8017, or if the signature
+/// fails its check against the public key.
+pub fn pss_sign<D: Digest, R: Rng>(key: &PrivateKey, message_hash: &[u8], salt_len: usize, rng: &mut R)
+    -> Option<Vec<u8>> {
+    assert_eq!(message_hash.len(), D::OUTPUT_LEN, "message_hash must be a hash from D");
+    let public = key.public_key();
+    let em_bits = public.n().bits() - 1;
+    let em_len = em_bits.div_ceil(8);
+    let h_len = D::OUTPUT_LEN;
+    if em_len < h_len + salt_len + 2 {
+        return None;
+    }
+
+    let mut salt = vec![0; salt_len];
+    rng.fill_bytes(&mut salt);
+    let h = m_prime_hash::<D>(message_hash, &salt);
+
+    // DB = PS || 0x01 || salt
+    let mut db = vec![0; em_len - salt_len - h_len - 2];
+    db.push(1);
+    db.extend_from_slice(&salt);
+    xor(&mut db, &mgf1::<D>(&h, em_len - h_len - 1));
+    db[0] &= 0xff >> (8 * em_len - em_bits);
+
+    let mut em = db;
+    em.extend_from_slice(&h);
+    em.push(0xbc);
+    key.rsadp_bytes(&os2ip(&em), rng)
+}
+
+/// Whether signature is a PSS signature of message_hash with a salt of
+/// salt_len bytes.
+pub fn pss_verify<D: Digest>(key: &PublicKey, message_hash: &[u8], signature: &[u8], salt_len: usize) -> bool {
+    assert_eq!(message_hash.len(), D::OUTPUT_LEN, "message_hash must be a hash from D");
+    let em_bits = key.n().bits() - 1;
+    let em_len = em_bits.div_ceil(8);
+    let h_len = D::OUTPUT_LEN;
+    if signature.len() != key.size() || em_len < h_len + salt_len + 2 {
+        return false;
+    }
+    let em = match key.rsaep(&os2ip(signature)).and_then(|m| i2osp(&m, em_len)) {
+        Some(em) => em,
+        None => return false,
+    };
+
+    let (masked_db, rest) = em.split_at(em_len - h_len - 1);
+    let (h, trailer) = rest.split_at(h_len);
+    let top_bits = 0xff >> (8 * em_len - em_bits);
+    let mut bad = !ct_is_zero(trailer[0] ^ 0xbc) | (masked_db[0] & !top_bits);
+    let mut db = masked_db.to_vec();
+    xor(&mut db, &mgf1::<D>(h, em_len - h_len - 1));
+    db[0] &= top_bits;
+
+    let (ps, rest) = db.split_at(em_len - h_len - salt_len - 2);
+    let (one, salt) = rest.split_at(1);
+    bad |= ps.iter().fold(0, |acc, &b| acc | b) | (one[0] ^ 1);
+    ct_eq(&m_prime_hash::<D>(message_hash, salt), h) & (bad == 0)
+}
+
+// H = Hash(M') where M' = (0x)00 00 00 00 00 00 00 00 || mHash || salt
+fn m_prime_hash<D: Digest>(message_hash: &[u8], salt: &[u8]) -> Vec<u8> {
+    let mut m_prime = vec![0; 8];
+    m_prime.extend_from_slice(message_hash);
+    m_prime.extend_from_slice(salt);
+    hash::<D>(&m_prime)
+}
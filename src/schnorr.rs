@@ -0,0 +1,214 @@
+//! Schnorr signatures over secp256k1 from BIP340, as used by Bitcoin's
+//! Taproot. Public keys are only an x coordinate, standing for the point
+//! with that x and an even y, and signing keys are negated when needed to
+//! match.
+
+use std::sync::OnceLock;
+
+use rand::Rng;
+
+use bigint::{Montgomery, U512};
+use ec::{AffinePoint, Curve, JacobianPoint, Secp256k1};
+use hash::{sha256, Sha256};
+
+type Point = AffinePoint<Secp256k1>;
+
+const N: U512 = <Secp256k1 as Curve>::N;
+
+// Arithmetic mod the curve order
+fn scalars() -> &'static Montgomery {
+    static SCALARS: OnceLock<Montgomery> = OnceLock::new();
+    SCALARS.get_or_init(|| Montgomery::new(N))
+}
+
+// x mod n for a 256 bit x, such as a hash, in constant time
+fn reduce_scalar(x: U512) -> U512 {
+    scalars().reduce_512(x)
+}
+
+/// SHA-256(SHA-256(tag) || SHA-256(tag) || data), where data is the
+/// concatenation of parts. The tag keeps hashes made for different purposes
+/// from colliding.
+pub fn tagged_hash(tag: &str, parts: &[&[u8]]) -> [u8; 32] {
+    let tag_hash = sha256(tag.as_bytes());
+    let mut hasher = Sha256::new();
+    hasher.update(&tag_hash);
+    hasher.update(&tag_hash);
+    for part in parts {
+        hasher.update(part);
+    }
+    hasher.finalize()
+}
+
+fn to_bytes(x: U512) -> [u8; 32] {
+    let mut out = [0u8; 32];
+    out.copy_from_slice(&x.to_bytes_be()[32..]);
+    out
+}
+
+// The point with x coordinate x and an even y, if there is one
+fn lift_x(x: &[u8; 32]) -> Option<Point> {
+    let mut encoded = [2u8; 33];
+    encoded[1..].copy_from_slice(x);
+    Point::from_sec1_bytes(&encoded)
+}
+
+// The x coordinate of a point that isn't the identity, and whether y is even
+fn x_and_parity(point: &Point) -> ([u8; 32], bool) {
+    let (x, y) = point.coordinates().expect("point at infinity");
+    (to_bytes(x), !y.bit(0))
+}
+
+fn challenge(r: &[u8], public_key: &[u8; 32], message: &[u8]) -> U512 {
+    let hash = tagged_hash("BIP0340/challenge", &[r, public_key, message]);
+    reduce_scalar(U512::from_bytes_be(hash.to_vec()))
+}
+
+/// A BIP340 secret key.
+#[derive(Clone)]
+pub struct SigningKey {
+    // The secret scalar, negated if needed so its public point has an even y
+    scalar: U512,
+    public_key: [u8; 32],
+}
+
+impl SigningKey {
+    /// The key with this 32 byte big endian secret, or None unless it's in
+    /// [1, n).
+    pub fn from_bytes(secret: [u8; 32]) -> Option<SigningKey> {
+        let scalar = U512::from_bytes_be(secret.to_vec());
+        if scalar.is_zero() || scalar >= N {
+            return None;
+        }
+        let (public_key, even) = x_and_parity(&Point::mul_generator(scalar));
+        let scalar = if even { scalar } else { N - scalar };
+        Some(SigningKey { scalar, public_key })
+    }
+
+    pub fn generate<R: Rng>(rng: &mut R) -> SigningKey {
+        let scalar = U512::random_in_range(U512::from_u64(1), N, rng);
+        SigningKey::from_bytes(to_bytes(scalar)).unwrap()
+    }
+
+    /// The secret, normalized so that its public point has an even y. This
+    /// signs the same way as the secret it was made from.
+    pub fn to_bytes(&self) -> [u8; 32] {
+        to_bytes(self.scalar)
+    }
+
+    /// The x-only public key.
+    pub fn public_key(&self) -> [u8; 32] {
+        self.public_key
+    }
+
+    /// The 64 byte signature R || s of message. aux_rand should be fresh
+    /// randomness, which protects the nonce against side channels and
+    /// faults, but signing is still secure if it's all zeros.
+    pub fn sign(&self, message: &[u8], aux_rand: &[u8; 32]) -> [u8; 64] {
+        // The nonce is a hash of the key masked by aux_rand, the public key
+        // and the message
+        let mask = tagged_hash("BIP0340/aux", &[aux_rand]);
+        let mut t = to_bytes(self.scalar);
+        for (byte, mask_byte) in t.iter_mut().zip(mask.iter()) {
+            *byte ^= mask_byte;
+        }
+        let nonce = tagged_hash("BIP0340/nonce", &[&t, &self.public_key, message]);
+        let k = reduce_scalar(U512::from_bytes_be(nonce.to_vec()));
+        assert!(!k.is_zero(), "BIP340 nonce is zero");
+
+        let (r, even) = x_and_parity(&Point::mul_generator(k));
+        let k = if even { k } else { N - k };
+        let e = challenge(&r, &self.public_key, message);
+
+        // s = k + e * d mod n
+        let m = scalars();
+        let s = m.add(k, m.mul(m.to_mont(e), self.scalar));
+
+        let mut signature = [0u8; 64];
+        signature[..32].copy_from_slice(&r);
+        signature[32..].copy_from_slice(&to_bytes(s));
+        signature
+    }
+}
+
+// A signature split into the point R, s and the public point P, or None if
+// any of them is out of range
+fn parse(public_key: &[u8; 32], signature: &[u8; 64]) -> Option<(Point, U512, Point)> {
+    let p = lift_x(public_key)?;
+    let mut r = [0u8; 32];
+    r.copy_from_slice(&signature[..32]);
+    let r = lift_x(&r)?;
+    let s = U512::from_bytes_be(signature[32..].to_vec());
+    if s >= N {
+        return None;
+    }
+    Some((r, s, p))
+}
+
+/// Whether signature is a valid BIP340 signature of message under the x-only
+/// public_key.
+pub fn verify(public_key: &[u8; 32], message: &[u8], signature: &[u8; 64]) -> bool {
+    let p = match lift_x(public_key) {
+        Some(p) => p,
+        None => return false,
+    };
+    let s = U512::from_bytes_be(signature[32..].to_vec());
+    if s >= N {
+        return false;
+    }
+
+    // R = s * G - e * P has to have an even y and x coordinate r. r itself
+    // doesn't need to be on the curve for this to be checked.
+    let e = challenge(&signature[..32], public_key, message);
+    let g = JacobianPoint::from(Point::generator());
+    let big_r = g.double_mul_glv(s, &JacobianPoint::from(p), N - e).to_affine();
+    match big_r.coordinates() {
+        Some((x, y)) => !y.bit(0) && to_bytes(x)[..] == signature[..32],
+        None => false,
+    }
+}
+
+/// Verifies many signatures at once, which is faster than verifying each
+/// one. True only if every signature is valid for its message and public
+/// key. The signatures are combined with random weights from rng, so a bad
+/// signature can't be cancelled out by another.
+pub fn verify_batch<R: Rng>(public_keys: &[[u8; 32]], messages: &[&[u8]], signatures: &[[u8; 64]],
+                            rng: &mut R) -> bool {
+    assert!(public_keys.len() == messages.len() && messages.len() == signatures.len(),
+            "batch inputs must be the same length");
+
+    // Check (s_1 + a_2 s_2 + ...) G = R_1 + a_2 R_2 + ... + e_1 P_1 + a_2 e_2 P_2 + ...
+    // for random a_i, with a_1 = 1
+    let m = scalars();
+    let mut s_sum = U512::zero();
+    let mut rhs = JacobianPoint::<Secp256k1>::identity();
+    for (idx, ((public_key, message), signature)) in public_keys.iter().zip(messages).zip(signatures).enumerate() {
+        let (r, s, p) = match parse(public_key, signature) {
+            Some(parsed) => parsed,
+            None => return false,
+        };
+        let e = challenge(&signature[..32], public_key, message);
+        let a = if idx == 0 {
+            U512::from_u64(1)
+        } else {
+            U512::random_in_range(U512::from_u64(1), N, rng)
+        };
+        let a_mont = m.to_mont(a);
+        s_sum = m.add(s_sum, m.mul(a_mont, s));
+        rhs = rhs + JacobianPoint::from(r).double_mul_glv(a, &JacobianPoint::from(p), m.mul(a_mont, e));
+    }
+    JacobianPoint::from(Point::generator()).mul_glv(s_sum) == rhs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tagged_hash_parts() {
+        // The parts are simply concatenated
+        assert_eq!(tagged_hash("BIP0340/challenge", &[b"ab", b"c"]),
+                   tagged_hash("BIP0340/challenge", &[b"abc"]));
+        assert!(tagged_hash("BIP0340/aux", &[b"abc"]) != tagged_hash("BIP0340/nonce", &[b"abc"]));
+    }
+}
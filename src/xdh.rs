@@ -0,0 +1,110 @@
+//! X25519 and X448 Diffie-Hellman from RFC 7748. Only the u coordinate of
+//! points on the Montgomery curves is used, and scalar multiplication is a
+//! constant time Montgomery ladder.
+
+use bigint::{Montgomery, U512};
+
+/// The u coordinate of the X25519 base point.
+pub const X25519_BASE_POINT: [u8; 32] = [
+    9, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+    0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+];
+
+/// The u coordinate of the X448 base point.
+pub const X448_BASE_POINT: [u8; 56] = [
+    5, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+    0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+    0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+    0, 0, 0, 0, 0, 0, 0, 0,
+];
+
+// 2^255 - 19
+pub(crate) const P25519: U512 = U512::from_hex_be(b"7fffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffed");
+// 2^448 - 2^224 - 1
+pub(crate) const P448: U512 = U512::from_hex_be(b"fffffffffffffffffffffffffffffffffffffffffffffffffffffffe\
+                                                  ffffffffffffffffffffffffffffffffffffffffffffffffffffffff");
+
+/// The X25519 function: scalar * u, with both the scalar and the result
+/// encoded as 32 little endian bytes. Use `X25519_BASE_POINT` as u to get a
+/// public key. An all zero result means u was a low order point, which
+/// protocols may want to reject.
+pub fn x25519(scalar: [u8; 32], u: [u8; 32]) -> [u8; 32] {
+    let mut scalar = scalar;
+    scalar[0] &= 248;
+    scalar[31] &= 127;
+    scalar[31] |= 64;
+
+    // The top bit of u is ignored
+    let mut u = u;
+    u[31] &= 127;
+
+    let ans = ladder(P25519, 121665, 255, U512::from_bytes_le(scalar.to_vec()),
+                     U512::from_bytes_le(u.to_vec()));
+    let mut out = [0u8; 32];
+    out.clone_from_slice(&ans.to_bytes_le()[..32]);
+    out
+}
+
+/// The X448 function: scalar * u, with both the scalar and the result
+/// encoded as 56 little endian bytes. Use `X448_BASE_POINT` as u to get a
+/// public key.
+pub fn x448(scalar: [u8; 56], u: [u8; 56]) -> [u8; 56] {
+    let mut scalar = scalar;
+    scalar[0] &= 252;
+    scalar[55] |= 128;
+
+    let ans = ladder(P448, 39081, 448, U512::from_bytes_le(scalar.to_vec()),
+                     U512::from_bytes_le(u.to_vec()));
+    let mut out = [0u8; 56];
+    out.clone_from_slice(&ans.to_bytes_le()[..56]);
+    out
+}
+
+// Swaps a and b if choice is true, without branching on choice
+fn cswap(a: &mut U512, b: &mut U512, choice: bool) {
+    let old_a = *a;
+    a.conditional_assign(b, choice);
+    b.conditional_assign(&old_a, choice);
+}
+
+// The ladder from section 5 of RFC 7748. Every step does the same
+// operations, and the two running points are swapped according to each bit
+// of the scalar with cswap instead of a branch. a24 is (A - 2) / 4 for the
+// curve coefficient A.
+fn ladder(p: U512, a24: u64, bits: usize, k: U512, u: U512) -> U512 {
+    let m = Montgomery::new(p);
+    // u may be any value that fits in the encoding, and is taken mod p
+    let x1 = m.to_mont(u % p);
+    let a24 = m.to_mont(U512::from_u64(a24));
+
+    let (mut x2, mut z2) = (m.one(), U512::zero());
+    let (mut x3, mut z3) = (x1, m.one());
+    let mut swap = false;
+    for idx in (0..bits).rev() {
+        let bit = k.bit(idx);
+        swap ^= bit;
+        cswap(&mut x2, &mut x3, swap);
+        cswap(&mut z2, &mut z3, swap);
+        swap = bit;
+
+        let a = m.add(x2, z2);
+        let aa = m.square(a);
+        let b = m.sub(x2, z2);
+        let bb = m.square(b);
+        let e = m.sub(aa, bb);
+        let c = m.add(x3, z3);
+        let d = m.sub(x3, z3);
+        let da = m.mul(d, a);
+        let cb = m.mul(c, b);
+        x3 = m.square(m.add(da, cb));
+        z3 = m.mul(x1, m.square(m.sub(da, cb)));
+        x2 = m.mul(aa, bb);
+        z2 = m.mul(e, m.add(aa, m.mul(a24, e)));
+    }
+    cswap(&mut x2, &mut x3, swap);
+    cswap(&mut z2, &mut z3, swap);
+
+    // x2 / z2, where z2^(p - 2) is 0 when z2 is
+    let z2_inv = m.pow(z2, p - U512::from_u64(2));
+    m.from_mont(m.mul(x2, z2_inv))
+}
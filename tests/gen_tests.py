@@ -1,3 +1,4 @@
+import math
 import operator
 import random
 
@@ -90,6 +91,144 @@ def gen_xor():
             y = random.randint(2 ** 255, 2 ** 511)
             write_test(operator.xor, x, y, f)
 
+def gen_mul_mod():
+    with open('mul_mod.data', 'w') as f:
+        for _ in range(NUM_TESTS):
+            x = random.randint(2 ** 255, 2 ** 511)
+            y = random.randint(2 ** 255, 2 ** 511)
+            m = random.randint(2 ** 20, 2 ** 511)
+            f.write('{}\t{}\t{}\t{}\n'.format(h(x), h(y), h(m), h(x * y % m)))
+
+def gen_pow_mod():
+    with open('pow_mod.data', 'w') as f:
+        # These are slow, so don't generate as many
+        for _ in range(NUM_TESTS // 10):
+            x = random.randint(2 ** 255, 2 ** 511)
+            e = random.randint(0, 2 ** random.randint(1, 512) - 1)
+            m = random.randint(2 ** 20, 2 ** 511)
+            # Even moduli take the slow path, so only test a few of them
+            if random.random() < 0.9:
+                m |= 1
+            f.write('{}\t{}\t{}\t{}\n'.format(h(x), h(e), h(m), h(pow(x, e, m))))
+
+def gen_multi_pow_mod():
+    with open('multi_pow_mod.data', 'w') as f:
+        for _ in range(NUM_TESTS // 50):
+            m = random.randint(2 ** 20, 2 ** 511) | 1
+            # Enough terms to exercise both the small and large count methods
+            n = random.randint(1, 48)
+            pairs = [(random.randint(0, 2 ** 511), random.randint(0, 2 ** 511))
+                     for _ in range(n)]
+            ans = 1
+            for (x, e) in pairs:
+                ans = ans * pow(x, e, m) % m
+            cols = [h(m), h(ans)] + [h(v) for pair in pairs for v in pair]
+            f.write('\t'.join(cols) + '\n')
+
+def gen_inv_mod():
+    with open('inv_mod.data', 'w') as f:
+        for _ in range(NUM_TESTS // 10):
+            m = random.randint(2 ** 20, 2 ** 511)
+            x = random.randint(1, 2 ** 511)
+            if math.gcd(x, m) == 1:
+                ans = h(pow(x, -1, m))
+            else:
+                ans = 'none'
+            f.write('{}\t{}\t{}\n'.format(h(x), h(m), ans))
+
+def signed_h(x):
+    return '-' + h(-x) if x < 0 else h(x)
+
+def gen_signed_div():
+    with open('signed_division.data', 'w') as f:
+        for _ in range(NUM_TESTS):
+            x = random.randint(-2 ** 510, 2 ** 510)
+            y = random.randint(1, 2 ** random.randint(1, 510)) * random.choice([-1, 1])
+            # Python's // rounds down, but I512 division truncates like C
+            q = abs(x) // abs(y) * (1 if (x < 0) == (y < 0) else -1)
+            r = x - q * y
+            # Euclidean remainder is never negative
+            re = x % abs(y)
+            qe = (x - re) // y
+            f.write('\t'.join(signed_h(v) for v in [x, y, q, r, qe, re]) + '\n')
+
+def gen_biguint():
+    with open('biguint.data', 'w') as f:
+        for _ in range(NUM_TESTS // 10):
+            x = random.randint(0, 2 ** random.randint(1, 4096))
+            y = random.randint(1, 2 ** random.randint(1, 4096))
+            shift = random.randint(0, 1000)
+            # No truncation here, these can be any size
+            cols = [x, y, x + y, abs(x - y), x * y, x // y, x % y, x << shift, x >> shift]
+            f.write('{}\t'.format(shift) + '\t'.join(hex(v)[2:] for v in cols) + '\n')
+
+def gen_biguint_pow_mod():
+    with open('biguint_pow_mod.data', 'w') as f:
+        for _ in range(NUM_TESTS // 50):
+            m = random.randint(2 ** 512, 2 ** random.randint(513, 4096))
+            x = random.randint(0, m * 2)
+            e = random.randint(0, m)
+            # Even moduli take the slow path, so only test a few of them
+            if random.random() < 0.9:
+                m |= 1
+            f.write('\t'.join(hex(v)[2:] for v in [x, e, m, pow(x, e, m)]) + '\n')
+
+# Short Weierstrass curves as (p, a, b, n, gx, gy)
+CURVES = {
+    'secp256k1': (
+        0xfffffffffffffffffffffffffffffffffffffffffffffffffffffffefffffc2f, 0, 7,
+        0xfffffffffffffffffffffffffffffffebaaedce6af48a03bbfd25e8cd0364141,
+        0x79be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798,
+        0x483ada7726a3c4655da4fbfc0e1108a8fd17b448a68554199c47d08ffb10d4b8),
+    'p256': (
+        0xffffffff00000001000000000000000000000000ffffffffffffffffffffffff,
+        0xffffffff00000001000000000000000000000000fffffffffffffffffffffffc,
+        0x5ac635d8aa3a93e7b3ebbd55769886bc651d06b0cc53b0f63bce3c3e27d2604b,
+        0xffffffff00000000ffffffffffffffffbce6faada7179e84f3b9cac2fc632551,
+        0x6b17d1f2e12c4247f8bce6e563a440f277037d812deb33a0f4a13945d898c296,
+        0x4fe342e2fe1a7f9b8ee7eb4a7c0f9e162bce33576b315ececbb6406837bf51f5),
+    'p384': (
+        0xfffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffeffffffff0000000000000000ffffffff,
+        0xfffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffeffffffff0000000000000000fffffffc,
+        0xb3312fa7e23ee7e4988e056be3f82d19181d9c6efe8141120314088f5013875ac656398d8a2ed19d2a85c8edd3ec2aef,
+        0xffffffffffffffffffffffffffffffffffffffffffffffffc7634d81f4372ddf581a0db248b0a77aecec196accc52973,
+        0xaa87ca22be8b05378eb1c71ef320ad746e1d3b628ba79b9859f741e082542a385502f25dbf55296c3a545e3872760ab7,
+        0x3617de4a96262c6f5d9e98bf9292dc29f8f41dbd289a147ce9da3113b5f0b8c00a60b1ce1d7e819d7a431d7c90ea0e5f),
+}
+
+# Affine addition, with None for the point at infinity
+def ec_add(curve, P, Q):
+    p, a = curve[0], curve[1]
+    if P is None:
+        return Q
+    if Q is None:
+        return P
+    if P[0] == Q[0] and (P[1] + Q[1]) % p == 0:
+        return None
+    if P == Q:
+        slope = (3 * P[0] * P[0] + a) * pow(2 * P[1], -1, p)
+    else:
+        slope = (Q[1] - P[1]) * pow(Q[0] - P[0], -1, p)
+    x = (slope * slope - P[0] - Q[0]) % p
+    return (x, (slope * (P[0] - x) - P[1]) % p)
+
+def ec_mul(curve, k, P):
+    ans = None
+    for bit in bin(k)[2:]:
+        ans = ec_add(curve, ans, ans)
+        if bit == '1':
+            ans = ec_add(curve, ans, P)
+    return ans
+
+def gen_ec_mul():
+    with open('ec_mul.data', 'w') as f:
+        for name, curve in sorted(CURVES.items()):
+            n = curve[3]
+            for _ in range(NUM_TESTS // 50):
+                k = random.choice([random.randint(1, n - 1), random.randint(1, 2 ** 64), n - random.randint(1, 2 ** 64)])
+                x, y = ec_mul(curve, k, (curve[4], curve[5]))
+                f.write('{}\t{}\t{}\t{}\n'.format(name, h(k), h(x), h(y)))
+
 if __name__ == '__main__':
     gen_div()
     gen_rem()
@@ -101,3 +240,11 @@ if __name__ == '__main__':
     gen_and()
     gen_or()
     gen_xor()
+    gen_mul_mod()
+    gen_pow_mod()
+    gen_multi_pow_mod()
+    gen_inv_mod()
+    gen_signed_div()
+    gen_biguint()
+    gen_biguint_pow_mod()
+    gen_ec_mul()
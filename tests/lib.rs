@@ -4,14 +4,27 @@ extern crate rand;
 use std::fs::File;
 use std::io::{BufRead, BufReader};
 
-use crypto_int::U512;
-use rand::OsRng;
+use crypto_int::dh;
+use crypto_int::dsa;
+use crypto_int::ecdh::{ecdh, Ecies, HmacStream};
+use crypto_int::ecdsa;
+use crypto_int::ed25519;
+use crypto_int::schnorr;
+use crypto_int::ed448;
+use crypto_int::hash_to_curve::{self, Shake256Xof, Xmd};
+use crypto_int::ristretto255::{self, RistrettoPoint};
+use crypto_int::rsa;
+use crypto_int::hash::{expand_message_xmd, expand_message_xof, hkdf_expand, hkdf_extract, hmac, sha256, sha384, sha512, shake256, Digest, Sha256, Sha384, Sha512};
+use crypto_int::ec::{AffinePoint, Curve, JacobianPoint, P256, P384, Secp256k1};
+use crypto_int::{BigUint, I512, U512};
+use crypto_int::{x25519, x448, X25519_BASE_POINT, X448_BASE_POINT};
+use rand::{OsRng, Rng};
 
 fn hex_char_to_u8(c: u8) -> u8 {
-    if 48 <= c && c <= 57 {
-        return c - 48
-    } else if 97 <= c && c <= 102 {
-        return c - 97 + 10
+    if (48..=57).contains(&c) {
+        c - 48
+    } else if (97..=102).contains(&c) {
+        c - 97 + 10
     } else {
         println!("What the fuck {}", c);
         panic!();
@@ -37,7 +50,11 @@ fn byte_str_to_bytes(s: &str) -> Vec<u8> {
 }
 
 fn byte_str_to_u512(s: &str) -> U512 {
-    U512::from_bytes_be(byte_str_to_bytes(&s))
+    U512::from_bytes_be(byte_str_to_bytes(s))
+}
+
+fn byte_str_to_biguint(s: &str) -> BigUint {
+    BigUint::from_bytes_be(byte_str_to_bytes(s))
 }
 
 #[test]
@@ -294,3 +311,1362 @@ fn random() {
         assert!(x >= low);
     }
 }
+
+#[test]
+fn mul_mod() {
+    let file = match File::open("./tests/mul_mod.data") {
+        Ok(fh) => fh,
+        Err(_) => panic!(),
+    };
+
+    let file = BufReader::new(file);
+    let one = U512::from_u64(1);
+    for line in file.lines() {
+        let line = match line {
+            Ok(l) => l,
+            Err(_) => panic!(),
+        };
+        let v: Vec<&str> = line.split("\t").collect();
+        let x = byte_str_to_u512(v[0].trim());
+        let y = byte_str_to_u512(v[1].trim());
+        let m = byte_str_to_u512(v[2].trim());
+        let ans = byte_str_to_u512(v[3].trim());
+        assert_eq!(x.mul_mod(y, m), ans);
+        assert_eq!(x.mul_mod(one, m), x % m);
+    }
+}
+
+#[test]
+fn pow_mod() {
+    let file = match File::open("./tests/pow_mod.data") {
+        Ok(fh) => fh,
+        Err(_) => panic!(),
+    };
+
+    let file = BufReader::new(file);
+    for line in file.lines() {
+        let line = match line {
+            Ok(l) => l,
+            Err(_) => panic!(),
+        };
+        let v: Vec<&str> = line.split("\t").collect();
+        let x = byte_str_to_u512(v[0].trim());
+        let e = byte_str_to_u512(v[1].trim());
+        let m = byte_str_to_u512(v[2].trim());
+        let ans = byte_str_to_u512(v[3].trim());
+        assert_eq!(x.pow_mod(e, m), ans);
+        assert_eq!(x.pow_mod_window(e, m, 1), ans);
+        assert_eq!(x.pow_mod_window(e, m, 7), ans);
+        if !m.is_even() {
+            assert_eq!(x.pow_mod_ct(e, m), ans);
+        }
+    }
+}
+
+#[test]
+fn multi_pow_mod() {
+    let file = match File::open("./tests/multi_pow_mod.data") {
+        Ok(fh) => fh,
+        Err(_) => panic!(),
+    };
+
+    let file = BufReader::new(file);
+    for line in file.lines() {
+        let line = match line {
+            Ok(l) => l,
+            Err(_) => panic!(),
+        };
+        let v: Vec<&str> = line.split("\t").collect();
+        let m = byte_str_to_u512(v[0].trim());
+        let ans = byte_str_to_u512(v[1].trim());
+        let pairs: Vec<(U512, U512)> = v[2..].chunks(2)
+            .map(|p| (byte_str_to_u512(p[0].trim()), byte_str_to_u512(p[1].trim())))
+            .collect();
+        assert_eq!(crypto_int::multi_pow_mod(&pairs, m), ans);
+    }
+}
+
+#[test]
+fn inv_mod() {
+    let file = match File::open("./tests/inv_mod.data") {
+        Ok(fh) => fh,
+        Err(_) => panic!(),
+    };
+
+    let file = BufReader::new(file);
+    for line in file.lines() {
+        let line = match line {
+            Ok(l) => l,
+            Err(_) => panic!(),
+        };
+        let v: Vec<&str> = line.split("\t").collect();
+        let x = byte_str_to_u512(v[0].trim());
+        let m = byte_str_to_u512(v[1].trim());
+        let ans = match v[2].trim() {
+            "none" => None,
+            s => Some(byte_str_to_u512(s)),
+        };
+        assert_eq!(x.inv_mod(m), ans);
+    }
+}
+
+#[test]
+fn batch_inv_mod() {
+    let mut rng = OsRng::new().unwrap();
+    let p = U512::from_hex_be(b"ffffffff00000001000000000000000000000000ffffffffffffffffffffffff");
+    let one = U512::from_u64(1);
+    let zero = U512::zero();
+
+    let mut values: Vec<U512> = (0..20).map(|_| U512::random_in_range(one, p, &mut rng)).collect();
+    values[3] = zero;
+    values[17] = p;
+    // Values don't have to be reduced
+    values[5] = p + U512::from_u64(2);
+    values[9] = !zero;
+    let original = values.clone();
+    let failed = crypto_int::batch_inv_mod(&mut values, p);
+    assert_eq!(failed, vec![3, 17]);
+    for (idx, (x, inv)) in original.iter().zip(values.iter()).enumerate() {
+        if failed.contains(&idx) {
+            assert_eq!(x, inv);
+        } else {
+            assert_eq!(x.mul_mod(*inv, p), one);
+        }
+    }
+
+    // Composite and even moduli have other values without inverses
+    for m in [U512::from_u64(15), U512::from_u64(16)].iter() {
+        let mut values: Vec<U512> = (0..16).map(U512::from_u64).collect();
+        let failed = crypto_int::batch_inv_mod(&mut values, *m);
+        for (idx, val) in values.iter().enumerate() {
+            let x = U512::from_u64(idx as u64);
+            match x.inv_mod(*m) {
+                Some(inv) => assert_eq!(*val, inv),
+                None => assert!(failed.contains(&idx)),
+            }
+        }
+    }
+}
+
+#[test]
+fn signed_division() {
+    let file = match File::open("./tests/signed_division.data") {
+        Ok(fh) => fh,
+        Err(_) => panic!(),
+    };
+
+    let file = BufReader::new(file);
+    for line in file.lines() {
+        let line = match line {
+            Ok(l) => l,
+            Err(_) => panic!(),
+        };
+        let v: Vec<I512> = line.split("\t").map(|s| s.trim().parse().unwrap()).collect();
+        let (x, y) = (v[0], v[1]);
+        assert_eq!(x / y, v[2]);
+        assert_eq!(x % y, v[3]);
+        assert_eq!(x.div_euclid(y), v[4]);
+        assert_eq!(x.rem_euclid(y), v[5]);
+        assert_eq!(x / y * y + x % y, x);
+        assert_eq!(x.to_string().parse(), Ok(x));
+    }
+}
+
+#[test]
+fn biguint() {
+    let file = match File::open("./tests/biguint.data") {
+        Ok(fh) => fh,
+        Err(_) => panic!(),
+    };
+
+    let file = BufReader::new(file);
+    for line in file.lines() {
+        let line = match line {
+            Ok(l) => l,
+            Err(_) => panic!(),
+        };
+        let v: Vec<&str> = line.split("\t").collect();
+        let shift: usize = match v[0].trim().parse() {
+            Ok(n) => n,
+            Err(_) => panic!(),
+        };
+        let v: Vec<BigUint> = v[1..].iter()
+            .map(|s| BigUint::from_bytes_be(byte_str_to_bytes(s.trim())))
+            .collect();
+        let (x, y) = (&v[0], &v[1]);
+        assert_eq!(x + y, v[2]);
+        if x >= y {
+            assert_eq!(x - y, v[3]);
+        } else {
+            assert_eq!(y - x, v[3]);
+        }
+        assert_eq!(x * y, v[4]);
+        assert_eq!(x.square(), x * x);
+        assert_eq!(x / y, v[5]);
+        assert_eq!(x % y, v[6]);
+        assert_eq!(x.clone() << shift, v[7]);
+        assert_eq!(x.clone() >> shift, v[8]);
+        assert_eq!(x.to_string(), line.split("\t").nth(1).unwrap().trim());
+    }
+}
+
+#[test]
+fn biguint_pow_mod() {
+    let file = match File::open("./tests/biguint_pow_mod.data") {
+        Ok(fh) => fh,
+        Err(_) => panic!(),
+    };
+
+    let file = BufReader::new(file);
+    for line in file.lines() {
+        let line = match line {
+            Ok(l) => l,
+            Err(_) => panic!(),
+        };
+        let v: Vec<BigUint> = line.split("\t").map(|s| byte_str_to_biguint(s.trim())).collect();
+        let (x, e, m) = (&v[0], &v[1], &v[2]);
+        assert_eq!(x.pow_mod(e, m), v[3]);
+        if !m.is_even() {
+            assert_eq!(x.pow_mod_ct(e, m), v[3]);
+        }
+    }
+}
+
+fn check_ec_mul<C: Curve>(k: U512, x: U512, y: U512) {
+    let g = AffinePoint::<C>::generator();
+    let ans = g.mul(k);
+    assert_eq!(ans.coordinates(), Some((x, y)));
+    assert!(ans.is_on_curve());
+    assert_eq!(JacobianPoint::from(g).mul_vartime(k).to_affine(), ans);
+}
+
+#[test]
+fn ec_mul() {
+    let file = match File::open("./tests/ec_mul.data") {
+        Ok(fh) => fh,
+        Err(_) => panic!(),
+    };
+
+    let file = BufReader::new(file);
+    for line in file.lines() {
+        let line = match line {
+            Ok(l) => l,
+            Err(_) => panic!(),
+        };
+        let v: Vec<&str> = line.split("\t").collect();
+        let k = byte_str_to_u512(v[1].trim());
+        let x = byte_str_to_u512(v[2].trim());
+        let y = byte_str_to_u512(v[3].trim());
+        match v[0] {
+            "secp256k1" => check_ec_mul::<Secp256k1>(k, x, y),
+            "p256" => check_ec_mul::<P256>(k, x, y),
+            "p384" => check_ec_mul::<P384>(k, x, y),
+            _ => panic!(),
+        }
+    }
+}
+
+// len is the length of a coordinate in bytes
+fn check_sec1<C: Curve>(len: usize) {
+    let mut rng = OsRng::new().unwrap();
+    let g = AffinePoint::<C>::generator();
+    for _ in 0..20 {
+        let point = g.mul(U512::random_in_range(U512::from_u64(1), C::N, &mut rng));
+        let (x, y) = point.coordinates().unwrap();
+        let compressed = point.to_sec1_bytes(true);
+        let uncompressed = point.to_sec1_bytes(false);
+        assert_eq!(compressed.len(), 1 + len);
+        assert_eq!(uncompressed.len(), 1 + 2 * len);
+        assert_eq!(AffinePoint::<C>::from_sec1_bytes(&compressed), Some(point));
+        assert_eq!(AffinePoint::<C>::from_sec1_bytes(&uncompressed), Some(point));
+        assert_eq!(AffinePoint::<C>::from_sec1_bytes(&(-point).to_sec1_bytes(true)), Some(-point));
+
+        // The hybrid form has to have the right parity for y
+        let mut hybrid = uncompressed.clone();
+        hybrid[0] = 6 | (y.to_bytes_be()[63] & 1);
+        assert_eq!(AffinePoint::<C>::from_sec1_bytes(&hybrid), Some(point));
+        hybrid[0] ^= 1;
+        assert_eq!(AffinePoint::<C>::from_sec1_bytes(&hybrid), None);
+
+        // Wrong lengths and tags
+        assert_eq!(AffinePoint::<C>::from_sec1_bytes(&compressed[..len]), None);
+        assert_eq!(AffinePoint::<C>::from_sec1_bytes(&uncompressed[..2 * len]), None);
+        let mut bad_tag = compressed.clone();
+        bad_tag[0] = 5;
+        assert_eq!(AffinePoint::<C>::from_sec1_bytes(&bad_tag), None);
+
+        // Off the curve
+        let mut bad_y = uncompressed.clone();
+        bad_y[2 * len] ^= 1;
+        assert_eq!(AffinePoint::<C>::from_sec1_bytes(&bad_y), None);
+
+        // x + p encodes the same x when it fits, but isn't canonical
+        let x_plus_p = x + C::P;
+        if x_plus_p.to_bytes_be()[..64 - len].iter().all(|&b| b == 0) {
+            let mut bad_x = compressed.clone();
+            bad_x[1..].copy_from_slice(&x_plus_p.to_bytes_be()[64 - len..]);
+            assert_eq!(AffinePoint::<C>::from_sec1_bytes(&bad_x), None);
+        }
+    }
+
+    let identity = AffinePoint::<C>::identity();
+    assert_eq!(identity.to_sec1_bytes(true), vec![0]);
+    assert_eq!(AffinePoint::<C>::from_sec1_bytes(&[0]), Some(identity));
+    assert_eq!(AffinePoint::<C>::from_sec1_bytes(&[]), None);
+
+    // About half of all x coordinates aren't on the curve
+    let mut misses = 0;
+    for idx in 0..20u8 {
+        let mut bytes = vec![0u8; 1 + len];
+        bytes[0] = 2;
+        bytes[len] = idx;
+        if AffinePoint::<C>::from_sec1_bytes(&bytes).is_none() {
+            misses += 1;
+        }
+    }
+    assert!(misses > 0 && misses < 20);
+}
+
+#[test]
+fn sec1() {
+    check_sec1::<Secp256k1>(32);
+    check_sec1::<P256>(32);
+    check_sec1::<P384>(48);
+
+    let g = AffinePoint::<P256>::generator();
+    assert_eq!(g.to_sec1_bytes(true),
+               byte_str_to_bytes("036b17d1f2e12c4247f8bce6e563a440f277037d812deb33a0f4a13945d898c296"));
+    assert_eq!(g.to_sec1_bytes(false),
+               byte_str_to_bytes("046b17d1f2e12c4247f8bce6e563a440f277037d812deb33a0f4a13945d898c296\
+                                  4fe342e2fe1a7f9b8ee7eb4a7c0f9e162bce33576b315ececbb6406837bf51f5"));
+}
+
+fn hex_to_array<const N: usize>(s: &str) -> [u8; N] {
+    let mut ans = [0u8; N];
+    ans.clone_from_slice(&byte_str_to_bytes(s));
+    ans
+}
+
+// Test vectors from RFC 7748 sections 5.2 and 6.1
+#[test]
+fn rfc7748_x25519() {
+    let vectors = [
+        ("a546e36bf0527c9d3b16154b82465edd62144c0ac1fc5a18506a2244ba449ac4",
+         "e6db6867583030db3594c1a424b15f7c726624ec26b3353b10a903a6d0ab1c4c",
+         "c3da55379de9c6908e94ea4df28d084f32eccf03491c71f754b4075577a28552"),
+        ("4b66e9d4d1b4673c5ad22691957d6af5c11b6421e0ea01d42ca4169e7918ba0d",
+         "e5210f12786811d3f4b7959d0538ae2c31dbe7106fc03c3efc4cd549c715a493",
+         "95cbde9476e8907d7aade45cb4b873f88b595a68799fa152e6f8f7647aac7957"),
+    ];
+    for &(scalar, u, ans) in vectors.iter() {
+        assert_eq!(x25519(hex_to_array(scalar), hex_to_array(u)), hex_to_array(ans));
+    }
+
+    let alice: [u8; 32] = hex_to_array("77076d0a7318a57d3c16c17251b26645df4c2f87ebc0992ab177fba51db92c2a");
+    let bob: [u8; 32] = hex_to_array("5dab087e624a8a4b79e17f8b83800ee66f3bb1292618b6fd1c2f8b27ff88e0eb");
+    let alice_pub = x25519(alice, X25519_BASE_POINT);
+    let bob_pub = x25519(bob, X25519_BASE_POINT);
+    assert_eq!(alice_pub, hex_to_array("8520f0098930a754748b7ddcb43ef75a0dbf3a0d26381af4eba4a98eaa9b4e6a"));
+    assert_eq!(bob_pub, hex_to_array("de9edb7d7b7dc1b4d35b61c2ece435373f8343c85b78674dadfc7e146f882b4f"));
+    let shared: [u8; 32] = hex_to_array("4a5d9d5ba4ce2de1728e3bf480350f25e07e21c947d19e3376f09b3c1e161742");
+    assert_eq!(x25519(alice, bob_pub), shared);
+    assert_eq!(x25519(bob, alice_pub), shared);
+}
+
+#[test]
+fn rfc7748_x448() {
+    let scalar = "3d262fddf9ec8e88495266fea19a34d28882acef045104d0d1aae121700a779c984c24f8cdd78fbff44943eba368f54b29259a4f1c600ad3";
+    let u = "06fce640fa3487bfda5f6cf2d5263f8aad88334cbd07437f020f08f9814dc031ddbdc38c19c6da2583fa5429db94ada18aa7a7fb4ef8a086";
+    let ans = "ce3e4ff95a60dc6697da1db1d85e6afbdf79b50a2412d7546d5f239fe14fbaadeb445fc66a01b0779d98223961111e21766282f73dd96b6f";
+    assert_eq!(x448(hex_to_array(scalar), hex_to_array(u)), hex_to_array(ans));
+
+    let scalar = "203d494428b8399352665ddca42f9de8fef600908e0d461cb021f8c538345dd77c3e4806e25f46d3315c44e0a5b4371282dd2c8d5be3095f";
+    let u = "0fbcc2f993cd56d3305b0b7d9e55d4c1a8fb5dbb52f8e9a1e9b6201b165d015894e56c4d3570bee52fe205e28a78b91cdfbde71ce8d157db";
+    let ans = "884a02576239ff7a2f2f63b2db6a9ff37047ac13568e1e30fe63c4a7ad1b3ee3a5700df34321d62077e63633c575c1c954514e99da7c179d";
+    assert_eq!(x448(hex_to_array(scalar), hex_to_array(u)), hex_to_array(ans));
+}
+
+// Section 5.2: start with k = u = the base point, then repeatedly set
+// k = X(k, u) and u to the old k.
+#[test]
+fn rfc7748_iterated() {
+    let (mut k, mut u) = (X25519_BASE_POINT, X25519_BASE_POINT);
+    for idx in 1..=1000 {
+        let next = x25519(k, u);
+        u = k;
+        k = next;
+        if idx == 1 {
+            assert_eq!(k, hex_to_array("422c8e7a6227d7bca1350b3e2bb7279f7897b87bb6854b783c60e80311ae3079"));
+        }
+    }
+    assert_eq!(k, hex_to_array("684cf59ba83309552800ef566f2f4d3c1c3887c49360e3875f2eb94d99532c51"));
+
+    let (mut k, mut u) = (X448_BASE_POINT, X448_BASE_POINT);
+    for idx in 1..=1000 {
+        let next = x448(k, u);
+        u = k;
+        k = next;
+        if idx == 1 {
+            assert_eq!(k, hex_to_array("3f482c8a9f19b01e6c46ee9711d9dc14fd4bf67af30765c2ae2b846a4d23a8cd\
+                                        0db897086239492caf350b51f833868b9bc2b3bca9cf4113"));
+        }
+    }
+    assert_eq!(k, hex_to_array("aa3b4749d55b9daf1e5b00288826c467274ce3ebbdd5c17b975e09d4af6c67cf\
+                                10d087202db88286e2b79fceea3ec353ef54faa26e219f38"));
+}
+
+#[test]
+fn sha512_vectors() {
+    let vectors: [(&[u8], &str); 3] = [
+        (b"", "cf83e1357eefb8bdf1542850d66d8007d620e4050b5715dc83f4a921d36ce9ce\
+               47d0d13c5d85f2b0ff8318d2877eec2f63b931bd47417a81a538327af927da3e"),
+        (b"abc", "ddaf35a193617abacc417349ae20413112e6fa4e89a97ea20a9eeee64b55d39a\
+                  2192992a274fc1a836ba3c23a3feebbd454d4423643ce80e2a9ac94fa54ca49f"),
+        (b"abcdefghbcdefghicdefghijdefghijkefghijklfghijklmghijklmnhijklmno\
+           ijklmnopjklmnopqklmnopqrlmnopqrsmnopqrstnopqrstu",
+         "8e959b75dae313da8cf4f72814fc143f8f7779c6eb9f7fa17299aeadb6889018\
+          501d289e4900f7e4331b99dec4b5433ac7d329eeb6dd26545e96e55b874be909"),
+    ];
+    for &(message, ans) in vectors.iter() {
+        assert_eq!(sha512(message).to_vec(), byte_str_to_bytes(ans));
+    }
+
+    // A million a's, fed in uneven pieces
+    let mut hasher = Sha512::new();
+    let data = vec![b'a'; 1000];
+    for idx in 0..1000 {
+        let split = idx % 200;
+        hasher.update(&data[..split]);
+        hasher.update(&data[split..]);
+    }
+    assert_eq!(hasher.finalize().to_vec(),
+               byte_str_to_bytes("e718483d0ce769644e2e42c7bc15b4638e1f98b13b2044285632a803afa973eb\
+                                  de0ff244877ea60a4cb0432ce577c31beb009c5c2c49aa2e4eadb217ad8cc09b"));
+}
+
+#[test]
+fn sha384_vectors() {
+    let vectors: [(&[u8], &str); 3] = [
+        (b"", "38b060a751ac96384cd9327eb1b1e36a21fdb71114be0743\
+               4c0cc7bf63f6e1da274edebfe76f65fbd51ad2f14898b95b"),
+        (b"abc", "cb00753f45a35e8bb5a03d699ac65007272c32ab0eded163\
+                  1a8b605a43ff5bed8086072ba1e7cc2358baeca134c825a7"),
+        (b"abcdefghbcdefghicdefghijdefghijkefghijklfghijklmghijklmnhijklmno\
+           ijklmnopjklmnopqklmnopqrlmnopqrsmnopqrstnopqrstu",
+         "09330c33f71147e83d192fc782cd1b4753111b173b3b05d2\
+          2fa08086e3b0f712fcc7c71a557e2db966c3e9fa91746039"),
+    ];
+    for &(message, ans) in vectors.iter() {
+        assert_eq!(sha384(message).to_vec(), byte_str_to_bytes(ans));
+    }
+
+    let mut hasher = Sha384::new();
+    let data = vec![b'a'; 1000];
+    for _ in 0..1000 {
+        hasher.update(&data);
+    }
+    assert_eq!(hasher.finalize().to_vec(),
+               byte_str_to_bytes("9d0e1809716474cb086e834e310a4a1ced149e9c00f24852\
+                                  7972cec5704c2a5b07b8b3dc38ecc4ebae97ddd87f3d8985"));
+}
+
+// The first few byte oriented ShortMsg vectors from the NIST CAVP
+// SHA256ShortMsg, SHA384ShortMsg and SHA512ShortMsg files
+#[test]
+fn cavp_short_msg() {
+    let sha256_vectors = [
+        ("d3", "28969cdfa74a12c82f3bad960b0b000aca2ac329deea5c2328ebc6f2ba9802c1"),
+        ("11af", "5ca7133fa735326081558ac312c620eeca9970d1e70a4b95533d956f072d1f98"),
+        ("b4190e", "dff2e73091f6c05e528896c4c831b9448653dc2ff043528f6769437bc7b975c2"),
+        ("74ba2521", "b16aa56be3880d18cd41e68384cf1ec8c17680c45a02b1575dc1518923ae8b0e"),
+        ("c299209682", "f0887fe961c9cd3beab957e8222494abb969b1ce4c6557976df8b0f6d20e9166"),
+        ("e1dc724d5621", "eca0a060b489636225b4fa64d267dabbe44273067ac679f20820bddc6b6a90ac"),
+        ("06e076f5a442d5", "3fd877e27450e6bbd5d74bb82f9870c64c66e109418baa8e6bbcff355e287926"),
+        ("5738c929c4f4ccb6", "963bb88f27f512777aab6c8b1a02c70ec0ad651d428f870036e1917120fb48bf"),
+        ("3334c58075d3f4139e", "078da3d77ed43bd3037a433fd0341855023793f9afd08b4b08ea1e5597ceef20"),
+    ];
+    for &(msg, md) in sha256_vectors.iter() {
+        assert_eq!(sha256(&byte_str_to_bytes(msg)).to_vec(), byte_str_to_bytes(md));
+    }
+
+    let sha384_vectors = [
+        ("c5", "b52b72da75d0666379e20f9b4a79c33a329a01f06a2fb786\
+                5c9062a28c1de860ba432edfd86b4cb1cb8a75b46076e3b1"),
+        ("6ece", "53d4773da50d8be4145d8f3a7098ff3691a554a29ae6f652\
+                  cc7121eb8bc96fd2210e06ae2fa2a36c4b3b3497341e70f0"),
+        ("50e3853d", "936a3c3991716ba4c413bc03de20f5ce1c63703b3a5bdb6a\
+                      b558c9ff70d537e46eb4a15d9f2c85e68d8678de5682695e"),
+    ];
+    for &(msg, md) in sha384_vectors.iter() {
+        assert_eq!(sha384(&byte_str_to_bytes(msg)).to_vec(), byte_str_to_bytes(md));
+    }
+
+    let sha512_vectors = [
+        ("21", "3831a6a6155e509dee59a7f451eb35324d8f8f2df6e3708894740f98fdee2388\
+                9f4de5adb0c5010dfb555cda77c8ab5dc902094c52de3278f35a75ebc25f093a"),
+        ("9083", "55586ebba48768aeb323655ab6f4298fc9f670964fc2e5f2731e34dfa4b0c09e\
+                  6e1e12e3d7286b3145c61c2047fb1a2a1297f36da64160b31fa4c8c2cddd2fb4"),
+        ("0a55db", "7952585e5330cb247d72bae696fc8a6b0f7d0804577e347d99bc1b11e52f3849\
+                    85a428449382306a89261ae143c2f3fb613804ab20b42dc097e5bf4a96ef919b"),
+    ];
+    for &(msg, md) in sha512_vectors.iter() {
+        assert_eq!(sha512(&byte_str_to_bytes(msg)).to_vec(), byte_str_to_bytes(md));
+    }
+}
+
+#[test]
+fn sha256_vectors() {
+    let vectors: [(&[u8], &str); 3] = [
+        (b"", "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"),
+        (b"abc", "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"),
+        (b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq",
+         "248d6a61d20638b8e5c026930c3e6039a33ce45964ff2167f6ecedd419db06c1"),
+    ];
+    for &(message, ans) in vectors.iter() {
+        assert_eq!(sha256(message).to_vec(), byte_str_to_bytes(ans));
+    }
+
+    // A million a's, fed in uneven pieces
+    let mut hasher = Sha256::new();
+    let data = vec![b'a'; 1000];
+    for idx in 0..1000 {
+        let split = idx % 200;
+        hasher.update(&data[..split]);
+        hasher.update(&data[split..]);
+    }
+    assert_eq!(hasher.finalize().to_vec(),
+               byte_str_to_bytes("cdc76e5c9914fb9281a1c7e284d73e67f1809a48a497200e046d39ccc7112cd0"));
+}
+
+// Test cases 1, 2, 6 and 7 from RFC 4231
+#[test]
+fn rfc4231_hmac() {
+    // Key, data, and the HMAC with SHA-256, SHA-384 and SHA-512
+    type Vector<'a> = (&'a [u8], &'a [u8], &'a str, &'a str, &'a str);
+    let long_key = vec![0xaa; 131];
+    let vectors: [Vector; 4] = [
+        (&[0x0b; 20], b"Hi There",
+         "b0344c61d8db38535ca8afceaf0bf12b881dc200c9833da726e9376c2e32cff7",
+         "afd03944d84895626b0825f4ab46907f15f9dadbe4101ec6\
+          82aa034c7cebc59cfaea9ea9076ede7f4af152e8b2fa9cb6",
+         "87aa7cdea5ef619d4ff0b4241a1d6cb02379f4e2ce4ec2787ad0b30545e17cde\
+          daa833b7d6b8a702038b274eaea3f4e4be9d914eeb61f1702e696c203a126854"),
+        (b"Jefe", b"what do ya want for nothing?",
+         "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843",
+         "af45d2e376484031617f78d2b58a6b1b9c7ef464f5a01b47\
+          e42ec3736322445e8e2240ca5e69e2c78b3239ecfab21649",
+         "164b7a7bfcf819e2e395fbe73b56e0a387bd64222e831fd610270cd7ea250554\
+          9758bf75c05a994a6d034f65f8f0e6fdcaeab1a34d4a6b4b636e070a38bce737"),
+        (&long_key, b"Test Using Larger Than Block-Size Key - Hash Key First",
+         "60e431591ee0b67f0d8a26aacbf5b77f8e0bc6213728c5140546040f0ee37f54",
+         "4ece084485813e9088d2c63a041bc5b44f9ef1012a2b588f\
+          3cd11f05033ac4c60c2ef6ab4030fe8296248df163f44952",
+         "80b24263c7c1a3ebb71493c1dd7be8b49b46d1f41b4aeec1121b013783f8f352\
+          6b56d037e05f2598bd0fd2215d6a1e5295e64f73f63f0aec8b915a985d786598"),
+        (&long_key, b"This is a test using a larger than block-size key and a larger than block-size data. \
+                      The key needs to be hashed before being used by the HMAC algorithm.",
+         "9b09ffa71b942fcb27635fbcd5b0e944bfdc63644f0713938a7f51535c3a35e2",
+         "6617178e941f020d351e2f254e8fd32c602420feb0b8fb9a\
+          dccebb82461e99c5a678cc31e799176d3860e6110c46523e",
+         "e37b6a775dc87dbaa4dfa9f96e5e3ffddebd71f8867289865df5a32d20cdc944\
+          b6022cac3c4982b10d5eeb55c3e4de15134676fb6de0446065c97440fa8c6a58"),
+    ];
+    for &(key, data, ans256, ans384, ans512) in vectors.iter() {
+        assert_eq!(hmac::<Sha256>(key, data).to_vec(), byte_str_to_bytes(ans256));
+        assert_eq!(hmac::<Sha384>(key, data).to_vec(), byte_str_to_bytes(ans384));
+        assert_eq!(hmac::<Sha512>(key, data).to_vec(), byte_str_to_bytes(ans512));
+    }
+}
+
+// Test cases 1 and 3 from RFC 5869
+#[test]
+fn rfc5869_hkdf() {
+    let ikm = [0x0b; 22];
+    let salt: Vec<u8> = (0..13).collect();
+    let info: Vec<u8> = (0xf0..0xfa).collect();
+    let prk = hkdf_extract::<Sha256>(&salt, &ikm);
+    assert_eq!(prk.to_vec(),
+               byte_str_to_bytes("077709362c2e32df0ddc3f0dc47bba6390b6c73bb50f9c3122ec844ad7c2b3e5"));
+    assert_eq!(hkdf_expand::<Sha256>(&prk, &info, 42),
+               byte_str_to_bytes("3cb25f25faacd57a90434f64d0362f2a2d2d0a90cf1a5a4c5db02d56ecc4c5bf\
+                                  34007208d5b887185865"));
+
+    let prk = hkdf_extract::<Sha256>(&[], &ikm);
+    assert_eq!(hkdf_expand::<Sha256>(&prk, &[], 42),
+               byte_str_to_bytes("8da4e775a563c18f715f802a063c5a31b8a11f5c5ee1879ec3454e5f3c738d2d\
+                                  9d201395faa4b61a96c8"));
+}
+
+fn check_ecdh<C: Curve>(private_key: &str, peer_x: &str, peer_y: &str, shared: &str) {
+    let private_key = byte_str_to_u512(private_key);
+    let peer = AffinePoint::<C>::new(byte_str_to_u512(peer_x), byte_str_to_u512(peer_y)).unwrap();
+    assert_eq!(ecdh(private_key, &peer), Some(byte_str_to_bytes(shared)));
+    assert_eq!(ecdh(private_key, &AffinePoint::<C>::identity()), None);
+
+    // Both sides get the same secret
+    let mut rng = OsRng::new().unwrap();
+    let other_key = ecdsa::generate_key::<C, _>(&mut rng);
+    let other_public = ecdsa::public_key::<C>(other_key);
+    assert_eq!(ecdh(private_key, &other_public), ecdh(other_key, &ecdsa::public_key::<C>(private_key)));
+}
+
+// Made with the Python cryptography package
+#[test]
+fn ecdh_vectors() {
+    check_ecdh::<Secp256k1>("75fd68c32ce1beaf2eb44716519270f68e7ad1d797423d1fd6b1eeaaae557f60",
+                            "25fdc5b23119a2a9013a81213d8f1bcdfb15344cdebcd97e5460f23e0f29294e",
+                            "d3ae93e5b3dd015f9a2c22d357458ab98b02d6b7ad0da45599cb44c9708968ff",
+                            "7d7c919ca2c129fc7723686051e8d696536fdce45b223a85091df039800578ad");
+    check_ecdh::<P256>("344e5d4b85e907664ec1883da424c51f75dd378bf2203f9192d0696048dd6256",
+                       "b5cd09cc76a6adc40830153466f88da7ccdcd17858975ce444adaf71aedb26d6",
+                       "642f6db49a4c8c4abd0017c6cf74c9886462a05223d858d62ea87496e2c6efbe",
+                       "5cacf8fa13e22ee53cfb0f0a489af5e8dee04c574fb00f68f24da846bb3fad3e");
+    check_ecdh::<P384>("0f822d2daf22036a741250f8c1bf1c0415a63986ed403e2cf23c40b8eb6ff8ee\
+                        2618fc94aca5ccef0159e74e9ad81b49",
+                       "dc1bd16a9103fc617e1fdce56e84b2686bad362bcb5ab5a93536dcd961bc4489\
+                        5aaeccdef9aeebaf9c6a3d3c4e092699",
+                       "2c9c5493f1320f2194c3c8e72f6f5884eb1738c90e76cf362d26c0f21ffbd4cd\
+                        c2902bb14f8cb41701d929d64e55d4e9",
+                       "30aaf28dd84558e2ed933d64b976be5241758773d1e5260d96a12d88f4fda690\
+                        2137e1da0a64add6ba3ff2f30e23a61e");
+}
+
+fn check_ecies<C: Curve>() {
+    type Cipher = HmacStream<Sha256>;
+    let mut rng = OsRng::new().unwrap();
+    let private_key = ecdsa::generate_key::<C, _>(&mut rng);
+    let public_key = ecdsa::public_key::<C>(private_key);
+
+    for len in [0, 1, 31, 32, 33, 100].iter() {
+        let plaintext: Vec<u8> = (0..*len as u8).collect();
+        let ciphertext = Ecies::<C, Sha256, Cipher>::encrypt(&public_key, &plaintext, &mut rng).unwrap();
+        assert_eq!(Ecies::<C, Sha256, Cipher>::decrypt(private_key, &ciphertext), Some(plaintext.clone()));
+
+        // Encryption is randomized
+        assert!(Ecies::<C, Sha256, Cipher>::encrypt(&public_key, &plaintext, &mut rng) != Some(ciphertext.clone()));
+
+        // Any change is caught by the MAC
+        for idx in [0, 1, ciphertext.len() / 2, ciphertext.len() - 1].iter() {
+            let mut bad = ciphertext.clone();
+            bad[*idx] ^= 4;
+            assert_eq!(Ecies::<C, Sha256, Cipher>::decrypt(private_key, &bad), None);
+        }
+        assert_eq!(Ecies::<C, Sha256, Cipher>::decrypt(private_key, &ciphertext[..ciphertext.len() - 1]), None);
+
+        let other_key = ecdsa::generate_key::<C, _>(&mut rng);
+        assert_eq!(Ecies::<C, Sha256, Cipher>::decrypt(other_key, &ciphertext), None);
+    }
+
+    // A recipient key from someone else might not be a valid public key
+    let identity = AffinePoint::<C>::identity();
+    assert_eq!(Ecies::<C, Sha256, Cipher>::encrypt(&identity, b"message", &mut rng), None);
+}
+
+#[test]
+fn ecies_round_trip() {
+    check_ecies::<P256>();
+    check_ecies::<Secp256k1>();
+}
+
+#[test]
+fn ffdhe_agreement() {
+    // The shared secret starts with a zero byte, which has to be kept
+    let group = dh::Group::ffdhe2048();
+    let a = byte_str_to_biguint("5f7ce3b8a2d14e6c90b3f1a7d8e2c4b6a9f0e1d2c3b4a5968778695a4b3c2d1e");
+    let b = BigUint::from_u64(0xc1004f);
+    let shared = byte_str_to_bytes("00b04aa7afc3126165d860c8321ebcd9d30fe52e5e5d80fa64f9deb4ba195748\
+                                    666aede82e51cd139d7eea8f082e0e63e557a0eab9940a8d6bd88752fa438c5a\
+                                    089ad1335684665ccd4e1f6088d8f934bc4ce7c83ccf3ad4c453d802c237311c\
+                                    731915c67f4b4b37596451ce09a602af230e50f91b0496999b6fc873a7507bcf\
+                                    cecee4e4b274f9b23dcc5d7a9f3d012607f06cf76c8e3d8dc670d7c71dfa5486\
+                                    d2cad240539abf05b23ecba962048e32297231ee8edd879486f044ff6ee0252f\
+                                    fa1a86d13c627f2e38d3d3443956bba27b7d9a025b4ed3ff8bedd135f45fc236\
+                                    561b2d742013526bc64f4d9dabcda09528432bd8d0844d108d7a3236a607cb39");
+    let public_a = dh::public_key(group, &a);
+    let public_b = dh::public_key(group, &b);
+    assert!(dh::is_valid_public_key(group, &public_a));
+    assert_eq!(dh::dh(group, &a, &public_b), Some(shared.clone()));
+    assert_eq!(dh::dh(group, &b, &public_a), Some(shared));
+
+    let mut rng = OsRng::new().unwrap();
+    let group = dh::Group::modp1536();
+    let a = dh::generate_key(group, &mut rng);
+    let b = dh::generate_key(group, &mut rng);
+    let secret = dh::dh(group, &a, &dh::public_key(group, &b)).unwrap();
+    assert_eq!(secret.len(), 192);
+    assert_eq!(dh::dh(group, &b, &dh::public_key(group, &a)), Some(secret));
+}
+
+fn check_rfc6979<C: Curve, D: Digest>(key: U512, message: &[u8], r: U512, s: U512) {
+    let mut hasher = D::new();
+    hasher.update(message);
+    let hash = hasher.finalize();
+
+    let signature = ecdsa::sign::<C, D>(key, hash.as_ref());
+    assert_eq!(signature, ecdsa::Signature::new(r, s).unwrap());
+    let public_key = ecdsa::public_key::<C>(key);
+    assert!(ecdsa::verify(&public_key, hash.as_ref(), &signature));
+    assert!(ecdsa::verify(&public_key, hash.as_ref(), &signature.normalize_s()));
+
+    let mut bad_hash = hash.as_ref().to_vec();
+    bad_hash[0] ^= 1;
+    assert!(!ecdsa::verify(&public_key, &bad_hash, &signature));
+    let other_key = ecdsa::public_key::<C>(key + U512::from_u64(1));
+    assert!(!ecdsa::verify(&other_key, hash.as_ref(), &signature));
+}
+
+// The P-256 and P-384 vectors are from RFC 6979 sections A.2.5 and A.2.6.
+// The secp256k1 ones were made the same way.
+#[test]
+fn rfc6979_ecdsa() {
+    let file = match File::open("./tests/rfc6979.data") {
+        Ok(fh) => fh,
+        Err(_) => panic!(),
+    };
+
+    let file = BufReader::new(file);
+    for line in file.lines() {
+        let line = match line {
+            Ok(l) => l,
+            Err(_) => panic!(),
+        };
+        let v: Vec<&str> = line.split("\t").collect();
+        let key = byte_str_to_u512(v[2].trim());
+        let message = v[3].trim().as_bytes();
+        let r = byte_str_to_u512(v[4].trim());
+        let s = byte_str_to_u512(v[5].trim());
+        match (v[0], v[1]) {
+            ("p256", "sha256") => check_rfc6979::<P256, Sha256>(key, message, r, s),
+            ("p256", "sha512") => check_rfc6979::<P256, Sha512>(key, message, r, s),
+            ("p384", "sha256") => check_rfc6979::<P384, Sha256>(key, message, r, s),
+            ("p384", "sha512") => check_rfc6979::<P384, Sha512>(key, message, r, s),
+            ("secp256k1", "sha256") => check_rfc6979::<Secp256k1, Sha256>(key, message, r, s),
+            ("secp256k1", "sha512") => check_rfc6979::<Secp256k1, Sha512>(key, message, r, s),
+            _ => panic!(),
+        }
+    }
+}
+
+#[test]
+fn ecdsa_low_s() {
+    let mut rng = OsRng::new().unwrap();
+    let mut high_s = 0;
+    for idx in 0..20u8 {
+        let key = ecdsa::generate_key::<Secp256k1, _>(&mut rng);
+        let public_key = ecdsa::public_key::<Secp256k1>(key);
+        let hash = sha256(&[idx]);
+        let signature = ecdsa::sign::<Secp256k1, Sha256>(key, &hash);
+        let low = signature.normalize_s();
+        assert!(low.is_low_s());
+        assert_eq!(ecdsa::sign_low_s::<Secp256k1, Sha256>(key, &hash), low);
+        assert_eq!(low.r(), signature.r());
+        if !signature.is_low_s() {
+            high_s += 1;
+            assert_eq!(low.s(), Secp256k1::N - signature.s());
+        }
+        assert!(ecdsa::verify(&public_key, &hash, &signature));
+        assert!(ecdsa::verify(&public_key, &hash, &low));
+    }
+    // Half of all signatures should need normalizing
+    assert!(high_s > 0);
+
+    assert!(ecdsa::Signature::<Secp256k1>::new(U512::zero(), U512::from_u64(1)).is_none());
+    assert!(ecdsa::Signature::<Secp256k1>::new(U512::from_u64(1), Secp256k1::N).is_none());
+}
+
+// The test vectors from BIP340
+#[test]
+fn bip340_vectors() {
+    let file = match File::open("./tests/bip340.csv") {
+        Ok(fh) => fh,
+        Err(_) => panic!(),
+    };
+
+    let file = BufReader::new(file);
+    let mut valid: Vec<([u8; 32], Vec<u8>, [u8; 64])> = Vec::new();
+    let mut invalid = Vec::new();
+    for line in file.lines().skip(1) {
+        let line = match line {
+            Ok(l) => l,
+            Err(_) => panic!(),
+        };
+        // The file uses upper case hex
+        let line = line.trim().to_lowercase();
+        let v: Vec<&str> = line.split(",").collect();
+        let public_key: [u8; 32] = hex_to_array(v[2]);
+        let message = byte_str_to_bytes(v[4]);
+        let signature: [u8; 64] = hex_to_array(v[5]);
+        let expected = v[6] == "true";
+
+        if !v[1].is_empty() {
+            let key = schnorr::SigningKey::from_bytes(hex_to_array(v[1])).unwrap();
+            assert_eq!(key.public_key(), public_key);
+            let aux_rand = hex_to_array(v[3]);
+            assert_eq!(key.sign(&message, &aux_rand).to_vec(), signature.to_vec());
+        }
+        assert_eq!(schnorr::verify(&public_key, &message, &signature), expected, "vector {}", v[0]);
+        if expected {
+            valid.push((public_key, message, signature));
+        } else {
+            invalid.push((public_key, message, signature));
+        }
+    }
+
+    let mut rng = OsRng::new().unwrap();
+    let public_keys: Vec<[u8; 32]> = valid.iter().map(|v| v.0).collect();
+    let messages: Vec<&[u8]> = valid.iter().map(|v| &v.1[..]).collect();
+    let signatures: Vec<[u8; 64]> = valid.iter().map(|v| v.2).collect();
+    assert!(schnorr::verify_batch(&public_keys, &messages, &signatures, &mut rng));
+    assert!(schnorr::verify_batch(&[], &[], &[], &mut rng));
+
+    // One bad signature spoils the batch
+    for bad in invalid.iter() {
+        let mut public_keys = public_keys.clone();
+        let mut messages = messages.clone();
+        let mut signatures = signatures.clone();
+        public_keys.insert(1, bad.0);
+        messages.insert(1, &bad.1);
+        signatures.insert(1, bad.2);
+        assert!(!schnorr::verify_batch(&public_keys, &messages, &signatures, &mut rng));
+    }
+}
+
+#[test]
+fn bip340_round_trip() {
+    let mut rng = OsRng::new().unwrap();
+    for idx in 0..10u8 {
+        let key = schnorr::SigningKey::generate(&mut rng);
+        let mut aux_rand = [0u8; 32];
+        rng.fill_bytes(&mut aux_rand);
+        let message = vec![idx; idx as usize];
+        let signature = key.sign(&message, &aux_rand);
+        assert!(schnorr::verify(&key.public_key(), &message, &signature));
+        assert!(!schnorr::verify(&key.public_key(), b"other", &signature));
+
+        // The normalized secret gives the same key
+        let same = schnorr::SigningKey::from_bytes(key.to_bytes()).unwrap();
+        assert_eq!(same.public_key(), key.public_key());
+    }
+    assert!(schnorr::SigningKey::from_bytes([0; 32]).is_none());
+    assert!(schnorr::SigningKey::from_bytes([0xff; 32]).is_none());
+}
+
+// Test vectors from RFC 8032 section 7.1
+#[test]
+fn rfc8032_ed25519() {
+    let vectors = [
+        ("9d61b19deffd5a60ba844af492ec2cc44449c5697b326919703bac031cae7f60",
+         "d75a980182b10ab7d54bfed3c964073a0ee172f3daa62325af021a68f707511a",
+         "",
+         "e5564300c360ac729086e2cc806e828a84877f1eb8e5d974d873e06522490155\
+          5fb8821590a33bacc61e39701cf9b46bd25bf5f0595bbe24655141438e7a100b"),
+        ("4ccd089b28ff96da9db6c346ec114e0f5b8a319f35aba624da8cf6ed4fb8a6fb",
+         "3d4017c3e843895a92b70aa74d1b7ebc9c982ccf2ec4968cc0cd55f12af4660c",
+         "72",
+         "92a009a9f0d4cab8720e820b5f642540a2b27b5416503f8fb3762223ebdb69da\
+          085ac1e43e15996e458f3613d0f11d8c387b2eaeb4302aeeb00d291612bb0c00"),
+        ("c5aa8df43f9f837bedb7442f31dcb7b166d38535076f094b85ce3a2e0b4458f7",
+         "fc51cd8e6218a1a38da47ed00230f0580816ed13ba3303ac5deb911548908025",
+         "af82",
+         "6291d657deec24024827e69c3abe01a30ce548a284743a445e3680d7db5ac3ac\
+          18ff9b538d16f290ae67f760984dc6594a7c15e9716ed28dc027beceea1ec40a"),
+        ("833fe62409237b9d62ec77587520911e9a759cec1d19755b7da901b96dca3d42",
+         "ec172b93ad5e563bf4932c70e1245034c35467ef2efd4d64ebf819683467e2bf",
+         "ddaf35a193617abacc417349ae20413112e6fa4e89a97ea20a9eeee64b55d39a\
+          2192992a274fc1a836ba3c23a3feebbd454d4423643ce80e2a9ac94fa54ca49f",
+         "dc2a4459e7369633a52b1bf277839a00201009a3efbf3ecb69bea2186c26b589\
+          09351fc9ac90b3ecfdfbc7c66431e0303dca179c138ac17ad9bef1177331a704"),
+    ];
+    for &(secret, public, message, signature) in vectors.iter() {
+        let key = ed25519::SigningKey::from_bytes(hex_to_array(secret));
+        let public: [u8; 32] = hex_to_array(public);
+        let message = byte_str_to_bytes(message);
+        let signature: [u8; 64] = hex_to_array(signature);
+        assert_eq!(key.public_key(), public);
+        assert_eq!(key.sign(&message).to_vec(), signature.to_vec());
+        assert!(ed25519::verify(&public, &message, &signature));
+
+        // Any change to the message or signature is rejected
+        let mut bad_message = message.clone();
+        bad_message.push(0);
+        assert!(!ed25519::verify(&public, &bad_message, &signature));
+        for idx in [0, 31, 32, 63].iter() {
+            let mut bad_signature = signature;
+            bad_signature[*idx] ^= 1;
+            assert!(!ed25519::verify(&public, &message, &bad_signature));
+        }
+    }
+}
+
+#[test]
+fn ed25519_round_trip() {
+    let mut rng = OsRng::new().unwrap();
+    let key = ed25519::SigningKey::generate(&mut rng);
+    let other = ed25519::SigningKey::generate(&mut rng);
+    let signature = key.sign(b"hello");
+    assert!(ed25519::verify(&key.public_key(), b"hello", &signature));
+    assert!(!ed25519::verify(&other.public_key(), b"hello", &signature));
+    assert_eq!(ed25519::SigningKey::from_bytes(key.to_bytes()).public_key(), key.public_key());
+}
+
+// Test vectors from RFC 9380 appendix K
+#[test]
+fn expand_message_vectors() {
+    let dst = b"QUUX-V01-CS02-with-expander-SHA256-128";
+    let vectors: [(&[u8], usize, &str); 4] = [
+        (b"", 0x20, "68a985b87eb6b46952128911f2a4412bbc302a9d759667f87f7a21d803f07235"),
+        (b"abc", 0x20, "d8ccab23b5985ccea865c6c97b6e5b8350e794e603b4b97902f53a8a0d605615"),
+        (b"abcdef0123456789", 0x20, "eff31487c770a893cfb36f912fbfcbff40d5661771ca4b2cb4eafe524333f5c1"),
+        (b"", 0x80, "af84c27ccfd45d41914fdff5df25293e221afc53d8ad2ac06d5e3e29485dadbe\
+                     e0d121587713a3e0dd4d5e69e93eb7cd4f5df4cd103e188cf60cb02edc3edf18\
+                     eda8576c412b18ffb658e3dd6ec849469b979d444cf7b26911a08e63cf31f9dc\
+                     c541708d3491184472c2c29bb749d4286b004ceb5ee6b9a7fa5b646c993f0ced"),
+    ];
+    for &(msg, len, ans) in vectors.iter() {
+        assert_eq!(expand_message_xmd::<Sha256>(msg, dst, len), byte_str_to_bytes(ans));
+    }
+
+    assert_eq!(expand_message_xmd::<Sha512>(b"", b"QUUX-V01-CS02-with-expander-SHA512-256", 0x20),
+               byte_str_to_bytes("6b9a7312411d92f921c6f68ca0b6380730a1a4d982c507211a90964c394179ba"));
+    assert_eq!(expand_message_xof(b"", b"QUUX-V01-CS02-with-expander-SHAKE256", 0x20, 256),
+               byte_str_to_bytes("2ffc05c48ed32b95d72e807f6eab9f7530dd1c2f013914c8fed38c5ccc15ad76"));
+
+    // A DST over 255 bytes stands for H("H2C-OVERSIZE-DST-" || DST), and
+    // one of exactly 255 is used as it is
+    let mut long_dst = b"QUUX-V01-CS02-with-expander-SHA256-128-long-DST-".to_vec();
+    long_dst.resize(256, b'1');
+    let mut prefixed = b"H2C-OVERSIZE-DST-".to_vec();
+    prefixed.extend_from_slice(&long_dst);
+    assert_eq!(expand_message_xmd::<Sha256>(b"abc", &long_dst, 0x20),
+               expand_message_xmd::<Sha256>(b"abc", &sha256(&prefixed), 0x20));
+    let mut hashed_dst = [0u8; 56];
+    shake256(&prefixed, &mut hashed_dst);
+    assert_eq!(expand_message_xof(b"abc", &long_dst, 0x20, 224),
+               expand_message_xof(b"abc", &hashed_dst, 0x20, 224));
+    assert!(expand_message_xof(b"abc", &long_dst, 0x20, 256) !=
+            expand_message_xof(b"abc", &long_dst, 0x20, 224));
+    prefixed.pop();
+    assert!(expand_message_xmd::<Sha256>(b"abc", &long_dst[..255], 0x20) !=
+            expand_message_xmd::<Sha256>(b"abc", &sha256(&prefixed), 0x20));
+}
+
+fn check_hash_to_curve<C: hash_to_curve::MapToCurve, E: hash_to_curve::Expander>(
+    dst: &[u8], vectors: &[(&[u8], &str, &str)], random_oracle: bool) {
+    for &(msg, x, y) in vectors.iter() {
+        let point = if random_oracle {
+            hash_to_curve::hash_to_curve::<C, E>(msg, dst)
+        } else {
+            hash_to_curve::encode_to_curve::<C, E>(msg, dst)
+        };
+        assert_eq!(point.coordinates(), Some((byte_str_to_u512(x), byte_str_to_u512(y))));
+    }
+}
+
+// Test vectors from RFC 9380 appendix J
+#[test]
+fn rfc9380_weierstrass() {
+    let dst = b"QUUX-V01-CS02-with-P256_XMD:SHA-256_SSWU_RO_";
+    let u = hash_to_curve::hash_to_field::<Xmd<Sha256>>(b"", dst, P256::P, 128, 2);
+    assert_eq!(u, vec![byte_str_to_u512("ad5342c66a6dd0ff080df1da0ea1c04b96e0330dd89406465eeba11582515009"),
+                       byte_str_to_u512("8c0f1d43204bd6f6ea70ae8013070a1518b43873bcd850aafa0a9e220e2eea5a")]);
+    check_hash_to_curve::<P256, Xmd<Sha256>>(dst, &[
+        (b"", "2c15230b26dbc6fc9a37051158c95b79656e17a1a920b11394ca91c44247d3e4",
+         "8a7a74985cc5c776cdfe4b1f19884970453912e9d31528c060be9ab5c43e8415"),
+        (b"abc", "0bb8b87485551aa43ed54f009230450b492fead5f1cc91658775dac4a3388a0f",
+         "5c41b3d0731a27a7b14bc0bf0ccded2d8751f83493404c84a88e71ffd424212e"),
+    ], true);
+    check_hash_to_curve::<P256, Xmd<Sha256>>(b"QUUX-V01-CS02-with-P256_XMD:SHA-256_SSWU_NU_", &[
+        (b"", "f871caad25ea3b59c16cf87c1894902f7e7b2c822c3d3f73596c5ace8ddd14d1",
+         "87b9ae23335bee057b99bac1e68588b18b5691af476234b8971bc4f011ddc99b"),
+    ], false);
+    check_hash_to_curve::<Secp256k1, Xmd<Sha256>>(b"QUUX-V01-CS02-with-secp256k1_XMD:SHA-256_SSWU_RO_", &[
+        (b"", "c1cae290e291aee617ebaef1be6d73861479c48b841eaba9b7b5852ddfeb1346",
+         "64fa678e07ae116126f08b022a94af6de15985c996c3a91b64c406a960e51067"),
+        (b"abc", "3377e01eab42db296b512293120c6cee72b6ecf9f9205760bd9ff11fb3cb2c4b",
+         "7f95890f33efebd1044d382a01b1bee0900fb6116f94688d487c6c7b9c8371f6"),
+    ], true);
+    check_hash_to_curve::<P384, Xmd<Sha384>>(b"QUUX-V01-CS02-with-P384_XMD:SHA-384_SSWU_RO_", &[
+        (b"", "eb9fe1b4f4e14e7140803c1d99d0a93cd823d2b024040f9c067a8eca1f5a2eeac9ad604973527a356f3fa3aeff0e4d83",
+         "0c21708cff382b7f4643c07b105c2eaec2cead93a917d825601e63c8f21f6abd9abc22c93c2bed6f235954b25048bb1a"),
+    ], true);
+}
+
+// Test vectors from RFC 9380 appendix J
+#[test]
+fn rfc9380_montgomery() {
+    let dst = b"QUUX-V01-CS02-with-curve25519_XMD:SHA-512_ELL2_RO_";
+    let vectors: [(&[u8], &str, &str); 2] = [
+        (b"", "2de3780abb67e861289f5749d16d3e217ffa722192d16bbd9d1bfb9d112b98c0",
+         "3b5dc2a498941a1033d176567d457845637554a2fe7a3507d21abd1c1bd6e878"),
+        (b"abc", "2b4419f1f2d48f5872de692b0aca72cc7b0a60915dd70bde432e826b6abc526d",
+         "1b8235f255a268f0a6fa8763e97eb3d22d149343d495da1160eff9703f2d07dd"),
+    ];
+    for &(msg, u, v) in vectors.iter() {
+        assert_eq!(hash_to_curve::hash_to_curve25519::<Xmd<Sha512>>(msg, dst),
+                   Some((byte_str_to_u512(u), byte_str_to_u512(v))));
+    }
+
+    let dst = b"QUUX-V01-CS02-with-curve448_XOF:SHAKE256_ELL2_RO_";
+    let u = "5ea5ff623d27c75e73717514134e73e419f831a875ca9e82915fdfc7069d0a9f8b532cfb32b1d8dd04ddeedbe3fa1d0d681c01e825d6a9ea";
+    let v = "afadd8de789f8f8e3516efbbe313a7eba364c939ecba00dabf4ced5c563b18e70a284c17d8f46b564c4e6ce11784a3825d941116622128c1";
+    assert_eq!(hash_to_curve::hash_to_curve448::<Shake256Xof>(b"", dst),
+               Some((byte_str_to_u512(u), byte_str_to_u512(v))));
+}
+
+// Test vectors from RFC 9496 appendix A.1: B, 2B, ..., 15B
+#[test]
+fn ristretto255_multiples() {
+    let multiples = [
+        "0000000000000000000000000000000000000000000000000000000000000000",
+        "e2f2ae0a6abc4e71a884a961c500515f58e30b6aa582dd8db6a65945e08d2d76",
+        "6a493210f7499cd17fecb510ae0cea23a110e8d5b901f8acadd3095c73a3b919",
+        "94741f5d5d52755ece4f23f044ee27d5d1ea1e2bd196b462166b16152a9d0259",
+        "da80862773358b466ffadfe0b3293ab3d9fd53c5ea6c955358f568322daf6a57",
+        "e882b131016b52c1d3337080187cf768423efccbb517bb495ab812c4160ff44e",
+        "f64746d3c92b13050ed8d80236a7f0007c3b3f962f5ba793d19a601ebb1df403",
+        "44f53520926ec81fbd5a387845beb7df85a96a24ece18738bdcfa6a7822a176d",
+        "903293d8f2287ebe10e2374dc1a53e0bc887e592699f02d077d5263cdd55601c",
+        "02622ace8f7303a31cafc63f8fc48fdc16e1c8c8d234b2f0d6685282a9076031",
+        "20706fd788b2720a1ed2a5dad4952b01f413bcf0e7564de8cdc816689e2db95f",
+        "bce83f8ba5dd2fa572864c24ba1810f9522bc6004afe95877ac73241cafdab42",
+        "e4549ee16b9aa03099ca208c67adafcafa4c3f3e4e5303de6026e3ca8ff84460",
+        "aa52e000df2e16f55fb1032fc33bc42742dad6bd5a8fc0be0167436c5948501f",
+        "46376b80f409b29dc2b5f6f0c52591990896e5716f41477cd30085ab7f10301e",
+        "e0c418f7c8d9c4cdd7395b93ea124f3ad99021bb681dfc3302a9d99a2e53e64e",
+    ];
+    let g = RistrettoPoint::generator();
+    let mut point = RistrettoPoint::identity();
+    for (idx, &encoded) in multiples.iter().enumerate() {
+        let encoded: [u8; 32] = hex_to_array(encoded);
+        assert_eq!(point.encode(), encoded);
+        assert_eq!(g.mul(U512::from_u64(idx as u64)).encode(), encoded);
+        assert_eq!(RistrettoPoint::decode(&encoded), Some(point));
+        point = point + g;
+    }
+    assert_eq!(g.mul(ristretto255::ORDER - U512::from_u64(1)), -g);
+}
+
+// Test vectors from RFC 9496 appendix A.2
+#[test]
+fn ristretto255_bad_encodings() {
+    let encodings = [
+        // Non-canonical field encodings
+        "00ffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff",
+        "ffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff7f",
+        "f3ffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff7f",
+        "edffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff7f",
+        // Negative field elements
+        "0100000000000000000000000000000000000000000000000000000000000000",
+        "01ffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff7f",
+        "ed57ffd8c914fb201471d1c3d245ce3c746fcbe63a3679d51b6a516ebebe0e20",
+        "c34c4e1826e5d403b78e246e88aa051c36ccf0aafebffe137d148a2bf9104562",
+        "c940e5a4404157cfb1628b108db051a8d439e1a421394ec4ebccb9ec92a8ac78",
+        "47cfc5497c53dc8e61c91d17fd626ffb1c49e2bca94eed052281b510b1117a24",
+        "f1c6165d33367351b0da8f6e4511010c68174a03b6581212c71c0e1d026c3c72",
+        "87260f7a2f12495118360f02c26a470f450dadf34a413d21042b43b9d93e1309",
+        // Non-square x^2
+        "26948d35ca62e643e26a83177332e6b6afeb9d08e4268b650f1f5bbd8d81d371",
+        "4eac077a713c57b4f4397629a4145982c661f48044dd3f96427d40b147d9742f",
+        "de6a7b00deadc788eb6b6c8d20c0ae96c2f2019078fa604fee5b87d6e989ad7b",
+        "bcab477be20861e01e4a0e295284146a510150d9817763caf1a6f4b422d67042",
+        "2a292df7e32cababbd9de088d1d1abec9fc0440f637ed2fba145094dc14bea08",
+        "f4a9e534fc0d216c44b218fa0c42d99635a0127ee2e53c712f70609649fdff22",
+        "8268436f8c4126196cf64b3c7ddbda90746a378625f9813dd9b8457077256731",
+        "2810e5cbc2cc4d4eece54f61c6f69758e289aa7ab440b3cbeaa21995c2f4232b",
+        // Negative x * y
+        "3eb858e78f5a7254d8c9731174a94f76755fd3941c0ac93735c07ba14579630e",
+        "a45fdc55c76448c049a1ab33f17023edfb2be3581e9c7aade8a6125215e04220",
+        "d483fe813c6ba647ebbfd3ec41adca1c6130c2beeee9d9bf065c8d151c5f396e",
+        "8a2e1d30050198c65a54483123960ccc38aef6848e1ec8f5f780e8523769ba32",
+        "32888462f8b486c68ad7dd9610be5192bbeaf3b443951ac1a8118419d9fa097b",
+        "227142501b9d4355ccba290404bde41575b037693cef1f438c47f8fbf35d1165",
+        "5c37cc491da847cfeb9281d407efc41e15144c876e0170b499a96a22ed31e01e",
+        "445425117cb8c90edcbc7c1cc0e74f747f2c1efa5630a967c64f287792a48a4b",
+        // s = -1, which gives y = 0
+        "ecffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff7f",
+    ];
+    for &encoded in encodings.iter() {
+        assert!(RistrettoPoint::decode(&hex_to_array(encoded)).is_none());
+    }
+}
+
+// Test vectors from RFC 9496 appendix A.3, where each input is the SHA-512
+// of a string
+#[test]
+fn ristretto255_from_uniform_bytes() {
+    let vectors = [
+        ("Ristretto is traditionally a short shot of espresso coffee",
+         "3066f82a1a747d45120d1740f14358531a8f04bbffe6a819f86dfe50f44a0a46"),
+        ("made with the normal amount of ground coffee but extracted with",
+         "f26e5b6f7d362d2d2a94c5d0e7602cb4773c95a2e5c31a64f133189fa76ed61b"),
+        ("about half the amount of water in the same amount of time",
+         "006ccd2a9e6867e6a2c5cea83d3302cc9de128dd2a9a57dd8ee7b9d7ffe02826"),
+        ("by using a finer grind.",
+         "f8f0c87cf237953c5890aec3998169005dae3eca1fbb04548c635953c817f92a"),
+        ("This produces a concentrated shot of coffee per volume.",
+         "ae81e7dedf20a497e10c304a765c1767a42d6e06029758d2d7e8ef7cc4c41179"),
+        ("Just pulling a normal shot short will produce a weaker shot",
+         "e2705652ff9f5e44d3e841bf1c251cf7dddb77d140870d1ab2ed64f1a9ce8628"),
+        ("and is not a Ristretto as some believe.",
+         "80bd07262511cdde4863f8a7434cef696750681cb9510eea557088f76d9e5065"),
+    ];
+    for &(label, encoded) in vectors.iter() {
+        let encoded: [u8; 32] = hex_to_array(encoded);
+        let point = RistrettoPoint::from_uniform_bytes(&sha512(label.as_bytes()));
+        assert_eq!(point.encode(), encoded);
+        assert_eq!(RistrettoPoint::decode(&encoded), Some(point));
+    }
+}
+
+#[test]
+fn shake256_vectors() {
+    let mut out = [0u8; 64];
+    shake256(b"", &mut out);
+    assert_eq!(out.to_vec(),
+               byte_str_to_bytes("46b9dd2b0ba88d13233b3feb743eeb243fcd52ea62b81b82b50c27646ed5762f\
+                                  d75dc4ddd8c0f200cb05019d67b592f6fc821c49479ab48640292eacb3b7c4be"));
+
+    // Longer than the 136 byte rate, both in and out
+    let mut out = [0u8; 300];
+    shake256(&[0xa3; 200], &mut out);
+    assert_eq!(out[..32].to_vec(),
+               byte_str_to_bytes("cd8a920ed141aa0407a22d59288652e9d9f1a7ee0c1e7c1ca699424da84a904d"));
+    assert_eq!(out[268..].to_vec(),
+               byte_str_to_bytes("a5e4fa0514ae974d8c2648513b5db494cea847156d277ad0e141c24c7839064c"));
+}
+
+// (secret key, public key, message, context, signature)
+fn check_ed448(vectors: &[(&str, &str, &str, &str, &str)], prehashed: bool) {
+    for &(secret, public, message, context, signature) in vectors.iter() {
+        let key = ed448::SigningKey::from_bytes(hex_to_array(secret));
+        let public: [u8; 57] = hex_to_array(public);
+        let message = byte_str_to_bytes(message);
+        let context = byte_str_to_bytes(context);
+        let signature: [u8; 114] = hex_to_array(signature);
+        assert_eq!(key.public_key(), public);
+
+        let verify = |message: &[u8], context: &[u8], signature: &[u8; 114]| if prehashed {
+            ed448::verify_prehashed(&public, message, context, signature)
+        } else {
+            ed448::verify(&public, message, context, signature)
+        };
+        let ours = if prehashed {
+            key.sign_prehashed(&message, &context)
+        } else {
+            key.sign(&message, &context)
+        };
+        assert_eq!(ours, signature);
+        assert!(verify(&message, &context, &signature));
+
+        // The signature doesn't carry over to another context or mode
+        assert!(!verify(&message, b"bar", &signature));
+        if prehashed {
+            assert!(!ed448::verify(&public, &message, &context, &signature));
+        } else {
+            assert!(!ed448::verify_prehashed(&public, &message, &context, &signature));
+        }
+        let mut bad_signature = signature;
+        bad_signature[60] ^= 1;
+        assert!(!verify(&message, &context, &bad_signature));
+    }
+}
+
+// Test vectors from RFC 8032 section 7.4
+#[test]
+fn rfc8032_ed448() {
+    let vectors = [
+        ("6c82a562cb808d10d632be89c8513ebf6c929f34ddfa8c9f63c9960ef6e348a3\
+          528c8a3fcc2f044e39a3fc5b94492f8f032e7549a20098f95b",
+         "5fd7449b59b461fd2ce787ec616ad46a1da1342485a70e1f8a0ea75d80e96778\
+          edf124769b46c7061bd6783df1e50f6cd1fa1abeafe8256180",
+         "",
+         "",
+         "533a37f6bbe457251f023c0d88f976ae2dfb504a843e34d2074fd823d41a591f\
+          2b233f034f628281f2fd7a22ddd47d7828c59bd0a21bfd3980ff0d2028d4b18a\
+          9df63e006c5d1c2d345b925d8dc00b4104852db99ac5c7cdda8530a113a0f4db\
+          b61149f05a7363268c71d95808ff2e652600"),
+        ("c4eab05d357007c632f3dbb48489924d552b08fe0c353a0d4a1f00acda2c463a\
+          fbea67c5e8d2877c5e3bc397a659949ef8021e954e0a12274e",
+         "43ba28f430cdff456ae531545f7ecd0ac834a55d9358c0372bfa0c6c6798c086\
+          6aea01eb00742802b8438ea4cb82169c235160627b4c3a9480",
+         "03",
+         "",
+         "26b8f91727bd62897af15e41eb43c377efb9c610d48f2335cb0bd0087810f435\
+          2541b143c4b981b7e18f62de8ccdf633fc1bf037ab7cd779805e0dbcc0aae1cb\
+          cee1afb2e027df36bc04dcecbf154336c19f0af7e0a6472905e799f1953d2a0f\
+          f3348ab21aa4adafd1d234441cf807c03a00"),
+        ("c4eab05d357007c632f3dbb48489924d552b08fe0c353a0d4a1f00acda2c463a\
+          fbea67c5e8d2877c5e3bc397a659949ef8021e954e0a12274e",
+         "43ba28f430cdff456ae531545f7ecd0ac834a55d9358c0372bfa0c6c6798c086\
+          6aea01eb00742802b8438ea4cb82169c235160627b4c3a9480",
+         "03",
+         "666f6f",
+         "d4f8f6131770dd46f40867d6fd5d5055de43541f8c5e35abbcd001b32a89f7d2\
+          151f7647f11d8ca2ae279fb842d607217fce6e042f6815ea000c85741de5c8da\
+          1144a6a1aba7f96de42505d7a7298524fda538fccbbb754f578c1cad10d54d0d\
+          5428407e85dcbc98a49155c13764e66c3c00"),
+        ("cd23d24f714274e744343237b93290f511f6425f98e64459ff203e8985083ffd\
+          f60500553abc0e05cd02184bdb89c4ccd67e187951267eb328",
+         "dcea9e78f35a1bf3499a831b10b86c90aac01cd84b67a0109b55a36e9328b1e3\
+          65fce161d71ce7131a543ea4cb5f7e9f1d8b00696447001400",
+         "0c3e544074ec63b0265e0c",
+         "",
+         "1f0a8888ce25e8d458a21130879b840a9089d999aaba039eaf3e3afa090a09d3\
+          89dba82c4ff2ae8ac5cdfb7c55e94d5d961a29fe0109941e00b8dbdeea6d3b05\
+          1068df7254c0cdc129cbe62db2dc957dbb47b51fd3f213fb8698f064774250a5\
+          028961c9bf8ffd973fe5d5c206492b140e00"),
+    ];
+    check_ed448(&vectors, false);
+}
+
+// Test vectors from RFC 8032 section 7.5
+#[test]
+fn rfc8032_ed448ph() {
+    let vectors = [
+        ("833fe62409237b9d62ec77587520911e9a759cec1d19755b7da901b96dca3d42\
+          ef7822e0d5104127dc05d6dbefde69e3ab2cec7c867c6e2c49",
+         "259b71c19f83ef77a7abd26524cbdb3161b590a48f7d17de3ee0ba9c52beb743\
+          c09428a131d6b1b57303d90d8132c276d5ed3d5d01c0f53880",
+         "616263",
+         "",
+         "822f6901f7480f3d5f562c592994d9693602875614483256505600bbc281ae38\
+          1f54d6bce2ea911574932f52a4e6cadd78769375ec3ffd1b801a0d9b3f4030cd\
+          433964b6457ea39476511214f97469b57dd32dbc560a9a94d00bff07620464a3\
+          ad203df7dc7ce360c3cd3696d9d9fab90f00"),
+        ("833fe62409237b9d62ec77587520911e9a759cec1d19755b7da901b96dca3d42\
+          ef7822e0d5104127dc05d6dbefde69e3ab2cec7c867c6e2c49",
+         "259b71c19f83ef77a7abd26524cbdb3161b590a48f7d17de3ee0ba9c52beb743\
+          c09428a131d6b1b57303d90d8132c276d5ed3d5d01c0f53880",
+         "616263",
+         "666f6f",
+         "c32299d46ec8ff02b54540982814dce9a05812f81962b649d528095916a2aa48\
+          1065b1580423ef927ecf0af5888f90da0f6a9a85ad5dc3f280d91224ba9911a3\
+          653d00e484e2ce232521481c8658df304bb7745a73514cdb9bf3e15784ab7128\
+          4f8d0704a608c54a6b62d97beb511d132100"),
+    ];
+    check_ed448(&vectors, true);
+}
+
+// Parameters generated with appendix A.1.1.2 and A.2.3 using SHA-256, and
+// checked with another implementation. The first signature uses RFC 6979
+// nonces, the second a random nonce.
+#[test]
+fn dsa_vectors() {
+    let p = byte_str_to_biguint("eb264ab46d43907d49fcf346881365b85def155a32b9a2cbe7a065b125db7f29\
+                                 dd0678b69bab84b930904d11f7c14035f1bfb142b635693c925c39c6d780c2eb\
+                                 c09b7a754e9543e6cb4500a04a548c31dbc91e75944ded0cc6163d6180df9386\
+                                 1548603e445dd0034e79cae3f545c9d290295c084767f87b5a8f0949fad5fa75");
+    let q = byte_str_to_u512("ba126c2ee5add81ffb8aa7a5c452877a1e309a29");
+    let g = byte_str_to_biguint("dec049bbfa71e4aa26c2026a44065069f53091e0b15b1070d170bb904d46987f\
+                                 f52fc20756609536ad44cd8b2288f8ac5aba111b5a27d185a8ac9b6b1bb74bdf\
+                                 d442c44eab021a730754e56a3e0368765e0ac979653ad2046ff22045fd69cd61\
+                                 205ceffeb9c6d1074ee4372ed38b6954dd1bab1368e14983a159b76325449ab5");
+    let seed = dsa::DomainSeed::new(byte_str_to_bytes("8074b6647b253a3dc38f710c4037c4490b5d6eed"), 146);
+    let mut rng = OsRng::new().unwrap();
+    assert!(dsa::verify_primes::<Sha256, _>(&p, q, &seed, &mut rng));
+    let wrong_counter = dsa::DomainSeed::new(seed.seed().to_vec(), seed.counter() - 1);
+    assert!(!dsa::verify_primes::<Sha256, _>(&p, q, &wrong_counter, &mut rng));
+    assert_eq!(dsa::derive_generator::<Sha256>(&p, q, seed.seed(), 1), Some(g.clone()));
+    let params = dsa::Parameters::new(p, q, g).unwrap();
+
+    let x = byte_str_to_u512("2c2f3e8a6b0d4e1f5a7c9b8d0e1f2a3b4c5d6e7f");
+    let y = byte_str_to_biguint("c1a6d44b228c2900c1e29eae10c757a33323a3b87b91c2970e16096f6ba9d26a\
+                                 4cc4a1d4cf4f6aee4160c965a2e481e57a510040a4519954a6a6bd51e2a23d22\
+                                 96f245de38378c07ebc75163f583248638a504cdb760157212603fbac6147ab8\
+                                 ea71f16934af783a41256d46ef436780f7badeaeb4fd37a2089d1e0792e0a90e");
+    assert_eq!(dsa::public_key(&params, x), y);
+
+    let signature = dsa::sign::<Sha256>(&params, x, &sha256(b"sample"));
+    assert_eq!(signature, dsa::Signature::new(byte_str_to_u512("2d32d6e9dd3d973a2468e4fc8f2515e195c8f969"),
+                                               byte_str_to_u512("b3c35ef27ec287abe9ade3128b04b3aa1af50f14")));
+    assert!(dsa::verify(&params, &y, &sha256(b"sample"), &signature));
+
+    let signature = dsa::Signature::new(byte_str_to_u512("1ba3f6a98129433986c7573b2b21a1d6082c056a"),
+                                        byte_str_to_u512("17b2960f3918b49a9f5e8fb1302a3861ade45a66"));
+    assert!(dsa::verify(&params, &y, &sha256(b"test"), &signature));
+    assert!(!dsa::verify(&params, &y, &sha256(b"sample"), &signature));
+}
+
+// The 1024 bit DSA key from RFC 6979 appendix A.2.1
+#[test]
+fn rfc6979_dsa() {
+    let p = byte_str_to_biguint("86f5ca03dcfeb225063ff830a0c769b9dd9d6153ad91d7ce27f787c43278b447\
+                                 e6533b86b18bed6e8a48b784a14c252c5be0dbf60b86d6385bd2f12fb763ed88\
+                                 73abfd3f5ba2e0a8c0a59082eac056935e529daf7c610467899c77adedfc846c\
+                                 881870b7b19b2b58f9be0521a17002e3bdd6b86685ee90b3d9a1b02b782b1779");
+    let q = byte_str_to_u512("996f967f6c8e388d9e28d01e205fba957a5698b1");
+    let g = byte_str_to_biguint("07b0f92546150b62514bb771e2a0c0ce387f03bda6c56b505209ff25fd3c133d\
+                                 89bbcd97e904e09114d9a7defdeadfc9078ea544d2e401aeecc40bb9fbbf78fd\
+                                 87995a10a1c27cb7789b594ba7efb5c4326a9fe59a070e136db77175464adca4\
+                                 17be5dce2f40d10a46a3a3943f26ab7fd9c0398ff8c76ee0a56826a8a88f1dbd");
+    let params = dsa::Parameters::new(p, q, g).unwrap();
+    let x = byte_str_to_u512("411602cb19a6ccc34494d79d98ef1e7ed5af25f7");
+    let y = byte_str_to_biguint("5df5e01ded31d0297e274e1691c192fe5868fef9e19a84776454b100cf16f653\
+                                 92195a38b90523e2542ee61871c0440cb87c322fc4b4d2ec5e1e7ec766e1be8d\
+                                 4ce935437dc11c3c8fd426338933ebfe739cb3465f4d3668c5e473508253b1e6\
+                                 82f65cbdc4fae93c2ea212390e54905a86e2223170b44eaa7da5dd9ffcfb7f3b");
+    assert_eq!(dsa::public_key(&params, x), y);
+
+    let vectors = [
+        (sha256(b"sample").to_vec(), dsa::sign::<Sha256>(&params, x, &sha256(b"sample")),
+         "81f2f5850be5bc123c43f71a3033e9384611c545", "4cdd914b65eb6c66a8aaad27299bee6b035f5e89"),
+        (sha256(b"test").to_vec(), dsa::sign::<Sha256>(&params, x, &sha256(b"test")),
+         "22518c127299b0f6fdc9872b282b9e70d0790812", "6837ec18f150d55de95b5e29be7af5d01e4fe160"),
+        (sha384(b"sample").to_vec(), dsa::sign::<Sha384>(&params, x, &sha384(b"sample")),
+         "07f2108557ee0e3921bc1774f1ca9b410b4ce65a", "54df70456c86fac10fab47c1949ab83f2c6f7595"),
+        (sha512(b"sample").to_vec(), dsa::sign::<Sha512>(&params, x, &sha512(b"sample")),
+         "16c3491f9b8c3fbbdd5e7a7b667057f0d8ee8e1b", "02c36a127a7b89edbb72e4ffbc71dabc7d4fc69c"),
+    ];
+    for (hash, signature, r, s) in vectors.iter() {
+        assert_eq!(*signature, dsa::Signature::new(byte_str_to_u512(r), byte_str_to_u512(s)));
+        assert!(dsa::verify(&params, &y, hash, signature));
+        assert!(!dsa::verify(&params, &y, &sha256(b"other"), signature));
+    }
+}
+
+#[test]
+fn rsa_vectors() {
+    let p = byte_str_to_biguint("d7de7b345fdfb9b87c1fe2b0501dd51c19474187d687d1e9d011b7cc933396fa\
+                                 e3ede52af83240e281f63104f40e2c0a0645d49167e4cc5e9b82708195383ff5");
+    let q = byte_str_to_biguint("cf7e1ac1a0911c2abec7ac7d1d24f33ea9b13a1480b3d699011bb6f5d87b2cf8\
+                                 99ec2d20f90adc96ac01f0b95d9ef5ffd1fbce54447c0cd727c51efef9ee5089");
+    let n = byte_str_to_biguint("aef73bafdbf0b5477b223264469186be30118f6c37d097a36d8adaa19f0a9909\
+                                 4b909af5cce635f7d02851ea37ba5fd444b0bc4f4428c1458e33ebfcf5fa9e60\
+                                 67d62b687cbe4974f4c7e04a3d6b068ecf8ee33993827c99dec71b7a2fbbcabc\
+                                 f5e4d5f5245f17bcb92fe7a30135d97664d9a4d5087ccac5ce6a4f9131dcca1d");
+    let key = rsa::PrivateKey::new(p, q, BigUint::from_u64(65537)).unwrap();
+    let public = key.public_key();
+    assert_eq!(*public.n(), n);
+    let mut rng = OsRng::new().unwrap();
+
+    // From pyca/cryptography: PSS with SHA-256 and a 32 byte salt
+    let signature = byte_str_to_bytes("8fc4e6aa03a2673bf1764498204fe67ea577e960d3f65d563c87219d39953a99\
+                                       9ef04cb1c534a96b370689732791665f4a1efb2b2eea9dcdbc3e28e8db7bbe89\
+                                       76dd1ae5bc1048adf88908247c036064e18270c8c5dc8c2278efd656a2f1eeb4\
+                                       8c3884f134c049600daed1bdaad85d3c10785e581037d9d72d81eae5abeee46e");
+    assert!(rsa::pss_verify::<Sha256>(public, &sha256(b"sample"), &signature, 32));
+    assert!(!rsa::pss_verify::<Sha256>(public, &sha256(b"sample"), &signature, 20));
+    assert!(!rsa::pss_verify::<Sha256>(public, &sha256(b"test"), &signature, 32));
+    let signature = rsa::pss_sign::<Sha256, _>(&key, &sha256(b"test"), 32, &mut rng).unwrap();
+    assert!(rsa::pss_verify::<Sha256>(public, &sha256(b"test"), &signature, 32));
+
+    // PKCS #1 v1.5 with SHA-384
+    let signature = byte_str_to_bytes("049e283261471ed7c4a8907d9485e58064c2f0e1993eda8f2e1848bba8f428e2\
+                                       bc631a37ac7d4628a05f5a401361454d46ff2a54e94b260b7c39e1321b68ac30\
+                                       90f454782b38b8c9aef0779ed89aa455061f49977a623deabcd6c7201b58975c\
+                                       212ce835f98a747641f5d28a569f8b1ac4ac765e1539048de0ae6298b538a71d");
+    assert!(rsa::pkcs1v15_verify::<Sha384>(public, &sha384(b"sample"), &signature));
+    assert!(!rsa::pkcs1v15_verify::<Sha384>(public, &sha384(b"test"), &signature));
+    assert!(!rsa::pkcs1v15_verify::<Sha384>(public, &sha384(b"sample"), &signature[1..]));
+
+    // OAEP with SHA-256 and the label "label"
+    let ciphertext = byte_str_to_bytes("32c1b8865967946f61db65943a1ffa7e36c8f942d99496da694ba199a7dec0cb\
+                                        4dd79f431b2b1d80d8111aa5ddb011156e09e9906f2ad1786b3bcdfd59e244dc\
+                                        1b1e435728b0340ca26a02d6401c6b9f9a4a71ce7c954ab958adc6abdcaf2a83\
+                                        3a05849e34a3a24e938edc92d97c0bfa8f00986f4bf7e4a0a160e323e528d9b9");
+    assert_eq!(rsa::oaep_decrypt::<Sha256, _>(&key, &ciphertext, b"label", &mut rng), Some(b"attack at dawn".to_vec()));
+    assert_eq!(rsa::oaep_decrypt::<Sha256, _>(&key, &ciphertext, b"", &mut rng), None);
+    assert_eq!(rsa::oaep_decrypt::<Sha384, _>(&key, &ciphertext, b"label", &mut rng), None);
+    let ciphertext = rsa::oaep_encrypt::<Sha256, _>(public, b"retreat", b"label", &mut rng).unwrap();
+    assert_eq!(rsa::oaep_decrypt::<Sha256, _>(&key, &ciphertext, b"label", &mut rng), Some(b"retreat".to_vec()));
+}